@@ -0,0 +1,226 @@
+// synthesizes the pcm wav samples of the music extension in `src/samples`, no recordings
+// are involved, so they fall under the license of this repository like the rest of it
+//
+//     cargo run --example gen_samples
+
+use std::{f64::consts::PI, fs, path::Path};
+
+const C4: f64 = 261.6255653;
+const INSTRUMENT_RATE: u32 = 11025;
+// cymbals and hi-hats need the highs
+const DRUM_RATE: u32 = 22050;
+// sustained samples loop from here to their end, see `$LOOP_START` in the runtime
+const LOOP_START: f64 = 0.4;
+// about 70 periods of middle C
+const LOOP_LEN: usize = 2950;
+
+// name, harmonics, attack, decay, a decay of 0 is sustained
+const INSTRUMENTS: [(&str, &[f64], f64, f64); 21] = [
+    ("piano", &[1.0, 0.5, 0.3, 0.15, 0.1], 0.005, 1.5),
+    ("electric-piano", &[1.0, 0.2, 0.05, 0.1], 0.005, 1.2),
+    (
+        "organ",
+        &[1.0, 0.8, 0.6, 0.0, 0.4, 0.0, 0.2, 0.2],
+        0.01,
+        0.0,
+    ),
+    ("guitar", &[1.0, 0.6, 0.4, 0.2, 0.15, 0.1], 0.005, 1.0),
+    (
+        "electric-guitar",
+        &[1.0, 0.7, 0.5, 0.4, 0.3, 0.25, 0.2],
+        0.005,
+        1.5,
+    ),
+    ("bass", &[1.0, 0.4, 0.1], 0.005, 1.5),
+    ("pizzicato", &[1.0, 0.3, 0.2, 0.1], 0.005, 0.4),
+    ("cello", &[1.0, 0.7, 0.5, 0.4, 0.3, 0.2], 0.1, 0.0),
+    ("trombone", &[1.0, 0.8, 0.6, 0.5, 0.3, 0.2], 0.05, 0.0),
+    ("clarinet", &[1.0, 0.0, 0.5, 0.0, 0.3, 0.0, 0.2], 0.03, 0.0),
+    ("saxophone", &[1.0, 0.6, 0.5, 0.4, 0.3, 0.2], 0.03, 0.0),
+    ("flute", &[1.0, 0.2, 0.05], 0.05, 0.0),
+    ("wooden-flute", &[1.0, 0.1, 0.1], 0.05, 0.0),
+    ("bassoon", &[1.0, 0.8, 0.7, 0.5, 0.3], 0.05, 0.0),
+    ("choir", &[1.0, 0.4, 0.2, 0.1], 0.2, 0.0),
+    ("vibraphone", &[1.0, 0.0, 0.0, 0.3], 0.005, 2.0),
+    ("music-box", &[1.0, 0.0, 0.2, 0.0, 0.1], 0.005, 0.8),
+    ("steel-drum", &[1.0, 0.5, 0.6, 0.2], 0.005, 0.8),
+    ("marimba", &[1.0, 0.0, 0.0, 0.2], 0.005, 0.5),
+    (
+        "synth-lead",
+        &[1.0, 0.5, 0.33, 0.25, 0.2, 0.17, 0.14],
+        0.01,
+        0.0,
+    ),
+    ("synth-pad", &[1.0, 0.3, 0.2], 0.3, 0.0),
+];
+
+// name, tone at the start and at the end, share of noise, noise band, length
+const DRUMS: [(&str, f64, f64, f64, f64, f64); 18] = [
+    ("snare-drum", 200.0, 160.0, 0.7, 3000.0, 0.2),
+    ("bass-drum", 150.0, 50.0, 0.0, 0.0, 0.4),
+    ("side-stick", 500.0, 400.0, 0.3, 2500.0, 0.06),
+    ("crash-cymbal", 0.0, 0.0, 1.0, 6000.0, 1.2),
+    ("open-hi-hat", 0.0, 0.0, 1.0, 9000.0, 0.4),
+    ("closed-hi-hat", 0.0, 0.0, 1.0, 9000.0, 0.08),
+    ("tambourine", 0.0, 0.0, 1.0, 7000.0, 0.25),
+    ("hand-clap", 0.0, 0.0, 1.0, 1500.0, 0.15),
+    ("claves", 2500.0, 2500.0, 0.0, 0.0, 0.06),
+    ("wood-block", 1000.0, 950.0, 0.1, 1000.0, 0.08),
+    ("cowbell", 560.0, 540.0, 0.05, 800.0, 0.3),
+    ("triangle", 4000.0, 4000.0, 0.0, 0.0, 1.2),
+    ("bongo", 350.0, 300.0, 0.1, 2000.0, 0.15),
+    ("conga", 220.0, 190.0, 0.1, 1500.0, 0.25),
+    ("cabasa", 0.0, 0.0, 1.0, 10000.0, 0.1),
+    ("guiro", 0.0, 0.0, 1.0, 3500.0, 0.3),
+    ("vibraslap", 0.0, 0.0, 1.0, 2500.0, 0.8),
+    ("cuica", 600.0, 300.0, 0.1, 1000.0, 0.3),
+];
+
+fn main() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/samples");
+    for (idx, (name, harmonics, attack, decay)) in INSTRUMENTS.iter().enumerate() {
+        let data = instrument(harmonics, *attack, *decay);
+        let path = dir.join(format!("instruments/{:02}-{}.wav", idx + 1, name));
+        fs::write(path, wav(INSTRUMENT_RATE, &data)).unwrap();
+    }
+    let mut noise = Noise(1);
+    for (idx, (name, tone, tone_end, share, band, length)) in DRUMS.iter().enumerate() {
+        let data = drum(&mut noise, *tone, *tone_end, *share, *band, *length);
+        let path = dir.join(format!("drums/{:02}-{}.wav", idx + 1, name));
+        fs::write(path, wav(DRUM_RATE, &data)).unwrap();
+    }
+}
+
+// middle C, the attack rises like a square root so the note is heard from the first sample
+fn instrument(harmonics: &[f64], attack: f64, decay: f64) -> Vec<f64> {
+    let rate = INSTRUMENT_RATE as f64;
+    let len = if decay == 0.0 {
+        (rate * LOOP_START) as usize + LOOP_LEN
+    } else {
+        (rate * (decay + 0.05).min(1.5)) as usize
+    };
+    let mut data: Vec<_> = (0..len)
+        .map(|i| {
+            let t = i as f64 / rate;
+            let tone: f64 = harmonics
+                .iter()
+                .enumerate()
+                .map(|(k, amp)| amp * (2.0 * PI * C4 * (k + 1) as f64 * t).sin())
+                .sum();
+            let mut env = (t / attack).min(1.0).sqrt();
+            if decay != 0.0 {
+                env *= (-(t - attack).max(0.0) / (decay / 3.0)).exp();
+            }
+            tone * env
+        })
+        .collect();
+    if decay != 0.0 {
+        fade_out(&mut data, rate, 0.01);
+    }
+    data
+}
+
+// a falling tone mixed with band-passed noise
+fn drum(
+    noise: &mut Noise,
+    tone: f64,
+    tone_end: f64,
+    share: f64,
+    band: f64,
+    length: f64,
+) -> Vec<f64> {
+    let rate = DRUM_RATE as f64;
+    let len = (rate * length) as usize;
+    let env: Vec<_> = (0..len)
+        .map(|i| {
+            let t = i as f64 / rate;
+            (t / 0.002).min(1.0) * (-t / (length / 3.0)).exp()
+        })
+        .collect();
+    let mut data = vec![0.0; len];
+    if share < 1.0 {
+        let mut phase = 0.0;
+        for (i, sample) in data.iter_mut().enumerate() {
+            let freq = tone * (tone_end / tone).powf(i as f64 / len as f64);
+            phase += 2.0 * PI * freq / rate;
+            *sample += (1.0 - share) * phase.sin() * env[i];
+        }
+    }
+    if share > 0.0 {
+        let white: Vec<_> = (0..len).map(|_| noise.sample()).collect();
+        let band = bandpass(&white, band, rate);
+        let peak = band.iter().fold(0.0f64, |peak, x| peak.max(x.abs()));
+        for (i, sample) in data.iter_mut().enumerate() {
+            *sample += share * band[i] / peak * env[i];
+        }
+    }
+    fade_out(&mut data, rate, 0.005);
+    data
+}
+
+fn fade_out(data: &mut [f64], rate: f64, secs: f64) {
+    let len = (rate * secs) as usize;
+    let start = data.len() - len;
+    for (i, sample) in data[start..].iter_mut().rev().enumerate() {
+        *sample *= i as f64 / len as f64;
+    }
+}
+
+// a biquad band-pass filter with a q of 1
+fn bandpass(input: &[f64], freq: f64, rate: f64) -> Vec<f64> {
+    let w0 = 2.0 * PI * freq / rate;
+    let alpha = w0.sin() / 2.0;
+    let (b0, b2) = (alpha, -alpha);
+    let (a0, a1, a2) = (1.0 + alpha, -2.0 * w0.cos(), 1.0 - alpha);
+    let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+    input
+        .iter()
+        .map(|&x| {
+            let y = (b0 * x + b2 * x2 - a1 * y1 - a2 * y2) / a0;
+            (x2, x1, y2, y1) = (x1, x, y1, y);
+            y
+        })
+        .collect()
+}
+
+// xorshift, so the drums come out the same on every run
+struct Noise(u32);
+
+impl Noise {
+    // uniform in [-1, 1]
+    fn sample(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f64 / u32::MAX as f64 * 2.0 - 1.0
+    }
+}
+
+// 8-bit mono, normalized to 90% of the full range
+fn wav(rate: u32, data: &[f64]) -> Vec<u8> {
+    let peak = data.iter().fold(0.0f64, |peak, x| peak.max(x.abs()));
+    let peak = if peak == 0.0 { 1.0 } else { peak };
+    let len = data.len() as u32;
+    let mut out = Vec::with_capacity(44 + data.len());
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + len + (len & 1)).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    // pcm, 1 channel, frames per second, bytes per second and per frame, bits per sample
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&rate.to_le_bytes());
+    out.extend_from_slice(&rate.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&8u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend(
+        data.iter()
+            .map(|x| (128.0 + 127.0 * 0.9 * x / peak).round().clamp(0.0, 255.0) as u8),
+    );
+    if len & 1 == 1 {
+        out.push(0);
+    }
+    out
+}
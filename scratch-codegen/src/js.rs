@@ -0,0 +1,1213 @@
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+use scratch_loader::{
+    cast,
+    sb3::{RotationStyle, ScratchValue},
+};
+use scratch_parser::ast::{
+    BlockItem, BlockStack, BoolExpr, Costume, CostumeMenu, CurrentMenu, Expr, FallbackBlock,
+    FallbackInput, GraphicEffect, GreaterThanMenu, List, MathOp, NumberName, ParsedScratchProject,
+    ResourcePath, SensingOfProperty, Sound, SoundEffect, Stmt, StopOption, Variable,
+};
+
+const RUNTIME: &str = include_str!("runtime.js");
+
+// pcm wav samples of the music extension in the order of its menus, synthesized by
+// `examples/gen_samples.rs`, instruments are at middle C and pitched by the runtime
+const INSTRUMENT_SAMPLES: [&[u8]; 21] = [
+    include_bytes!("samples/instruments/01-piano.wav"),
+    include_bytes!("samples/instruments/02-electric-piano.wav"),
+    include_bytes!("samples/instruments/03-organ.wav"),
+    include_bytes!("samples/instruments/04-guitar.wav"),
+    include_bytes!("samples/instruments/05-electric-guitar.wav"),
+    include_bytes!("samples/instruments/06-bass.wav"),
+    include_bytes!("samples/instruments/07-pizzicato.wav"),
+    include_bytes!("samples/instruments/08-cello.wav"),
+    include_bytes!("samples/instruments/09-trombone.wav"),
+    include_bytes!("samples/instruments/10-clarinet.wav"),
+    include_bytes!("samples/instruments/11-saxophone.wav"),
+    include_bytes!("samples/instruments/12-flute.wav"),
+    include_bytes!("samples/instruments/13-wooden-flute.wav"),
+    include_bytes!("samples/instruments/14-bassoon.wav"),
+    include_bytes!("samples/instruments/15-choir.wav"),
+    include_bytes!("samples/instruments/16-vibraphone.wav"),
+    include_bytes!("samples/instruments/17-music-box.wav"),
+    include_bytes!("samples/instruments/18-steel-drum.wav"),
+    include_bytes!("samples/instruments/19-marimba.wav"),
+    include_bytes!("samples/instruments/20-synth-lead.wav"),
+    include_bytes!("samples/instruments/21-synth-pad.wav"),
+];
+
+const DRUM_SAMPLES: [&[u8]; 18] = [
+    include_bytes!("samples/drums/01-snare-drum.wav"),
+    include_bytes!("samples/drums/02-bass-drum.wav"),
+    include_bytes!("samples/drums/03-side-stick.wav"),
+    include_bytes!("samples/drums/04-crash-cymbal.wav"),
+    include_bytes!("samples/drums/05-open-hi-hat.wav"),
+    include_bytes!("samples/drums/06-closed-hi-hat.wav"),
+    include_bytes!("samples/drums/07-tambourine.wav"),
+    include_bytes!("samples/drums/08-hand-clap.wav"),
+    include_bytes!("samples/drums/09-claves.wav"),
+    include_bytes!("samples/drums/10-wood-block.wav"),
+    include_bytes!("samples/drums/11-cowbell.wav"),
+    include_bytes!("samples/drums/12-triangle.wav"),
+    include_bytes!("samples/drums/13-bongo.wav"),
+    include_bytes!("samples/drums/14-conga.wav"),
+    include_bytes!("samples/drums/15-cabasa.wav"),
+    include_bytes!("samples/drums/16-guiro.wav"),
+    include_bytes!("samples/drums/17-vibraslap.wav"),
+    include_bytes!("samples/drums/18-cuica.wav"),
+];
+
+pub struct JsCodegen<'a> {
+    proj: &'a ParsedScratchProject,
+    // variables and lists owned by the stage, everything else lives on `$t`
+    globals: HashSet<ResourcePath>,
+    out: String,
+    indent: usize,
+    // generating the body of a custom block, which runs in the thread of its caller
+    in_def: bool,
+    // generating the body of a custom block that runs without screen refresh
+    warp: bool,
+    // shared with the generators of fallback substacks
+    samples: Rc<RefCell<UsedSamples>>,
+}
+
+// indices of the music samples the generated code may play
+#[derive(Default)]
+struct UsedSamples {
+    instruments: HashSet<usize>,
+    drums: HashSet<usize>,
+}
+
+impl<'a> JsCodegen<'a> {
+    pub fn new(proj: &'a ParsedScratchProject) -> Self {
+        let globals = proj
+            .background
+            .variables
+            .values()
+            .map(|v| v.0.clone())
+            .chain(proj.background.lists.values().map(|l| l.0.clone()))
+            .collect();
+        Self {
+            proj,
+            globals,
+            out: String::new(),
+            indent: 0,
+            in_def: false,
+            warp: false,
+            samples: Rc::default(),
+        }
+    }
+
+    pub fn generate(mut self) -> String {
+        self.out.push_str(RUNTIME);
+        self.line("");
+        self.line("const $runtime = new $Runtime();");
+        self.gen_broadcasts();
+        self.gen_stage();
+        for idx in 0..self.proj.sprites.len() {
+            self.gen_sprite(idx);
+        }
+        // after the scripts, which tell the samples they play
+        if self.proj.extensions.iter().any(|e| e == "music") {
+            self.gen_music_samples();
+        }
+        self.line("export default $runtime;");
+        self.out
+    }
+
+    fn line(&mut self, code: &str) {
+        if !code.is_empty() {
+            for _ in 0..self.indent {
+                self.out.push_str("  ");
+            }
+            self.out.push_str(code);
+        }
+        self.out.push('\n');
+    }
+
+    fn gen_music_samples(&mut self) {
+        self.line("$runtime.musicSamples = {");
+        self.indent += 1;
+        let UsedSamples { instruments, drums } = self.samples.take();
+        for (name, samples, used) in [
+            ("instruments", &INSTRUMENT_SAMPLES[..], instruments),
+            ("drums", &DRUM_SAMPLES[..], drums),
+        ] {
+            self.line(&format!("{}: [", name));
+            self.indent += 1;
+            for (idx, sample) in samples.iter().enumerate() {
+                match used.contains(&idx) {
+                    true => self.line(&format!("\"{}\",", base64(sample))),
+                    false => self.line("null,"),
+                }
+            }
+            self.indent -= 1;
+            self.line("],");
+        }
+        self.indent -= 1;
+        self.line("};");
+    }
+
+    fn gen_broadcasts(&mut self) {
+        let proj = self.proj;
+        let mut broadcasts: Vec<_> = proj.background.broadcasts.values().collect();
+        broadcasts.sort_by_key(|b| b.id());
+        for b in broadcasts {
+            let code = format!("const {} = {};", b.js_name(), js_str(b.name()));
+            self.line(&code);
+        }
+    }
+
+    fn gen_stage(&mut self) {
+        let proj = self.proj;
+        let stage = &proj.background;
+        self.line("const $stage = $runtime.addTarget(new $Target($runtime, \"Stage\", true));");
+        let code = format!(
+            "$stage.init({{ costume: {}, volume: {}, tempo: {} }});",
+            stage.current_costume,
+            js_num(stage.volume),
+            js_num(stage.tempo)
+        );
+        self.line(&code);
+        self.gen_data("$stage", &stage.variables, &stage.lists);
+        self.gen_assets("$stage", &stage.costumes, &stage.sounds);
+        for item in &stage.blocks {
+            self.gen_item("$stage", item);
+        }
+    }
+
+    fn gen_sprite(&mut self, idx: usize) {
+        let proj = self.proj;
+        let sprite = &proj.sprites[idx];
+        let target = format!("$sprite{}", idx);
+        let code = format!(
+            "const {} = $runtime.addTarget(new $Target($runtime, {}, false));",
+            target,
+            js_str(&sprite.name)
+        );
+        self.line(&code);
+        let code = format!(
+            "{}.init({{ x: {}, y: {}, direction: {}, rotationStyle: {}, size: {}, visible: {}, draggable: {}, costume: {}, volume: {} }});",
+            target,
+            js_num(sprite.position.0),
+            js_num(sprite.position.1),
+            js_num(sprite.direction),
+            js_str(rotation_style(sprite.rotation_style)),
+            js_num(sprite.size),
+            sprite.visible,
+            sprite.draggable,
+            sprite.current_costume,
+            js_num(sprite.volume)
+        );
+        self.line(&code);
+        self.gen_data(&target, &sprite.variables, &sprite.lists);
+        self.gen_assets(&target, &sprite.costumes, &sprite.sounds);
+        for item in &sprite.blocks {
+            self.gen_item(&target, item);
+        }
+    }
+
+    fn gen_data<V, L>(&mut self, target: &str, variables: V, lists: L)
+    where
+        V: IntoIterator<Item = (&'a String, &'a Variable)>,
+        L: IntoIterator<Item = (&'a String, &'a List)>,
+    {
+        let mut variables: Vec<_> = variables.into_iter().map(|(_, v)| v).collect();
+        variables.sort_by_key(|v| v.0.id());
+        for (path, val) in &variables {
+            let code = format!(
+                "{}.{} = {}; // {}",
+                target,
+                path.js_name(),
+                js_value(val),
+                js_comment(path.name())
+            );
+            self.line(&code);
+        }
+        // for `sensing_of` variables the parser could not resolve
+        if !variables.is_empty() {
+            let names: Vec<_> = variables
+                .iter()
+                .map(|(path, _)| format!("{}: {}", js_str(path.name()), js_str(&path.js_name())))
+                .collect();
+            let code = format!("{}.variableNames = {};", target, js_object(&names));
+            self.line(&code);
+        }
+        let mut lists: Vec<_> = lists.into_iter().map(|(_, l)| l).collect();
+        lists.sort_by_key(|l| l.0.id());
+        for (path, items) in lists {
+            let items: Vec<_> = items.iter().map(js_value).collect();
+            let code = format!(
+                "{}.{} = [{}]; // {}",
+                target,
+                path.js_name(),
+                items.join(", "),
+                js_comment(path.name())
+            );
+            self.line(&code);
+        }
+    }
+
+    // assets are referenced by their md5ext, next to the generated module
+    fn gen_assets(&mut self, target: &str, costumes: &[Costume], sounds: &[Sound]) {
+        let costumes = costumes
+            .iter()
+            .map(|costume| {
+                format!(
+                    "{{ name: {}, asset: {}, center: [{}, {}] }}",
+                    js_str(&costume.name),
+                    js_str(costume.asset.name()),
+                    js_num(costume.center.0),
+                    js_num(costume.center.1)
+                )
+            })
+            .collect();
+        self.gen_array(&format!("{}.costumes", target), costumes);
+        let sounds = sounds
+            .iter()
+            .map(|sound| {
+                format!(
+                    "{{ name: {}, asset: {}, rate: {}, sampleCount: {} }}",
+                    js_str(&sound.name),
+                    js_str(sound.asset.name()),
+                    js_num(sound.rate),
+                    js_num(sound.sample_count)
+                )
+            })
+            .collect();
+        self.gen_array(&format!("{}.sounds", target), sounds);
+    }
+
+    fn gen_array(&mut self, lhs: &str, items: Vec<String>) {
+        if items.is_empty() {
+            self.line(&format!("{} = [];", lhs));
+            return;
+        }
+        self.line(&format!("{} = [", lhs));
+        self.indent += 1;
+        for item in items {
+            self.line(&format!("{},", item));
+        }
+        self.indent -= 1;
+        self.line("];");
+    }
+
+    fn gen_item(&mut self, target: &str, item: &BlockItem) {
+        let (head, tail) = match item {
+            BlockItem::EvWhenGreenFlagClicked(_) => (
+                format!("$runtime.whenGreenFlag({}, function* ($t) {{", target),
+                "});",
+            ),
+            BlockItem::EvWhenKeyPressed(key, _) => (
+                format!(
+                    "$runtime.whenKeyPressed({}, {}, function* ($t) {{",
+                    target,
+                    js_str(&key.name())
+                ),
+                "});",
+            ),
+            BlockItem::EvWhenThisSpriteClicked(_) | BlockItem::EvWhenStageClicked(_) => (
+                format!("$runtime.whenClicked({}, function* ($t) {{", target),
+                "});",
+            ),
+            BlockItem::EvWhenBackdropSwitchesTo(backdrop, _) => (
+                format!(
+                    "$runtime.whenBackdropSwitchesTo({}, {}, function* ($t) {{",
+                    target,
+                    js_str(backdrop)
+                ),
+                "});",
+            ),
+            BlockItem::EvWhenGreaterThan(menu, threshold, _) => (
+                format!(
+                    "$runtime.whenGreaterThan({}, {}, ($t) => {}, function* ($t) {{",
+                    target,
+                    js_str(match menu {
+                        GreaterThanMenu::Loudness => "loudness",
+                        GreaterThanMenu::Timer => "timer",
+                    }),
+                    self.num(threshold)
+                ),
+                "});",
+            ),
+            BlockItem::ControlStartAsClone(_) => (
+                format!("$runtime.whenStartAsClone({}, function* ($t) {{", target),
+                "});",
+            ),
+            BlockItem::EWhenRecieveBroadcast(broadcast, _) => (
+                format!(
+                    "$runtime.whenReceive({}, {}, function* ($t) {{",
+                    target,
+                    broadcast.js_name()
+                ),
+                "});",
+            ),
+            // custom blocks are methods of their target, so clones share them
+            BlockItem::Def(def, _) => {
+                let params: Vec<_> = std::iter::once("$t".to_string())
+                    .chain(def.args.iter().map(|arg| arg.path.js_name()))
+                    .collect();
+                (
+                    format!(
+                        "{}.{} = function* ({}) {{ // {}",
+                        target,
+                        def.path.js_name(),
+                        params.join(", "),
+                        js_comment(def.path.name())
+                    ),
+                    "};",
+                )
+            }
+        };
+        let body = match item {
+            BlockItem::EvWhenGreenFlagClicked(bs)
+            | BlockItem::EvWhenKeyPressed(_, bs)
+            | BlockItem::EvWhenThisSpriteClicked(bs)
+            | BlockItem::EvWhenStageClicked(bs)
+            | BlockItem::EvWhenBackdropSwitchesTo(_, bs)
+            | BlockItem::EvWhenGreaterThan(_, _, bs)
+            | BlockItem::EWhenRecieveBroadcast(_, bs)
+            | BlockItem::ControlStartAsClone(bs)
+            | BlockItem::Def(_, bs) => bs,
+        };
+        self.in_def = matches!(item, BlockItem::Def(..));
+        self.warp = matches!(item, BlockItem::Def(def, _) if def.warp);
+        self.line(&head);
+        self.indent += 1;
+        if self.warp {
+            // the thread stays in warp until the outermost warp custom block returns
+            self.line("$runtime.thread.warp++;");
+            self.line("try {");
+            self.indent += 1;
+            self.gen_stack(body);
+            self.indent -= 1;
+            self.line("} finally {");
+            self.line("  $runtime.thread.warp--;");
+            self.line("}");
+        } else {
+            self.gen_stack(body);
+        }
+        self.indent -= 1;
+        self.line(tail);
+        self.in_def = false;
+        self.warp = false;
+    }
+
+    fn gen_stack(&mut self, bs: &BlockStack) {
+        for block in bs.blocks() {
+            self.gen_stmt(block);
+        }
+    }
+
+    fn gen_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::MotionMove(steps) => {
+                let code = format!("$t.move({});", self.num(steps));
+                self.line(&code);
+            }
+            Stmt::MotionTurnRight(degrees) => {
+                let code = format!("$t.turn({});", self.num(degrees));
+                self.line(&code);
+            }
+            Stmt::MotionTurnLeft(degrees) => {
+                let code = format!("$t.turn(-({}));", self.num(degrees));
+                self.line(&code);
+            }
+            Stmt::MotionGoTo(to) => {
+                let code = format!("$t.goTo({});", self.str(to));
+                self.line(&code);
+            }
+            Stmt::MotionGoToXY(x, y) => {
+                let code = format!("$t.moveTo({}, {});", self.num(x), self.num(y));
+                self.line(&code);
+            }
+            Stmt::MotionGlideTo(secs, to) => {
+                let code = format!("yield* $t.glideTo({}, {});", self.num(secs), self.str(to));
+                self.line(&code);
+            }
+            Stmt::MotionGlideToXY(secs, x, y) => {
+                let code = format!(
+                    "yield* $t.glide({}, {}, {});",
+                    self.num(secs),
+                    self.num(x),
+                    self.num(y)
+                );
+                self.line(&code);
+            }
+            Stmt::MotionPointInDirection(direction) => {
+                let code = format!("$t.setDirection({});", self.num(direction));
+                self.line(&code);
+            }
+            Stmt::MotionPointTowards(towards) => {
+                let code = format!("$t.pointTowards({});", self.str(towards));
+                self.line(&code);
+            }
+            Stmt::MotionChangeX(dx) => {
+                let code = format!("$t.moveTo($t.x + {}, $t.y);", self.num(dx));
+                self.line(&code);
+            }
+            Stmt::MotionSetX(x) => {
+                let code = format!("$t.moveTo({}, $t.y);", self.num(x));
+                self.line(&code);
+            }
+            Stmt::MotionChangeY(dy) => {
+                let code = format!("$t.moveTo($t.x, $t.y + {});", self.num(dy));
+                self.line(&code);
+            }
+            Stmt::MotionSetY(y) => {
+                let code = format!("$t.moveTo($t.x, {});", self.num(y));
+                self.line(&code);
+            }
+            Stmt::MotionIfOnEdgeBounce => self.line("$t.bounce();"),
+            Stmt::MotionSetRotationStyle(style) => {
+                let code = format!("$t.rotationStyle = {};", js_str(rotation_style(*style)));
+                self.line(&code);
+            }
+            Stmt::LooksSay(message) => {
+                let code = format!("$t.say(\"say\", {});", self.expr(message));
+                self.line(&code);
+            }
+            Stmt::LooksSayForSecs(message, secs) => {
+                let code = format!(
+                    "yield* $t.sayForSecs(\"say\", {}, {});",
+                    self.expr(message),
+                    self.num(secs)
+                );
+                self.line(&code);
+            }
+            Stmt::LooksThink(message) => {
+                let code = format!("$t.say(\"think\", {});", self.expr(message));
+                self.line(&code);
+            }
+            Stmt::LooksThinkForSecs(message, secs) => {
+                let code = format!(
+                    "yield* $t.sayForSecs(\"think\", {}, {});",
+                    self.expr(message),
+                    self.num(secs)
+                );
+                self.line(&code);
+            }
+            // the menu value is passed as is, numbers and names are told apart at runtime
+            Stmt::LooksSwitchCostume(costume) => {
+                let code = format!("$t.switchCostume({});", self.expr(costume));
+                self.line(&code);
+            }
+            Stmt::LooksNextCostume => self.line("$t.setCostume($t.costume + 1);"),
+            Stmt::LooksSwitchBackdrop(backdrop) => {
+                let code = format!("$runtime.switchBackdrop({});", self.expr(backdrop));
+                self.line(&code);
+            }
+            Stmt::LooksSwitchBackdropAndWait(backdrop) => {
+                let code = format!(
+                    "yield* $runtime.waitFor($runtime.switchBackdrop({}));",
+                    self.expr(backdrop)
+                );
+                self.line(&code);
+            }
+            // costume numbers start at 1, so this is the one after the current
+            Stmt::LooksNextBackdrop => {
+                self.line("$runtime.switchBackdrop($stage.costume + 2);");
+            }
+            Stmt::LooksChangeSize(change) => {
+                let code = format!("$t.setSize($t.size + {});", self.num(change));
+                self.line(&code);
+            }
+            Stmt::LooksSetSize(size) => {
+                let code = format!("$t.setSize({});", self.num(size));
+                self.line(&code);
+            }
+            Stmt::LooksChangeEffect(effect, change) => {
+                let code = format!(
+                    "$t.setEffect({0}, $t.effects[{0}] + {1});",
+                    js_str(graphic_effect(*effect)),
+                    self.num(change)
+                );
+                self.line(&code);
+            }
+            Stmt::LooksSetEffect(effect, value) => {
+                let code = format!(
+                    "$t.setEffect({}, {});",
+                    js_str(graphic_effect(*effect)),
+                    self.num(value)
+                );
+                self.line(&code);
+            }
+            Stmt::LooksClearEffects => self.line("$t.clearEffects();"),
+            Stmt::LooksShow => self.line("$t.setVisible(true);"),
+            Stmt::LooksHide => self.line("$t.setVisible(false);"),
+            Stmt::LooksGoToFront => self.line("$runtime.goToFront($t);"),
+            Stmt::LooksGoToBack => self.line("$runtime.goToBack($t);"),
+            Stmt::LooksGoForwardLayers(layers) => {
+                let code = format!("$runtime.goForward($t, {});", self.num(layers));
+                self.line(&code);
+            }
+            Stmt::LooksGoBackwardLayers(layers) => {
+                let code = format!("$runtime.goForward($t, -({}));", self.num(layers));
+                self.line(&code);
+            }
+            Stmt::SoundPlay(sound) => {
+                let code = format!("$t.playSound({});", self.expr(sound));
+                self.line(&code);
+            }
+            Stmt::SoundPlayUntilDone(sound) => {
+                let code = format!("yield* $t.playSoundUntilDone({});", self.expr(sound));
+                self.line(&code);
+            }
+            Stmt::SoundStopAll => self.line("$runtime.stopAllSounds();"),
+            Stmt::SoundChangeVolume(change) => {
+                let code = format!("$t.setVolume($t.volume + {});", self.num(change));
+                self.line(&code);
+            }
+            Stmt::SoundSetVolume(volume) => {
+                let code = format!("$t.setVolume({});", self.num(volume));
+                self.line(&code);
+            }
+            Stmt::SoundChangeEffect(effect, change) => {
+                let code = format!(
+                    "$t.setSoundEffect({0}, $t.soundEffects[{0}] + {1});",
+                    js_str(sound_effect(*effect)),
+                    self.num(change)
+                );
+                self.line(&code);
+            }
+            Stmt::SoundSetEffect(effect, value) => {
+                let code = format!(
+                    "$t.setSoundEffect({}, {});",
+                    js_str(sound_effect(*effect)),
+                    self.num(value)
+                );
+                self.line(&code);
+            }
+            Stmt::SoundClearEffects => self.line("$t.clearSoundEffects();"),
+            Stmt::EventBroadcast(broadcast) => {
+                let code = format!("$runtime.broadcast({});", self.str(broadcast));
+                self.line(&code);
+            }
+            Stmt::EventBroadcastAndWait(broadcast) => {
+                let code = format!("yield* $runtime.broadcastAndWait({});", self.str(broadcast));
+                self.line(&code);
+            }
+            Stmt::PenClear => self.line("$runtime.emit(\"penClear\");"),
+            Stmt::PenStamp => self.line("$runtime.emit(\"stamp\", $t);"),
+            Stmt::PenDown => self.line("$t.setPenDown(true);"),
+            Stmt::PenUp => self.line("$t.setPenDown(false);"),
+            Stmt::PenSetColor(color) => {
+                let code = format!("$t.setPenColor({});", self.expr(color));
+                self.line(&code);
+            }
+            Stmt::PenChangeColorParam(param, change) => {
+                let code = format!(
+                    "$t.setPenColorParam({}, {}, true);",
+                    self.str(param),
+                    self.num(change)
+                );
+                self.line(&code);
+            }
+            Stmt::PenSetColorParam(param, value) => {
+                let code = format!(
+                    "$t.setPenColorParam({}, {});",
+                    self.str(param),
+                    self.num(value)
+                );
+                self.line(&code);
+            }
+            Stmt::PenChangeSize(change) => {
+                let code = format!("$t.setPenSize($t.pen.size + {});", self.num(change));
+                self.line(&code);
+            }
+            Stmt::PenSetSize(size) => {
+                let code = format!("$t.setPenSize({});", self.num(size));
+                self.line(&code);
+            }
+            Stmt::PenChangeShade(change) => {
+                let code = format!("$t.setPenShade($t.pen.shade + {});", self.num(change));
+                self.line(&code);
+            }
+            Stmt::PenSetShade(shade) => {
+                let code = format!("$t.setPenShade({});", self.num(shade));
+                self.line(&code);
+            }
+            Stmt::PenChangeHue(change) => {
+                let code = format!("$t.setPenHue({}, true);", self.num(change));
+                self.line(&code);
+            }
+            Stmt::PenSetHue(hue) => {
+                let code = format!("$t.setPenHue({});", self.num(hue));
+                self.line(&code);
+            }
+            Stmt::MusicPlayDrumForBeats(drum, beats) => {
+                let used = match drum {
+                    Expr::LlDrum(drum) => drum.number() - 1..drum.number(),
+                    _ => 0..DRUM_SAMPLES.len(),
+                };
+                self.samples.borrow_mut().drums.extend(used);
+                let code = format!(
+                    "yield* $t.playDrum({}, {});",
+                    self.expr(drum),
+                    self.num(beats)
+                );
+                self.line(&code);
+            }
+            Stmt::MusicRestForBeats(beats) => {
+                let code = format!("yield* $runtime.rest({});", self.num(beats));
+                self.line(&code);
+            }
+            Stmt::MusicPlayNoteForBeats(note, beats) => {
+                // every sprite starts with the piano
+                self.samples.borrow_mut().instruments.insert(0);
+                let code = format!(
+                    "yield* $t.playNote({}, {});",
+                    self.num(note),
+                    self.num(beats)
+                );
+                self.line(&code);
+            }
+            Stmt::MusicSetInstrument(instrument) => {
+                // a computed instrument may be any of them
+                let used = match instrument {
+                    Expr::LlInstrument(instrument) => instrument.number() - 1..instrument.number(),
+                    _ => 0..INSTRUMENT_SAMPLES.len(),
+                };
+                self.samples.borrow_mut().instruments.extend(used);
+                let code = format!("$t.setInstrument({});", self.expr(instrument));
+                self.line(&code);
+            }
+            Stmt::MusicSetTempo(tempo) => {
+                let code = format!("$runtime.setTempo({});", self.num(tempo));
+                self.line(&code);
+            }
+            Stmt::MusicChangeTempo(change) => {
+                let code = format!("$runtime.setTempo($stage.tempo + {});", self.num(change));
+                self.line(&code);
+            }
+            Stmt::ControlForever(body) => self.gen_loop("while (true) {", None, body),
+            Stmt::ControlIf(cond, body) => {
+                let head = format!("if ({}) {{", self.bool(cond));
+                self.gen_block(&head, body);
+                self.line("}");
+            }
+            Stmt::ControlIfElse(cond, body, else_body) => {
+                let head = format!("if ({}) {{", self.bool(cond));
+                self.gen_block(&head, body);
+                self.gen_block("} else {", else_body);
+                self.line("}");
+            }
+            // nested loops shadow the counter of the outer one
+            Stmt::ControlRepeat(times, body) => {
+                let head = format!(
+                    "for (let $i = Math.round({}); $i > 0; $i--) {{",
+                    self.num(times)
+                );
+                self.gen_loop(&head, None, body);
+            }
+            Stmt::ControlRepeatUntil(cond, body) => {
+                let head = format!("while (!{}) {{", self.bool(cond));
+                self.gen_loop(&head, None, body);
+            }
+            Stmt::ControlWhile(cond, body) => {
+                let head = format!("while ({}) {{", self.bool(cond));
+                self.gen_loop(&head, None, body);
+            }
+            Stmt::ControlForEach(var, value, body) => {
+                let head = format!("for (let $i = 1; $i <= {}; $i++) {{", self.num(value));
+                let set = format!("{} = $i;", self.data_ref(var));
+                self.gen_loop(&head, Some(&set), body);
+            }
+            Stmt::ControlWait(secs) => {
+                let code = format!("yield* $runtime.wait({});", self.num(secs));
+                self.line(&code);
+            }
+            // waiting gives up the frame even in warp custom blocks
+            Stmt::ControlWaitUntil(cond) => {
+                let code = format!("while (!{}) yield;", self.bool(cond));
+                self.line(&code);
+            }
+            Stmt::ControlStop(StopOption::All) => {
+                self.line("$runtime.stopAll();");
+                self.line("yield* $runtime.stopThisScript();");
+            }
+            // inside a custom block it only returns to the caller
+            Stmt::ControlStop(StopOption::ThisScript) if self.in_def => self.line("return;"),
+            Stmt::ControlStop(StopOption::ThisScript) => {
+                self.line("yield* $runtime.stopThisScript();")
+            }
+            Stmt::ControlStop(StopOption::OtherScriptsInSprite) => {
+                self.line("$runtime.stopOtherScripts($t);")
+            }
+            Stmt::ControlCreateClone(name) => {
+                let code = format!("$runtime.createClone($t, {});", self.str(name));
+                self.line(&code);
+            }
+            Stmt::ControlDeleteThisClone => self.line("yield* $runtime.deleteThisClone($t);"),
+            Stmt::SensingAskAndWait(question) => {
+                let code = format!("yield* $runtime.ask($t, {});", self.str(question));
+                self.line(&code);
+            }
+            Stmt::SensingResetTimer => self.line("$runtime.resetTimer();"),
+            Stmt::SensingSetDragMode(draggable) => {
+                let code = format!("$t.draggable = {};", draggable);
+                self.line(&code);
+            }
+            Stmt::DataSetVariable(var, value) => {
+                let code = format!("{} = {};", self.data_ref(var), self.expr(value));
+                self.line(&code);
+            }
+            Stmt::DataChangeVariable(var, value) => {
+                let var = self.data_ref(var);
+                let code = format!("{} = $num({}) + {};", var, var, self.num(value));
+                self.line(&code);
+            }
+            Stmt::DataShowVariable(path) | Stmt::DataShowList(path) => self.gen_monitor(path, true),
+            Stmt::DataHideVariable(path) | Stmt::DataHideList(path) => {
+                self.gen_monitor(path, false)
+            }
+            Stmt::DataAddToList(list, item) => {
+                let code = format!("$listAdd({}, {});", self.data_ref(list), self.expr(item));
+                self.line(&code);
+            }
+            Stmt::DataDeleteOfList(list, index) => {
+                let code = format!(
+                    "$listDelete({}, {});",
+                    self.data_ref(list),
+                    self.expr(index)
+                );
+                self.line(&code);
+            }
+            Stmt::DataDeleteAllOfList(list) => {
+                let code = format!("{}.length = 0;", self.data_ref(list));
+                self.line(&code);
+            }
+            Stmt::DataInsertAtList(list, index, item) => {
+                let code = format!(
+                    "$listInsert({}, {}, {});",
+                    self.data_ref(list),
+                    self.expr(index),
+                    self.expr(item)
+                );
+                self.line(&code);
+            }
+            Stmt::DataReplaceItemOfList(list, index, item) => {
+                let code = format!(
+                    "$listReplace({}, {}, {});",
+                    self.data_ref(list),
+                    self.expr(index),
+                    self.expr(item)
+                );
+                self.line(&code);
+            }
+            Stmt::ProcCall(path, args) => {
+                let args: Vec<_> = std::iter::once("$t".to_string())
+                    .chain(args.iter().map(|arg| self.expr(arg)))
+                    .collect();
+                let code = format!("yield* $t.{}({});", path.js_name(), args.join(", "));
+                self.line(&code);
+            }
+            Stmt::Stub(opcode) => {
+                self.line(&format!("// unsupported block `{}`", js_comment(opcode)))
+            }
+            Stmt::Fallback(block) => {
+                let code = format!("yield* $runtime.fallback({});", self.fallback_args(block));
+                self.line(&code);
+            }
+        }
+    }
+
+    fn expr(&self, expr: &Expr) -> String {
+        match expr {
+            Expr::LlScratchValue(val) => js_value(val),
+            Expr::LlBroadcast(path) => path.js_name(),
+            Expr::LlVar(path) => self.data_ref(path),
+            Expr::LlList(path) => format!("$listContents({})", self.data_ref(path)),
+            Expr::LlKey(key) => js_str(&key.name()),
+            Expr::LlTarget(target) => js_str(target.name()),
+            Expr::MotionXPosition => "$t.xPosition()".to_string(),
+            Expr::MotionYPosition => "$t.yPosition()".to_string(),
+            Expr::MotionDirection => "$t.direction".to_string(),
+            Expr::LlCostume(CostumeMenu::Costume(idx)) => (idx + 1).to_string(),
+            Expr::LlCostume(CostumeMenu::Next) => js_str("next backdrop"),
+            Expr::LlCostume(CostumeMenu::Previous) => js_str("previous backdrop"),
+            Expr::LlCostume(CostumeMenu::Random) => js_str("random backdrop"),
+            Expr::LooksCostume(NumberName::Number) => "($t.costume + 1)".to_string(),
+            Expr::LooksCostume(NumberName::Name) => "$t.costumes[$t.costume].name".to_string(),
+            Expr::LooksBackdrop(NumberName::Number) => "($stage.costume + 1)".to_string(),
+            Expr::LooksBackdrop(NumberName::Name) => {
+                "$stage.costumes[$stage.costume].name".to_string()
+            }
+            Expr::LooksSize => "Math.round($t.size)".to_string(),
+            Expr::LlSound(idx) => (idx + 1).to_string(),
+            Expr::SoundVolume => "$t.volume".to_string(),
+            Expr::SensingDistanceTo(name) => {
+                format!("$runtime.distanceTo($t, {})", self.str(name))
+            }
+            Expr::SensingAnswer => "$runtime.lastAnswer".to_string(),
+            Expr::SensingMouseX => "$runtime.mouse.x".to_string(),
+            Expr::SensingMouseY => "$runtime.mouse.y".to_string(),
+            Expr::SensingLoudness => "$runtime.loudness".to_string(),
+            Expr::SensingTimer => "$runtime.timer()".to_string(),
+            Expr::SensingOf(SensingOfProperty::VariableName(name), object) => format!(
+                "$runtime.sensingOfVariable({}, {})",
+                self.str(object),
+                js_str(name)
+            ),
+            Expr::SensingOf(property, object) => format!(
+                "$runtime.sensingOf({}, {})",
+                self.str(object),
+                js_str(&sensing_of_property(property))
+            ),
+            Expr::SensingCurrent(menu) => format!("$current({})", js_str(current_menu(*menu))),
+            Expr::SensingDaysSince2000 => "$daysSince2000()".to_string(),
+            Expr::SensingUsername => "$runtime.username".to_string(),
+            Expr::OperatorAdd(a, b) => format!("({} + {})", self.num(a), self.num(b)),
+            Expr::OperatorSubtract(a, b) => format!("({} - {})", self.num(a), self.num(b)),
+            Expr::OperatorMultiply(a, b) => format!("({} * {})", self.num(a), self.num(b)),
+            Expr::OperatorDivide(a, b) => format!("({} / {})", self.num(a), self.num(b)),
+            // `$random` looks at the raw values to pick an integer or not
+            Expr::OperatorRandom(from, to) => {
+                format!("$random({}, {})", self.expr(from), self.expr(to))
+            }
+            Expr::OperatorJoin(a, b) => format!("({} + {})", self.str(a), self.str(b)),
+            Expr::OperatorLetterOf(letter, string) => {
+                format!("$letterOf({}, {})", self.num(letter), self.str(string))
+            }
+            Expr::OperatorLength(string) => format!("{}.length", self.str(string)),
+            Expr::OperatorMod(a, b) => format!("$mod({}, {})", self.num(a), self.num(b)),
+            Expr::OperatorRound(n) => format!("Math.round({})", self.num(n)),
+            Expr::OperatorMathOp(op, n) => math_op(*op, &self.num(n)),
+            Expr::LlListIndex(index) => js_str(index.name()),
+            Expr::LlPenColorParam(param) => js_str(param.name()),
+            Expr::LlDrum(drum) => drum.number().to_string(),
+            Expr::LlInstrument(instrument) => instrument.number().to_string(),
+            Expr::MusicGetTempo => "$stage.tempo".to_string(),
+            Expr::Fallback(block) => {
+                format!("$runtime.fallbackReporter({})", self.fallback_args(block))
+            }
+            Expr::DataItemOfList(list, index) => {
+                format!("$listItem({}, {})", self.data_ref(list), self.expr(index))
+            }
+            Expr::DataItemNumOfList(list, item) => {
+                format!("$listItemNum({}, {})", self.data_ref(list), self.expr(item))
+            }
+            Expr::DataLengthOfList(list) => format!("{}.length", self.data_ref(list)),
+            Expr::ProcArg(path) => path.js_name(),
+            Expr::Bool(cond) => self.bool(cond),
+        }
+    }
+
+    fn bool(&self, cond: &BoolExpr) -> String {
+        match cond {
+            BoolExpr::LlBool(b) => b.to_string(),
+            BoolExpr::Empty => "false".to_string(),
+            BoolExpr::OperatorLt(a, b) => format!("($cmp({}, {}) < 0)", self.expr(a), self.expr(b)),
+            BoolExpr::OperatorGt(a, b) => format!("($cmp({}, {}) > 0)", self.expr(a), self.expr(b)),
+            BoolExpr::OperatorEquals(a, b) => {
+                format!("($cmp({}, {}) === 0)", self.expr(a), self.expr(b))
+            }
+            BoolExpr::OperatorAnd(a, b) => format!("({} && {})", self.bool(a), self.bool(b)),
+            BoolExpr::OperatorOr(a, b) => format!("({} || {})", self.bool(a), self.bool(b)),
+            BoolExpr::OperatorNot(a) => format!("!{}", self.bool(a)),
+            BoolExpr::OperatorContains(a, b) => {
+                format!("$contains({}, {})", self.str(a), self.str(b))
+            }
+            BoolExpr::DataListContainsItem(list, item) => {
+                format!(
+                    "$listContains({}, {})",
+                    self.data_ref(list),
+                    self.expr(item)
+                )
+            }
+            BoolExpr::SensingTouchingObject(name) => {
+                format!("$runtime.isTouching($t, {})", self.str(name))
+            }
+            BoolExpr::SensingTouchingColor(color) => {
+                format!("$runtime.isTouchingColor($t, {})", self.expr(color))
+            }
+            BoolExpr::SensingColorIsTouchingColor(color, color2) => format!(
+                "$runtime.isColorTouchingColor($t, {}, {})",
+                self.expr(color),
+                self.expr(color2)
+            ),
+            BoolExpr::SensingMouseDown => "$runtime.mouseDown".to_string(),
+            BoolExpr::SensingKeyPressed(key) => {
+                format!("$runtime.isKeyPressed({})", self.expr(key))
+            }
+            BoolExpr::ProcArg(path) => path.js_name(),
+            BoolExpr::Cast(expr) => match &**expr {
+                Expr::LlScratchValue(val) => cast::to_boolean(val).to_string(),
+                expr => format!("$bool({})", self.expr(expr)),
+            },
+        }
+    }
+
+    // `head` opens a block that `body` is generated into, the caller closes it
+    fn gen_block(&mut self, head: &str, body: &BlockStack) {
+        self.line(head);
+        self.indent += 1;
+        self.gen_stack(body);
+        self.indent -= 1;
+    }
+
+    fn gen_loop(&mut self, head: &str, prelude: Option<&str>, body: &BlockStack) {
+        self.line(head);
+        self.indent += 1;
+        if let Some(prelude) = prelude {
+            self.line(prelude);
+        }
+        self.gen_stack(body);
+        self.gen_yield();
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    // loops only give up the frame outside of warp custom blocks, other custom blocks
+    // check at runtime whether a warp one called them
+    fn gen_yield(&mut self) {
+        if self.warp {
+            return;
+        }
+        if self.in_def {
+            self.line("if (!$runtime.thread.warp) yield;");
+        } else {
+            self.line("yield;");
+        }
+    }
+
+    fn num(&self, expr: &Expr) -> String {
+        match expr {
+            // literals are cast at compile time, exactly like `$num` would at runtime
+            Expr::LlScratchValue(val) => js_num(cast::to_number(val)),
+            // these are numbers already and never NaN, arithmetic may be
+            Expr::OperatorLength(_)
+            | Expr::OperatorRound(_)
+            | Expr::MotionXPosition
+            | Expr::MotionYPosition
+            | Expr::MotionDirection
+            | Expr::LooksCostume(NumberName::Number)
+            | Expr::LooksBackdrop(NumberName::Number)
+            | Expr::LooksSize
+            | Expr::SoundVolume
+            | Expr::SensingDistanceTo(_)
+            | Expr::SensingMouseX
+            | Expr::SensingMouseY
+            | Expr::SensingLoudness
+            | Expr::SensingTimer
+            | Expr::SensingCurrent(_)
+            | Expr::SensingDaysSince2000
+            | Expr::DataItemNumOfList(_, _)
+            | Expr::DataLengthOfList(_)
+            | Expr::LlDrum(_)
+            | Expr::LlInstrument(_)
+            | Expr::MusicGetTempo => self.expr(expr),
+            _ => format!("$num({})", self.expr(expr)),
+        }
+    }
+
+    fn str(&self, expr: &Expr) -> String {
+        match expr {
+            Expr::LlScratchValue(val) => js_str(&cast::to_string(val)),
+            Expr::LlKey(_)
+            | Expr::LlTarget(_)
+            | Expr::LlBroadcast(_)
+            | Expr::LlList(_)
+            | Expr::LlListIndex(_)
+            | Expr::LlPenColorParam(_)
+            | Expr::OperatorJoin(_, _)
+            | Expr::OperatorLetterOf(_, _) => self.expr(expr),
+            _ => format!("$str({})", self.expr(expr)),
+        }
+    }
+
+    // inputs are evaluated before the shim runs, substacks become generator functions
+    fn fallback_args(&self, block: &FallbackBlock) -> String {
+        let fields: Vec<_> = block
+            .fields
+            .iter()
+            .map(|(name, value)| format!("{}: {}", js_str(name), js_str(value)))
+            .collect();
+        let inputs: Vec<_> = block
+            .inputs
+            .iter()
+            .map(|(name, input)| {
+                let value = match input {
+                    FallbackInput::Expr(expr) => self.expr(expr),
+                    FallbackInput::Stack(body) => self.stack_fn(body),
+                };
+                format!("{}: {}", js_str(name), value)
+            })
+            .collect();
+        format!(
+            "$t, {}, {}, {}",
+            js_str(&block.opcode),
+            js_object(&fields),
+            js_object(&inputs)
+        )
+    }
+
+    fn stack_fn(&self, body: &BlockStack) -> String {
+        let mut gen = JsCodegen {
+            proj: self.proj,
+            globals: self.globals.clone(),
+            out: String::new(),
+            indent: self.indent + 1,
+            in_def: self.in_def,
+            warp: self.warp,
+            samples: self.samples.clone(),
+        };
+        gen.gen_stack(body);
+        format!("function* () {{\n{}{}}}", gen.out, "  ".repeat(self.indent))
+    }
+
+    fn gen_monitor(&mut self, path: &ResourcePath, visible: bool) {
+        let code = format!(
+            "$runtime.setMonitorVisible({}, {}, {});",
+            self.data_owner(path),
+            js_str(&path.js_name()),
+            visible
+        );
+        self.line(&code);
+    }
+
+    fn data_owner(&self, path: &ResourcePath) -> &str {
+        if self.globals.contains(path) {
+            "$stage"
+        } else {
+            "$t"
+        }
+    }
+
+    fn data_ref(&self, path: &ResourcePath) -> String {
+        format!("{}.{}", self.data_owner(path), path.js_name())
+    }
+}
+
+fn js_str(s: &str) -> String {
+    serde_json::to_string(s).unwrap()
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * idx) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// scratch names end up in `//` comments, a line terminator would end the comment early
+fn js_comment(s: &str) -> String {
+    s.replace(['\n', '\r', '\u{2028}', '\u{2029}'], " ")
+}
+
+fn js_object(entries: &[String]) -> String {
+    if entries.is_empty() {
+        "{}".to_string()
+    } else {
+        format!("{{ {} }}", entries.join(", "))
+    }
+}
+
+fn js_num(num: f64) -> String {
+    if num.is_nan() {
+        "NaN".to_string()
+    } else if num.is_infinite() {
+        if num > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else {
+        num.to_string()
+    }
+}
+
+// variables are read by their js name, they are properties of the target
+fn sensing_of_property(property: &SensingOfProperty) -> String {
+    match property {
+        SensingOfProperty::XPosition => "x position".to_string(),
+        SensingOfProperty::YPosition => "y position".to_string(),
+        SensingOfProperty::Direction => "direction".to_string(),
+        SensingOfProperty::CostumeNumber => "costume #".to_string(),
+        SensingOfProperty::CostumeName => "costume name".to_string(),
+        SensingOfProperty::Size => "size".to_string(),
+        SensingOfProperty::Volume => "volume".to_string(),
+        SensingOfProperty::BackdropNumber => "backdrop #".to_string(),
+        SensingOfProperty::BackdropName => "backdrop name".to_string(),
+        SensingOfProperty::Variable(path) => path.js_name(),
+        SensingOfProperty::VariableName(name) => name.clone(),
+    }
+}
+
+fn current_menu(menu: CurrentMenu) -> &'static str {
+    match menu {
+        CurrentMenu::Year => "year",
+        CurrentMenu::Month => "month",
+        CurrentMenu::Date => "date",
+        CurrentMenu::DayOfWeek => "dayofweek",
+        CurrentMenu::Hour => "hour",
+        CurrentMenu::Minute => "minute",
+        CurrentMenu::Second => "second",
+    }
+}
+
+fn math_op(op: MathOp, n: &str) -> String {
+    match op {
+        MathOp::Abs => format!("Math.abs({})", n),
+        MathOp::Floor => format!("Math.floor({})", n),
+        MathOp::Ceiling => format!("Math.ceil({})", n),
+        MathOp::Sqrt => format!("Math.sqrt({})", n),
+        MathOp::Sin => format!("$sin({})", n),
+        MathOp::Cos => format!("$cos({})", n),
+        MathOp::Tan => format!("$tan({})", n),
+        MathOp::Asin => format!("(Math.asin({}) * 180 / Math.PI)", n),
+        MathOp::Acos => format!("(Math.acos({}) * 180 / Math.PI)", n),
+        MathOp::Atan => format!("(Math.atan({}) * 180 / Math.PI)", n),
+        MathOp::Ln => format!("Math.log({})", n),
+        MathOp::Log => format!("(Math.log({}) / Math.LN10)", n),
+        MathOp::PowE => format!("Math.exp({})", n),
+        MathOp::Pow10 => format!("Math.pow(10, {})", n),
+    }
+}
+
+fn rotation_style(style: RotationStyle) -> &'static str {
+    match style {
+        RotationStyle::AllAround => "all around",
+        RotationStyle::LeftRight => "left-right",
+        RotationStyle::NoRotation => "don't rotate",
+    }
+}
+
+fn graphic_effect(effect: GraphicEffect) -> &'static str {
+    match effect {
+        GraphicEffect::Color => "color",
+        GraphicEffect::Fisheye => "fisheye",
+        GraphicEffect::Whirl => "whirl",
+        GraphicEffect::Pixelate => "pixelate",
+        GraphicEffect::Mosaic => "mosaic",
+        GraphicEffect::Brightness => "brightness",
+        GraphicEffect::Ghost => "ghost",
+    }
+}
+
+fn sound_effect(effect: SoundEffect) -> &'static str {
+    match effect {
+        SoundEffect::Pitch => "pitch",
+        SoundEffect::Pan => "pan",
+    }
+}
+
+fn js_value(val: &ScratchValue) -> String {
+    match val {
+        ScratchValue::Num(num) => js_num(*num),
+        ScratchValue::Str(s) => js_str(s),
+        ScratchValue::Bool(b) => b.to_string(),
+    }
+}
@@ -0,0 +1,1251 @@
+// ira runtime, bundled into every generated module
+
+const $num = (value) => {
+  const n = Number(value);
+  return Number.isNaN(n) ? 0 : n;
+};
+
+const $str = (value) => String(value);
+
+const $bool = (value) => {
+  if (typeof value === "boolean") return value;
+  if (typeof value === "string") {
+    return !(value === "" || value === "0" || value.toLowerCase() === "false");
+  }
+  return Boolean(value);
+};
+
+const $isWhiteSpace = (value) => typeof value === "string" && value.trim().length === 0;
+
+// same as scratch-vm's `Cast.compare`, numbers when both sides look like one
+const $cmp = (a, b) => {
+  let n1 = Number(a);
+  let n2 = Number(b);
+  if (n1 === 0 && $isWhiteSpace(a)) {
+    n1 = NaN;
+  } else if (n2 === 0 && $isWhiteSpace(b)) {
+    n2 = NaN;
+  }
+  if (Number.isNaN(n1) || Number.isNaN(n2)) {
+    const s1 = String(a).toLowerCase();
+    const s2 = String(b).toLowerCase();
+    return s1 < s2 ? -1 : s1 > s2 ? 1 : 0;
+  }
+  if (n1 === Infinity && n2 === Infinity) return 0;
+  if (n1 === -Infinity && n2 === -Infinity) return 0;
+  return n1 - n2;
+};
+
+// same as scratch-vm's `Cast.isInt`, decides whether `pick random` picks an integer
+const $isInt = (value) => {
+  if (typeof value === "number") return Number.isNaN(value) || value === Math.floor(value);
+  if (typeof value === "boolean") return true;
+  return !String(value).includes(".");
+};
+
+const $random = (from, to) => {
+  const nFrom = $num(from);
+  const nTo = $num(to);
+  const low = Math.min(nFrom, nTo);
+  const high = Math.max(nFrom, nTo);
+  if (low === high) return low;
+  if ($isInt(from) && $isInt(to)) return low + Math.floor(Math.random() * (high + 1 - low));
+  return Math.random() * (high - low) + low;
+};
+
+// the result has the sign of the divisor
+const $mod = (n, modulus) => {
+  let result = n % modulus;
+  if (result / modulus < 0) result += modulus;
+  return result;
+};
+
+const $letterOf = (index, str) => {
+  index -= 1;
+  if (index < 0 || index >= str.length) return "";
+  return str.charAt(index);
+};
+
+const $contains = (str, part) => str.toLowerCase().includes(part.toLowerCase());
+
+// lists, indices work like scratch-vm's `Cast.toListIndex`, 0 for an invalid one
+const $LIST_ITEM_LIMIT = 200000;
+
+const $listIndex = (index, length, acceptAll) => {
+  if (index === "all") return acceptAll ? -1 : 0;
+  if (index === "last") return length;
+  if (index === "random" || index === "any") {
+    return length > 0 ? 1 + Math.floor(Math.random() * length) : 0;
+  }
+  index = Math.floor($num(index));
+  return index < 1 || index > length ? 0 : index;
+};
+
+const $listAdd = (list, item) => {
+  if (list.length < $LIST_ITEM_LIMIT) list.push(item);
+};
+
+const $listDelete = (list, index) => {
+  index = $listIndex(index, list.length, true);
+  if (index === -1) list.length = 0;
+  else if (index > 0) list.splice(index - 1, 1);
+};
+
+const $listInsert = (list, index, item) => {
+  index = $listIndex(index, list.length + 1, false);
+  if (index === 0 || index > $LIST_ITEM_LIMIT) return;
+  list.splice(index - 1, 0, item);
+  if (list.length > $LIST_ITEM_LIMIT) list.pop();
+};
+
+const $listReplace = (list, index, item) => {
+  index = $listIndex(index, list.length, false);
+  if (index > 0) list[index - 1] = item;
+};
+
+const $listItem = (list, index) => {
+  index = $listIndex(index, list.length, false);
+  return index > 0 ? list[index - 1] : "";
+};
+
+const $listItemNum = (list, item) => {
+  const index = list.findIndex((i) => $cmp(i, item) === 0);
+  return index + 1;
+};
+
+const $listContains = (list, item) =>
+  list.includes(item) || list.some((i) => $cmp(i, item) === 0);
+
+// a list of single letters reads as a word
+const $listContents = (list) => {
+  const letters = list.every((i) => typeof i === "string" && i.length === 1);
+  return list.join(letters ? "" : " ");
+};
+
+// trigonometry in degrees, rounded like scratch-vm so `sin 180` is 0
+const $sin = (n) => parseFloat(Math.sin((Math.PI * n) / 180).toFixed(10));
+
+const $cos = (n) => parseFloat(Math.cos((Math.PI * n) / 180).toFixed(10));
+
+const $tan = (n) => {
+  n %= 360;
+  if (n === 90 || n === -270) return Infinity;
+  if (n === -90 || n === 270) return -Infinity;
+  return parseFloat(Math.tan((Math.PI * n) / 180).toFixed(10));
+};
+
+const $current = (menu) => {
+  const date = new Date();
+  switch (menu) {
+    case "year":
+      return date.getFullYear();
+    case "month":
+      return date.getMonth() + 1;
+    case "date":
+      return date.getDate();
+    case "dayofweek":
+      return date.getDay() + 1;
+    case "hour":
+      return date.getHours();
+    case "minute":
+      return date.getMinutes();
+    case "second":
+      return date.getSeconds();
+  }
+  return 0;
+};
+
+// in local time, like scratch-vm
+const $daysSince2000 = () => {
+  const start = new Date(2000, 0, 1);
+  const today = new Date();
+  const dstAdjust = today.getTimezoneOffset() - start.getTimezoneOffset();
+  let msecs = today.valueOf() - start.valueOf();
+  msecs += (today.getTimezoneOffset() - dstAdjust) * 60 * 1000;
+  return msecs / (24 * 60 * 60 * 1000);
+};
+
+const $KEY_NAMES = [
+  "any", "space", "left arrow", "up arrow", "right arrow", "down arrow", "enter",
+  "backspace", "delete", "shift", "caps lock", "scroll lock", "control", "escape",
+  "insert", "home", "end", "page up", "page down",
+];
+
+// same as scratch-vm's `_keyArgToScratchKey`, key codes are not supported
+const $key = (key) => {
+  key = String(key);
+  if ($KEY_NAMES.includes(key)) return key;
+  if (key.length > 1) key = key[0];
+  if (key === " ") return "space";
+  return key.toUpperCase();
+};
+
+// `KeyboardEvent.key` values that scratch names differently
+const $BROWSER_KEYS = {
+  " ": "space",
+  ArrowLeft: "left arrow",
+  ArrowUp: "up arrow",
+  ArrowRight: "right arrow",
+  ArrowDown: "down arrow",
+  Enter: "enter",
+  Backspace: "backspace",
+  Delete: "delete",
+  Shift: "shift",
+  CapsLock: "caps lock",
+  ScrollLock: "scroll lock",
+  Control: "control",
+  Escape: "escape",
+  Insert: "insert",
+  Home: "home",
+  End: "end",
+  PageUp: "page up",
+  PageDown: "page down",
+};
+
+const $wrapClamp = (n, min, max) => {
+  const range = max - min + 1;
+  return n - Math.floor((n - min) / range) * range;
+};
+
+const $limitPrecision = (n) => {
+  const rounded = Math.round(n);
+  return Math.abs(n - rounded) < 1e-9 ? rounded : n;
+};
+
+const $clearEffects = () => ({
+  color: 0,
+  fisheye: 0,
+  whirl: 0,
+  pixelate: 0,
+  mosaic: 0,
+  brightness: 0,
+  ghost: 0,
+});
+
+// same as scratch-vm's `_formatBubbleText`
+const $bubbleText = (text) => {
+  if (typeof text === "number" && Math.abs(text) >= 0.01 && text % 1 !== 0) {
+    text = text.toFixed(2);
+  }
+  return String(text).substring(0, 330);
+};
+
+// colors, the same conversions as scratch-vm's `Color` and `Cast.toRgbColorObject`
+const $rgb = (value) => {
+  if (typeof value === "string" && value.startsWith("#")) {
+    let hex = value.slice(1);
+    if (/^[0-9a-f]{3}$/i.test(hex)) hex = hex.replace(/./g, "$&$&");
+    if (!/^[0-9a-f]{6}$/i.test(hex)) return { r: 0, g: 0, b: 0 };
+    const n = parseInt(hex, 16);
+    return { r: (n >> 16) & 0xff, g: (n >> 8) & 0xff, b: n & 0xff };
+  }
+  const n = $num(value);
+  const a = (n >> 24) & 0xff;
+  return { r: (n >> 16) & 0xff, g: (n >> 8) & 0xff, b: n & 0xff, a: a > 0 ? a : 255 };
+};
+
+const $rgbToHsv = ({ r, g, b }) => {
+  r /= 255;
+  g /= 255;
+  b /= 255;
+  const x = Math.min(r, g, b);
+  const v = Math.max(r, g, b);
+  let h = 0;
+  let s = 0;
+  if (x !== v) {
+    const f = r === x ? g - b : g === x ? b - r : r - g;
+    const i = r === x ? 3 : g === x ? 5 : 1;
+    h = ((i - f / (v - x)) * 60) % 360;
+    s = (v - x) / v;
+  }
+  return { h, s, v };
+};
+
+const $hsvToRgb = ({ h, s, v }) => {
+  h %= 360;
+  if (h < 0) h += 360;
+  s = Math.max(0, Math.min(s, 1));
+  v = Math.max(0, Math.min(v, 1));
+  const i = Math.floor(h / 60);
+  const f = h / 60 - i;
+  const p = v * (1 - s);
+  const q = v * (1 - s * f);
+  const t = v * (1 - s * (1 - f));
+  const [r, g, b] = [
+    [v, t, p],
+    [q, v, p],
+    [p, v, t],
+    [p, q, v],
+    [t, p, v],
+    [v, p, q],
+  ][i] ?? [v, t, p];
+  return { r: Math.floor(r * 255), g: Math.floor(g * 255), b: Math.floor(b * 255) };
+};
+
+const $mixRgb = (rgb0, rgb1, fraction1) => {
+  if (fraction1 <= 0) return rgb0;
+  if (fraction1 >= 1) return rgb1;
+  const fraction0 = 1 - fraction1;
+  return {
+    r: fraction0 * rgb0.r + fraction1 * rgb1.r,
+    g: fraction0 * rgb0.g + fraction1 * rgb1.g,
+    b: fraction0 * rgb0.b + fraction1 * rgb1.b,
+  };
+};
+
+// scratch-vm's default pen, `color` is a hue from 0 to 100 and `shade` is scratch 2's
+const $defaultPen = () => ({
+  down: false,
+  color: 66.66,
+  saturation: 100,
+  brightness: 100,
+  transparency: 0,
+  shade: 50,
+  size: 1,
+});
+
+class $Target {
+  constructor(runtime, name, isStage) {
+    this.runtime = runtime;
+    this.name = name;
+    this.isStage = isStage;
+    this.x = 0;
+    this.y = 0;
+    this.direction = 90;
+    this.rotationStyle = "all around";
+    this.size = 100;
+    this.visible = true;
+    this.draggable = false;
+    this.effects = $clearEffects();
+    this.bubbleId = 0;
+    this.costume = 0;
+    this.volume = 100;
+    this.soundEffects = { pitch: 0, pan: 0 };
+    this.pen = $defaultPen();
+    this.instrument = 0;
+    // the stage only, beats per minute of the music extension
+    this.tempo = 60;
+    this.costumes = [];
+    this.sounds = [];
+    // variable names to their properties, clones share it
+    this.variableNames = {};
+    // the original of a clone, hats are registered on it
+    this.sprite = this;
+    this.isClone = false;
+  }
+
+  init(state) {
+    Object.assign(this, state);
+  }
+
+  // custom blocks and variables are own properties and get copied, lists are not shared
+  clone() {
+    const clone = Object.assign(Object.create($Target.prototype), this);
+    for (const [key, value] of Object.entries(this)) {
+      if (key.startsWith("$") && Array.isArray(value)) clone[key] = [...value];
+    }
+    clone.effects = { ...this.effects };
+    clone.soundEffects = { ...this.soundEffects };
+    clone.pen = { ...this.pen };
+    clone.bubbleId = 0;
+    clone.isClone = true;
+    return clone;
+  }
+
+  move(steps) {
+    const rad = ((90 - this.direction) * Math.PI) / 180;
+    this.moveTo(this.x + steps * Math.cos(rad), this.y + steps * Math.sin(rad));
+  }
+
+  moveTo(x, y) {
+    const ox = this.x;
+    const oy = this.y;
+    this.x = x;
+    this.y = y;
+    this.runtime.emit("move", this, ox, oy);
+    if (this.pen.down) this.runtime.emit("penLine", this, ox, oy, x, y);
+  }
+
+  turn(degrees) {
+    this.setDirection(this.direction + degrees);
+  }
+
+  setDirection(direction) {
+    if (!Number.isFinite(direction)) return;
+    this.direction = $wrapClamp(direction, -179, 180);
+  }
+
+  // coordinates are reported without float noise, like scratch does
+  xPosition() {
+    return $limitPrecision(this.x);
+  }
+
+  yPosition() {
+    return $limitPrecision(this.y);
+  }
+
+  goTo(name) {
+    const pos = this.runtime.position(name);
+    if (pos) this.moveTo(...pos);
+  }
+
+  pointTowards(name) {
+    if (name === "_random_") {
+      this.setDirection(Math.round(Math.random() * 360) - 180);
+      return;
+    }
+    const pos = this.runtime.position(name);
+    if (!pos) return;
+    const dx = pos[0] - this.x;
+    const dy = pos[1] - this.y;
+    this.setDirection(90 - (Math.atan2(dy, dx) * 180) / Math.PI);
+  }
+
+  *glideTo(secs, name) {
+    const pos = this.runtime.position(name);
+    if (pos) yield* this.glide(secs, ...pos);
+  }
+
+  *glide(secs, x, y) {
+    const start = Date.now();
+    const [sx, sy] = [this.x, this.y];
+    while (true) {
+      const frac = (Date.now() - start) / 1000 / secs;
+      if (!(frac < 1)) break;
+      this.moveTo(sx + (x - sx) * frac, sy + (y - sy) * frac);
+      yield;
+    }
+    this.moveTo(x, y);
+  }
+
+  say(type, message) {
+    this.bubbleId++;
+    this.runtime.emit("say", this, type, $bubbleText(message));
+  }
+
+  *sayForSecs(type, message, secs) {
+    this.say(type, message);
+    const id = this.bubbleId;
+    yield* this.runtime.wait(secs);
+    // a newer bubble stays
+    if (this.bubbleId === id) this.say(type, "");
+  }
+
+  setCostume(index) {
+    if (!Number.isFinite(index) || this.costumes.length === 0) return;
+    this.costume = $wrapClamp(Math.round(index), 0, this.costumes.length - 1);
+    this.runtime.emit("costume", this);
+  }
+
+  // same as scratch-vm's `_setCostumeOrBackdrop`
+  switchCostume(value) {
+    if (typeof value === "number") return this.setCostume(value - 1);
+    const index = this.costumes.findIndex((c) => c.name === String(value));
+    if (index !== -1) return this.setCostume(index);
+    if (value === "next backdrop") return this.setCostume(this.costume + 1);
+    if (value === "previous backdrop") return this.setCostume(this.costume - 1);
+    if (value === "random backdrop") {
+      if (this.costumes.length < 2) return;
+      let other = Math.floor(Math.random() * (this.costumes.length - 1));
+      if (other >= this.costume) other++;
+      return this.setCostume(other);
+    }
+    if (!(Number.isNaN(Number(value)) || $isWhiteSpace(value))) this.setCostume(Number(value) - 1);
+  }
+
+  // scratch also keeps the costume within the stage, which needs a renderer
+  setSize(size) {
+    if (!Number.isFinite(size)) return;
+    this.size = Math.max(0, size);
+    this.runtime.emit("looks", this);
+  }
+
+  setEffect(effect, value) {
+    if (effect === "ghost") value = Math.max(0, Math.min(100, value));
+    if (effect === "brightness") value = Math.max(-100, Math.min(100, value));
+    this.effects[effect] = value;
+    this.runtime.emit("looks", this);
+  }
+
+  clearEffects() {
+    this.effects = $clearEffects();
+    this.runtime.emit("looks", this);
+  }
+
+  setVisible(visible) {
+    this.visible = visible;
+    this.runtime.emit("looks", this);
+  }
+
+  // same as scratch-vm's `_getSoundIndex`, -1 if there is no such sound
+  soundIndex(value) {
+    if (this.sounds.length === 0) return -1;
+    const index = this.sounds.findIndex((s) => s.name === value);
+    if (index !== -1) return index;
+    const oneIndexed = parseInt(value, 10);
+    if (Number.isNaN(oneIndexed)) return -1;
+    return $wrapClamp(oneIndexed - 1, 0, this.sounds.length - 1);
+  }
+
+  // the host plays `sound` on `play`, a sound already playing restarts
+  playSound(value) {
+    const sound = this.sounds[this.soundIndex(value)];
+    if (sound) this.runtime.emit("playSound", this, sound);
+    return sound;
+  }
+
+  *playSoundUntilDone(value) {
+    const sound = this.playSound(value);
+    if (!sound) return;
+    const stops = this.runtime.soundStops;
+    const end = Date.now() + (sound.sampleCount / sound.rate) * 1000;
+    do {
+      yield;
+    } while (Date.now() < end && stops === this.runtime.soundStops);
+  }
+
+  // the music extension numbers instruments and drums from 1, other numbers wrap around
+  setInstrument(value) {
+    this.instrument = $wrapClamp(Math.round($num(value)) - 1, 0, $INSTRUMENTS.length - 1);
+  }
+
+  *playNote(note, beats) {
+    beats = Math.max(0, Math.min(100, beats));
+    // unlike a drum, a note of 0 beats is silent and does not wait
+    if (beats === 0) return;
+    const secs = this.runtime.beatsToSecs(beats);
+    note = Math.max(0, Math.min(130, note));
+    this.runtime.emit("note", this, this.instrument, note, secs);
+    this.runtime.synth()?.playNote(this.instrument, note, secs, this.volume / 100);
+    yield* this.runtime.wait(secs);
+  }
+
+  *playDrum(value, beats) {
+    const drum = $wrapClamp(Math.round($num(value)) - 1, 0, $DRUMS.length - 1);
+    beats = Math.max(0, Math.min(100, beats));
+    this.runtime.emit("drum", this, drum);
+    this.runtime.synth()?.playDrum(drum, this.volume / 100);
+    yield* this.runtime.wait(this.runtime.beatsToSecs(beats));
+  }
+
+  setVolume(volume) {
+    if (Number.isNaN(volume)) return;
+    this.volume = Math.max(0, Math.min(100, volume));
+    this.runtime.emit("soundEffects", this);
+  }
+
+  setSoundEffect(effect, value) {
+    if (effect === "pitch") value = Math.max(-360, Math.min(360, value));
+    if (effect === "pan") value = Math.max(-100, Math.min(100, value));
+    if (Number.isNaN(value)) return;
+    this.soundEffects[effect] = value;
+    this.runtime.emit("soundEffects", this);
+  }
+
+  clearSoundEffects() {
+    this.soundEffects = { pitch: 0, pan: 0 };
+    this.runtime.emit("soundEffects", this);
+  }
+
+  // without a renderer the sprite is treated as a point
+  bounce() {
+    const { width, height } = this.runtime.bounds;
+    const dist = {
+      left: width / 2 + this.x,
+      right: width / 2 - this.x,
+      top: height / 2 - this.y,
+      bottom: height / 2 + this.y,
+    };
+    const nearest = Object.keys(dist).reduce((a, b) => (dist[a] <= dist[b] ? a : b));
+    if (dist[nearest] > 0) return;
+    const rad = ((90 - this.direction) * Math.PI) / 180;
+    let dx = Math.cos(rad);
+    let dy = -Math.sin(rad);
+    if (nearest === "left") dx = Math.max(0.2, Math.abs(dx));
+    if (nearest === "top") dy = Math.max(0.2, Math.abs(dy));
+    if (nearest === "right") dx = -Math.max(0.2, Math.abs(dx));
+    if (nearest === "bottom") dy = -Math.max(0.2, Math.abs(dy));
+    this.setDirection((Math.atan2(dy, dx) * 180) / Math.PI + 90);
+    this.moveTo(
+      Math.max(-width / 2, Math.min(width / 2, this.x)),
+      Math.max(-height / 2, Math.min(height / 2, this.y)),
+    );
+  }
+
+  // a host draws `penLine`s and `stamp`s with `penColor()` and `pen.size`
+  penColor() {
+    const { color, saturation, brightness, transparency } = this.pen;
+    const rgb = $hsvToRgb({ h: color * 3.6, s: saturation / 100, v: brightness / 100 });
+    return { ...rgb, a: 1 - transparency / 100 };
+  }
+
+  // putting the pen down draws a dot, even if it already was
+  setPenDown(down) {
+    this.pen.down = down;
+    if (down) this.runtime.emit("penLine", this, this.x, this.y, this.x, this.y);
+  }
+
+  setPenColor(value) {
+    const rgb = $rgb(value);
+    const hsv = $rgbToHsv(rgb);
+    this.pen.color = (hsv.h / 360) * 100;
+    this.pen.saturation = hsv.s * 100;
+    this.pen.brightness = hsv.v * 100;
+    this.pen.transparency = "a" in rgb ? 100 * (1 - rgb.a / 255) : 0;
+    this.pen.shade = this.pen.brightness / 2;
+  }
+
+  // `change` adds to the current value, unknown params are ignored like in scratch-vm
+  setPenColorParam(param, value, change = false) {
+    if (!["color", "saturation", "brightness", "transparency"].includes(param)) return;
+    if (change) value += this.pen[param];
+    this.pen[param] =
+      param === "color" ? $wrapClamp(value, 0, 100) : Math.max(0, Math.min(100, value));
+  }
+
+  setPenSize(size) {
+    this.pen.size = Math.max(1, Math.min(1200, size));
+  }
+
+  setPenShade(shade) {
+    shade %= 200;
+    if (shade < 0) shade += 200;
+    this.pen.shade = shade;
+    this.legacyPenColor();
+  }
+
+  setPenHue(hue, change = false) {
+    this.setPenColorParam("color", hue / 2, change);
+    if (!change) this.pen.transparency = 0;
+    this.legacyPenColor();
+  }
+
+  // scratch 2 mixed the hue with black or white depending on the shade
+  legacyPenColor() {
+    let rgb = $hsvToRgb({ h: this.pen.color * 3.6, s: 1, v: 1 });
+    const shade = this.pen.shade > 100 ? 200 - this.pen.shade : this.pen.shade;
+    if (shade < 50) {
+      rgb = $mixRgb({ r: 0, g: 0, b: 0 }, rgb, (10 + shade) / 60);
+    } else {
+      rgb = $mixRgb(rgb, { r: 255, g: 255, b: 255 }, (shade - 50) / 60);
+    }
+    const hsv = $rgbToHsv(rgb);
+    this.pen.color = (hsv.h / 360) * 100;
+    this.pen.saturation = hsv.s * 100;
+    this.pen.brightness = hsv.v * 100;
+  }
+}
+
+// the instruments of the music extension: how long a note fades out after it ends, and
+// whether its sample loops from `$LOOP_START` to hold long notes
+const $INSTRUMENTS = [
+  [0.5, false], // piano
+  [0.5, false], // electric piano
+  [0.2, true], // organ
+  [0.5, false], // guitar
+  [0.5, false], // electric guitar
+  [0.25, false], // bass
+  [0.25, false], // pizzicato
+  [0.1, true], // cello
+  [0.1, true], // trombone
+  [0.1, true], // clarinet
+  [0.1, true], // saxophone
+  [0.1, true], // flute
+  [0.1, true], // wooden flute
+  [0.1, true], // bassoon
+  [0.25, true], // choir
+  [0.5, false], // vibraphone
+  [0.25, false], // music box
+  [0.25, false], // steel drum
+  [0.1, false], // marimba
+  [0.1, true], // synth lead
+  [1, true], // synth pad
+];
+
+const $DRUMS = [
+  "snare drum",
+  "bass drum",
+  "side stick",
+  "crash cymbal",
+  "open hi-hat",
+  "closed hi-hat",
+  "tambourine",
+  "hand clap",
+  "claves",
+  "wood block",
+  "cowbell",
+  "triangle",
+  "bongo",
+  "conga",
+  "cabasa",
+  "guiro",
+  "vibraslap",
+  "cuica",
+];
+
+// the note every instrument sample is recorded at, and where sustained ones start to loop
+const $SAMPLE_NOTE = 60;
+const $LOOP_START = 0.4;
+
+// reads a PCM wav sample synchronously, unlike `decodeAudioData`, so the first note plays
+function $decodeWav(context, base64) {
+  const bytes = Uint8Array.from(atob(base64), (c) => c.charCodeAt(0));
+  const view = new DataView(bytes.buffer);
+  let channels = 1;
+  let rate = 0;
+  let bits = 8;
+  for (let pos = 12; pos + 8 <= bytes.length; ) {
+    const id = String.fromCharCode(...bytes.subarray(pos, pos + 4));
+    const size = view.getUint32(pos + 4, true);
+    if (id === "fmt ") {
+      channels = view.getUint16(pos + 10, true);
+      rate = view.getUint32(pos + 12, true);
+      bits = view.getUint16(pos + 22, true);
+    } else if (id === "data") {
+      // only the first channel is played
+      const frameSize = (bits / 8) * channels;
+      const buffer = context.createBuffer(1, Math.floor(size / frameSize), rate);
+      const data = buffer.getChannelData(0);
+      for (let i = 0; i < data.length; i++) {
+        const at = pos + 8 + i * frameSize;
+        data[i] = bits === 8 ? (bytes[at] - 128) / 128 : view.getInt16(at, true) / 32768;
+      }
+      return buffer;
+    }
+    pos += 8 + size + (size & 1);
+  }
+  return null;
+}
+
+// plays the bundled samples with WebAudio, at most 30 at once like scratch
+class $Synth {
+  constructor(context, samples) {
+    this.context = context;
+    this.voices = new Set();
+    // samples the project never plays are left out as `null`
+    this.instruments = samples.instruments.map((data) => data && $decodeWav(context, data));
+    this.drums = samples.drums.map((data) => data && $decodeWav(context, data));
+  }
+
+  play(buffer, volume) {
+    const source = this.context.createBufferSource();
+    source.buffer = buffer;
+    const gain = this.context.createGain();
+    gain.gain.value = volume;
+    source.connect(gain);
+    gain.connect(this.context.destination);
+    const voice = { source, gain };
+    this.voices.add(voice);
+    source.onended = () => {
+      gain.disconnect();
+      this.voices.delete(voice);
+    };
+    return voice;
+  }
+
+  // the sample is pitched to the note, then faded out once the note ends
+  playNote(instrument, note, secs, volume) {
+    const buffer = this.instruments[instrument];
+    if (!buffer || this.voices.size >= 30) return;
+    const [release, sustained] = $INSTRUMENTS[instrument];
+    const { source, gain } = this.play(buffer, volume);
+    source.playbackRate.value = 2 ** ((note - $SAMPLE_NOTE) / 12);
+    if (sustained) {
+      source.loop = true;
+      source.loopStart = $LOOP_START;
+      source.loopEnd = buffer.duration;
+    }
+    const end = this.context.currentTime + secs;
+    gain.gain.setValueAtTime(volume, end);
+    gain.gain.linearRampToValueAtTime(0, end + release);
+    source.start();
+    source.stop(end + release);
+  }
+
+  // drums always play their whole sample
+  playDrum(drum, volume) {
+    const buffer = this.drums[drum];
+    if (!buffer || this.voices.size >= 30) return;
+    this.play(buffer, volume).source.start();
+  }
+
+  stopAll() {
+    for (const { source } of this.voices) source.stop();
+  }
+}
+
+// blocks the compiler leaves to the runtime, by opcode, called with the target, the fields
+// and the evaluated inputs, substacks are generator functions to `yield*`; a host adds its own
+const $SHIMS = {
+  // the hidden counter blocks of scratch 2
+  control_incr_counter(target) {
+    this.counter++;
+  },
+  control_clear_counter(target) {
+    this.counter = 0;
+  },
+  control_get_counter(target) {
+    return this.counter;
+  },
+  sensing_loud(target) {
+    return this.loudness > 10;
+  },
+  sensing_userid(target) {
+    return "";
+  },
+  looks_hideallsprites(target) {
+    for (const t of this.targets) if (!t.isStage) t.setVisible(false);
+  },
+};
+
+class $Runtime {
+  constructor() {
+    this.targets = [];
+    this.threads = [];
+    // the thread being stepped
+    this.thread = null;
+    this.hats = {
+      flag: [],
+      key: [],
+      click: [],
+      broadcast: [],
+      backdrop: [],
+      greaterThan: [],
+      clone: [],
+    };
+    this.keys = new Set();
+    this.mouse = { x: 0, y: 0 };
+    this.mouseDown = false;
+    // pending `ask and wait`s, the first one is shown
+    this.questions = [];
+    this.lastAnswer = "";
+    this.username = "";
+    this.soundStops = 0;
+    // created on the first note, stays `null` without WebAudio or music samples
+    this.musicSynth = undefined;
+    // base64 wav files of the instruments and drums, set by projects using the music extension
+    this.musicSamples = null;
+    this.timerStart = Date.now();
+    // set by the host, -1 means no microphone like in scratch
+    this.loudness = -1;
+    this.bounds = { width: 480, height: 360 };
+    this.listeners = {};
+    this.shims = { ...$SHIMS };
+    this.counter = 0;
+    // opcodes without a shim, reported once each
+    this.missingShims = new Set();
+  }
+
+  // host hooks, e.g. a renderer listening on `move` or `penClear`
+  on(event, listener) {
+    (this.listeners[event] ||= []).push(listener);
+  }
+
+  emit(event, ...args) {
+    for (const listener of this.listeners[event] || []) listener(...args);
+  }
+
+  addTarget(target) {
+    this.targets.push(target);
+    return target;
+  }
+
+  whenGreenFlag(target, script) {
+    this.hats.flag.push([target, script]);
+  }
+
+  whenKeyPressed(target, key, script) {
+    this.hats.key.push([target, key, script]);
+  }
+
+  whenReceive(target, broadcast, script) {
+    this.hats.broadcast.push([target, broadcast, script]);
+  }
+
+  whenClicked(target, script) {
+    this.hats.click.push([target, script]);
+  }
+
+  whenBackdropSwitchesTo(target, backdrop, script) {
+    this.hats.backdrop.push([target, backdrop, script]);
+  }
+
+  // `threshold` is evaluated every frame, the script starts when the value rises above it
+  whenGreaterThan(target, menu, threshold, script) {
+    this.hats.greaterThan.push({ target, menu, threshold, script, above: false });
+  }
+
+  whenStartAsClone(target, script) {
+    this.hats.clone.push([target, script]);
+  }
+
+  // a script already running on `target` is restarted or left alone, depending on the hat
+  startScript(target, script, restart = false) {
+    const running = this.threads.find(
+      (t) => t.target === target && t.script === script && !t.stopped,
+    );
+    if (running) {
+      if (restart) {
+        running.gen = script(target);
+        running.warp = 0;
+      }
+      return running;
+    }
+    // `warp` counts the warp custom blocks on the call stack
+    const thread = { target, script, gen: script(target), stopped: false, warp: 0 };
+    this.threads.push(thread);
+    return thread;
+  }
+
+  // runs a hat registered on `sprite` for the sprite and all its clones
+  startHat(sprite, script, restart = false) {
+    return this.targets
+      .filter((t) => t.sprite === sprite)
+      .map((t) => this.startScript(t, script, restart));
+  }
+
+  *stopThisScript() {
+    this.thread.stopped = true;
+    yield;
+  }
+
+  stopOtherScripts(target) {
+    for (const thread of this.threads) {
+      if (thread.target === target && thread !== this.thread) thread.stopped = true;
+    }
+  }
+
+  // the stop sign, also used by the green flag
+  stopAll() {
+    for (const thread of this.threads) thread.stopped = true;
+    this.threads = [];
+    this.questions = [];
+    for (const target of this.targets.filter((t) => t.isClone)) this.deleteClone(target);
+    this.stopAllSounds();
+  }
+
+  // `name` is a sprite name or `_myself_`, clones are limited to 300 like in scratch
+  createClone(target, name) {
+    const sprite =
+      name === "_myself_"
+        ? target.sprite
+        : this.sprite(name);
+    if (!sprite || sprite.isStage) return;
+    if (this.targets.filter((t) => t.isClone).length >= 300) return;
+    const original = name === "_myself_" ? target : sprite;
+    const clone = original.clone();
+    // clones start right behind the target they were made from
+    this.targets.splice(this.targets.indexOf(original), 0, clone);
+    this.emit("clone", clone);
+    for (const [t, script] of this.hats.clone) {
+      if (t === sprite) this.startScript(clone, script);
+    }
+  }
+
+  deleteClone(clone) {
+    for (const thread of this.threads) {
+      if (thread.target === clone) thread.stopped = true;
+    }
+    this.targets.splice(this.targets.indexOf(clone), 1);
+    this.emit("deleteClone", clone);
+  }
+
+  *deleteThisClone(target) {
+    if (!target.isClone) return;
+    this.deleteClone(target);
+    yield;
+  }
+
+  // waits until all of `threads` are done
+  *waitFor(threads) {
+    while (threads.some((thread) => this.threads.includes(thread))) yield;
+  }
+
+  // yields at least once, like scratch's timed blocks
+  *wait(secs) {
+    const end = Date.now() + secs * 1000;
+    do {
+      yield;
+    } while (Date.now() < end);
+  }
+
+  // returns the threads of the `when backdrop switches to` hats it started
+  switchBackdrop(value) {
+    const stage = this.targets.find((t) => t.isStage);
+    stage.switchCostume(value);
+    const name = stage.costumes[stage.costume]?.name;
+    return this.hats.backdrop
+      .filter(([, backdrop]) => backdrop.toLowerCase() === name?.toLowerCase())
+      .flatMap(([target, , script]) => this.startHat(target, script));
+  }
+
+  stopAllSounds() {
+    this.soundStops++;
+    this.musicSynth?.stopAll();
+    this.emit("stopSounds");
+  }
+
+  synth() {
+    if (this.musicSynth === undefined) {
+      const AudioContext = globalThis.AudioContext ?? globalThis.webkitAudioContext;
+      this.musicSynth =
+        AudioContext && this.musicSamples
+          ? new $Synth(new AudioContext(), this.musicSamples)
+          : null;
+    }
+    return this.musicSynth;
+  }
+
+  beatsToSecs(beats) {
+    const stage = this.targets.find((t) => t.isStage);
+    return (60 / stage.tempo) * beats;
+  }
+
+  *rest(beats) {
+    yield* this.wait(this.beatsToSecs(Math.max(0, Math.min(100, beats))));
+  }
+
+  setTempo(tempo) {
+    const stage = this.targets.find((t) => t.isStage);
+    stage.tempo = Math.max(20, Math.min(500, tempo));
+  }
+
+  goToFront(target) {
+    this.setLayer(target, this.targets.length - 1);
+  }
+
+  goToBack(target) {
+    this.setLayer(target, 1);
+  }
+
+  goForward(target, layers) {
+    if (!Number.isFinite(layers)) return;
+    this.setLayer(target, this.targets.indexOf(target) + Math.round(layers));
+  }
+
+  // sprites are drawn in the order of `targets`, the stage always comes first
+  setLayer(target, index) {
+    this.targets.splice(this.targets.indexOf(target), 1);
+    index = Math.max(1, Math.min(this.targets.length, index));
+    this.targets.splice(index, 0, target);
+    this.emit("layer", target);
+  }
+
+  greenFlag() {
+    this.stopAll();
+    this.resetTimer();
+    for (const [target, script] of this.hats.flag) this.startScript(target, script, true);
+  }
+
+  timer() {
+    return (Date.now() - this.timerStart) / 1000;
+  }
+
+  resetTimer() {
+    this.timerStart = Date.now();
+  }
+
+  // host input, a click on a sprite or the stage
+  click(target) {
+    for (const [t, script] of this.hats.click) {
+      if (t === target.sprite) this.startScript(target, script, true);
+    }
+  }
+
+  mouseMove(x, y) {
+    this.mouse = { x, y };
+  }
+
+  mouseButton(down) {
+    this.mouseDown = down;
+  }
+
+  // `name` is the `$id` of a variable or list owned by `target`
+  setMonitorVisible(target, name, visible) {
+    this.emit("monitor", target, name, visible);
+  }
+
+  // a block left to the runtime, a shim of a stack block may be a generator function
+  *fallback(target, opcode, fields, inputs) {
+    const shim = this.shim(opcode);
+    const result = shim?.call(this, target, fields, inputs);
+    if (typeof result?.next === "function") yield* result;
+  }
+
+  // an unknown reporter is `0`, like the parser's stub
+  fallbackReporter(target, opcode, fields, inputs) {
+    return this.shim(opcode)?.call(this, target, fields, inputs) ?? 0;
+  }
+
+  shim(opcode) {
+    const shim = this.shims[opcode];
+    if (!shim && !this.missingShims.has(opcode)) {
+      this.missingShims.add(opcode);
+      this.emit("unsupported", opcode);
+    }
+    return shim;
+  }
+
+  // the original sprite called `name`, never a clone
+  sprite(name) {
+    return this.targets.find((t) => !t.isStage && !t.isClone && t.name === name);
+  }
+
+  // where `go to` and friends head for a menu value, `null` for unknown sprites
+  position(name) {
+    const { width, height } = this.bounds;
+    if (name === "_mouse_") return [this.mouse.x, this.mouse.y];
+    if (name === "_random_") {
+      return [
+        Math.round(width * (Math.random() - 0.5)),
+        Math.round(height * (Math.random() - 0.5)),
+      ];
+    }
+    const target = this.sprite(name);
+    return target ? [target.x, target.y] : null;
+  }
+
+  // a renderer replaces the touching checks with pixel exact ones, without one only the
+  // position of a sprite is known
+  isTouching(target, name) {
+    if (target.isStage || name !== "_edge_") return false;
+    const { width, height } = this.bounds;
+    return Math.abs(target.x) >= width / 2 || Math.abs(target.y) >= height / 2;
+  }
+
+  isTouchingColor(target, color) {
+    return false;
+  }
+
+  isColorTouchingColor(target, color, color2) {
+    return false;
+  }
+
+  distanceTo(target, name) {
+    if (target.isStage) return 10000;
+    const other = name === "_mouse_" ? this.mouse : this.sprite(name);
+    if (!other) return 10000;
+    return Math.sqrt((target.x - other.x) ** 2 + (target.y - other.y) ** 2);
+  }
+
+  // `property` is an attribute name of `sensing_of` or the `$id` of a variable
+  sensingOf(name, property) {
+    const target = name === "_stage_" ? this.targets.find((t) => t.isStage) : this.sprite(name);
+    if (!target) return 0;
+    if (property.startsWith("$")) return target[property] ?? 0;
+    const costume = target.costumes[target.costume]?.name ?? "";
+    if (target.isStage) {
+      switch (property) {
+        case "backdrop #":
+          return target.costume + 1;
+        case "backdrop name":
+          return costume;
+        case "volume":
+          return target.volume;
+      }
+      return 0;
+    }
+    switch (property) {
+      case "x position":
+        return target.x;
+      case "y position":
+        return target.y;
+      case "direction":
+        return target.direction;
+      case "costume #":
+        return target.costume + 1;
+      case "costume name":
+        return costume;
+      case "size":
+        return target.size;
+      case "volume":
+        return target.volume;
+    }
+    return 0;
+  }
+
+  // a variable the compiler could not resolve, scratch-vm looks it up by name too
+  sensingOfVariable(name, variable) {
+    const target = name === "_stage_" ? this.targets.find((t) => t.isStage) : this.sprite(name);
+    const property = target?.variableNames[variable];
+    return property ? target[property] : 0;
+  }
+
+  // questions are asked one at a time, the host shows a prompt on `ask` and calls `answer`
+  *ask(target, question) {
+    const ask = { answered: false };
+    this.questions.push(ask);
+    while (this.questions[0] !== ask) yield;
+    // visible sprites say the question, the prompt stays empty then
+    const bubble = !target.isStage && target.visible;
+    if (bubble) target.say("say", question);
+    this.emit("ask", bubble ? "" : question);
+    while (!ask.answered) yield;
+    if (bubble) target.say("say", "");
+  }
+
+  answer(text) {
+    const ask = this.questions.shift();
+    if (!ask) return;
+    this.lastAnswer = String(text);
+    ask.answered = true;
+  }
+
+  // host input, `key` may be a scratch key name or a `KeyboardEvent.key`
+  keyDown(key) {
+    key = $key($BROWSER_KEYS[key] ?? key);
+    this.keys.add(key);
+    for (const [target, k, script] of this.hats.key) {
+      if (k === key || k === "any") this.startHat(target, script);
+    }
+  }
+
+  keyUp(key) {
+    this.keys.delete($key($BROWSER_KEYS[key] ?? key));
+  }
+
+  isKeyPressed(key) {
+    key = $key(key);
+    return key === "any" ? this.keys.size > 0 : this.keys.has(key);
+  }
+
+  // broadcast names are case insensitive, returns the threads it started
+  broadcast(name) {
+    name = String(name).toLowerCase();
+    return this.hats.broadcast
+      .filter(([, b]) => b.toLowerCase() === name)
+      .flatMap(([target, , script]) => this.startHat(target, script, true));
+  }
+
+  *broadcastAndWait(name) {
+    yield* this.waitFor(this.broadcast(name));
+  }
+
+  startEdgeHats() {
+    for (const hat of this.hats.greaterThan) {
+      const value = hat.menu === "timer" ? this.timer() : this.loudness;
+      const above = value > hat.threshold(hat.target);
+      if (above && !hat.above) this.startHat(hat.target, hat.script);
+      hat.above = above;
+    }
+  }
+
+  step() {
+    this.startEdgeHats();
+    // threads started during this step run from the next one
+    for (const thread of [...this.threads]) {
+      if (thread.stopped) continue;
+      this.thread = thread;
+      const gen = thread.gen;
+      // a thread restarted while running is kept
+      if (gen.next().done && thread.gen === gen) thread.stopped = true;
+    }
+    this.thread = null;
+    this.threads = this.threads.filter((thread) => !thread.stopped);
+  }
+
+  start(fps = 30) {
+    this.greenFlag();
+    return setInterval(() => this.step(), 1000 / fps);
+  }
+}
@@ -0,0 +1,139 @@
+// every test binary uses its own part of these helpers
+#![allow(dead_code)]
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use scratch_codegen::js::JsCodegen;
+use scratch_loader::sb3::{Project, Sb3File};
+use scratch_parser::parser::Sb3FormatParser;
+use serde_json::json;
+
+// the js generated for `scratch-parser/tests/fixtures/<name>.json`, assets are left out
+pub fn fixture(name: &str) -> String {
+    let path = format!(
+        "{}/../scratch-parser/tests/fixtures/{}.json",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    let project: Project = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+    generate(project)
+}
+
+// the js generated for `blocks` as the scripts of a sprite next to an empty stage
+pub fn sprite(blocks: serde_json::Value) -> String {
+    sprite_with(json!({ "blocks": blocks }), &[])
+}
+
+// like `sprite`, with the fields of `target` replacing the sprite's own
+pub fn sprite_with(target: serde_json::Value, extensions: &[&str]) -> String {
+    let mut project = json!({
+        "targets": [
+            {
+                "isStage": true,
+                "name": "Stage",
+                "blocks": {},
+                "costumes": [],
+                "currentCostume": 0,
+                "sounds": [],
+                "volume": 100,
+                "layerOrder": 0
+            },
+            {
+                "isStage": false,
+                "name": "Sprite1",
+                "blocks": {},
+                "costumes": [],
+                "currentCostume": 0,
+                "sounds": [],
+                "volume": 100,
+                "layerOrder": 1,
+                "x": 0,
+                "y": 0,
+                "size": 100,
+                "direction": 90,
+                "visible": true,
+                "draggable": false,
+                "rotationStyle": "all around"
+            }
+        ],
+        "extensions": extensions,
+        "meta": { "semver": "3.0.0" }
+    });
+    let sprite = project["targets"][1].as_object_mut().unwrap();
+    for (key, value) in target.as_object().unwrap() {
+        sprite.insert(key.clone(), value.clone());
+    }
+    generate(serde_json::from_value(project).unwrap())
+}
+
+fn generate(project: Project) -> String {
+    let src = Sb3File {
+        resources: HashMap::new(),
+        project,
+    };
+    let proj = Sb3FormatParser::new(src).parse().unwrap();
+    JsCodegen::new(&proj).generate()
+}
+
+// the generated statements, without the bundled runtime and indentation
+pub fn lines(js: &str) -> Vec<&str> {
+    let start = js.find("const $runtime = new $Runtime();").unwrap();
+    js[start..].lines().map(str::trim).collect()
+}
+
+pub fn has_line(js: &str, line: &str) -> bool {
+    lines(js).contains(&line)
+}
+
+fn temp_module(js: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "scratch-codegen-{}-{}.mjs",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&path, js).unwrap();
+    path
+}
+
+// runs node with `args`, `None` when node is not installed
+fn node(args: &[&Path]) -> Option<String> {
+    let output = match Command::new("node").args(args).output() {
+        Ok(output) => output,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(err) => panic!("{}", err),
+    };
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Some(String::from_utf8(output.stdout).unwrap())
+}
+
+// `node --check` the module, skipped when node is not installed
+pub fn check_syntax(js: &str) {
+    let path = temp_module(js);
+    node(&["--check".as_ref(), &path]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+// runs `driver` with the generated runtime imported as `rt`, returns what it logged
+pub fn run(js: &str, driver: &str) -> Option<String> {
+    let module = temp_module(js);
+    let driver = format!(
+        "import rt from {};\n{}\n",
+        serde_json::to_string(&module.to_str().unwrap()).unwrap(),
+        driver
+    );
+    let driver = temp_module(&driver);
+    let output = node(&[&driver]);
+    std::fs::remove_file(&module).unwrap();
+    std::fs::remove_file(&driver).unwrap();
+    output.map(|out| out.trim_end().to_string())
+}
@@ -0,0 +1,198 @@
+mod common;
+
+use common::{check_syntax, fixture, has_line, run, sprite};
+use serde_json::json;
+
+// the generated module of `name` is valid js and contains every line of `expected`
+fn generates(name: &str, expected: &[&str]) {
+    let js = fixture(name);
+    for line in expected {
+        assert!(has_line(&js, line), "`{}` not generated for {}", line, name);
+    }
+    check_syntax(&js);
+}
+
+#[test]
+fn motion() {
+    generates(
+        "motion",
+        &[
+            "$t.move(10);",
+            "$t.turn(15);",
+            "$t.moveTo(1, 2);",
+            "yield* $t.glideTo(1, \"Sprite2\");",
+            "$t.moveTo($t.x + 5, $t.y);",
+            "$t.rotationStyle = \"left-right\";",
+        ],
+    );
+}
+
+#[test]
+fn looks() {
+    generates(
+        "looks",
+        &[
+            "yield* $t.sayForSecs(\"say\", \"Hmm\", 2);",
+            "$runtime.switchBackdrop(\"random backdrop\");",
+            "yield* $runtime.waitFor($runtime.switchBackdrop(\"next backdrop\"));",
+            "$t.setEffect(\"ghost\", 50);",
+            "$runtime.goForward($t, 1);",
+        ],
+    );
+}
+
+#[test]
+fn sound() {
+    generates(
+        "sound",
+        &[
+            "yield* $t.playSoundUntilDone(1);",
+            "$t.setVolume($t.volume + -10);",
+            "$t.setSoundEffect(\"pan\", -100);",
+        ],
+    );
+}
+
+#[test]
+fn events() {
+    generates(
+        "events",
+        &[
+            "const $13 = \"message1\";",
+            "$runtime.whenReceive($stage, $13, function* ($t) {",
+            "$runtime.whenGreaterThan($sprite0, \"timer\", ($t) => 0.05, function* ($t) {",
+            "yield* $runtime.broadcastAndWait($str(Math.round($t.size)));",
+        ],
+    );
+}
+
+#[test]
+fn control() {
+    generates(
+        "control",
+        &[
+            "for (let $i = Math.round(3); $i > 0; $i--) {",
+            "for (let $i = 1; $i <= 4; $i++) {",
+            "while (!$runtime.isKeyPressed(\"B\")) yield;",
+            "$runtime.createClone($t, \"_myself_\");",
+            "$runtime.stopOtherScripts($t);",
+        ],
+    );
+}
+
+#[test]
+fn operators() {
+    generates(
+        "operators",
+        &[
+            "$t.say(\"say\", $mod(-7, 3));",
+            "$t.say(\"say\", $letterOf(2, \"hello\"));",
+            "$t.say(\"say\", ($cmp(\"1\", \"10\") < 0));",
+            "$t.move($num((0 / 0)));",
+        ],
+    );
+}
+
+#[test]
+fn sensing() {
+    generates(
+        "sensing",
+        &[
+            "yield* $runtime.ask($t, \"name?\");",
+            "$t.say(\"say\", $runtime.sensingOf(\"Sprite2\", \"$37\"));",
+            "$t.say(\"say\", $runtime.sensingOf(\"_stage_\", \"$33\"));",
+            "$t.say(\"say\", $runtime.isTouching($t, \"_edge_\"));",
+            "$t.say(\"say\", $runtime.sensingOfVariable(\"Sprite2\", \"missing\"));",
+            "$sprite1.variableNames = { \"hp\": \"$37\" };",
+        ],
+    );
+}
+
+#[test]
+fn sensing_variable_by_name() {
+    let js = fixture("sensing");
+    let driver = "console.log(rt.sensingOfVariable('Sprite2', 'hp'), rt.sensingOfVariable('_stage_', 'score'), rt.sensingOfVariable('Sprite2', 'missing'));";
+    if let Some(out) = run(&js, driver) {
+        assert_eq!(out, "7 99 0");
+    }
+}
+
+#[test]
+fn data() {
+    generates(
+        "data",
+        &[
+            "$sprite0.$9 = []; // items",
+            "$t.$8 = $num($t.$8) + 2;",
+            "$listInsert($t.$9, \"last\", \"q\");",
+            "$stage.$6.length = 0;",
+            "$runtime.setMonitorVisible($stage, \"$5\", false);",
+            "$t.say(\"say\", $listItem($stage.$6, \"random\"));",
+        ],
+    );
+}
+
+#[test]
+fn pen() {
+    generates(
+        "pen",
+        &[
+            "$runtime.emit(\"penClear\");",
+            "$t.setPenDown(true);",
+            "$t.setPenColorParam(\"color\", 110, true);",
+            "$t.setPenShade($t.pen.shade + -60);",
+        ],
+    );
+}
+
+#[test]
+fn music() {
+    generates(
+        "music",
+        &[
+            "$stage.init({ costume: 0, volume: 100, tempo: 120 });",
+            "yield* $t.playDrum(1, 0.25);",
+            "yield* $runtime.rest(0.5);",
+            "$runtime.setTempo($stage.tempo + -40);",
+        ],
+    );
+}
+
+#[test]
+fn unsupported() {
+    generates(
+        "unsupported",
+        &[
+            "// unsupported block `control_incr_counter`",
+            "// unsupported block `foo_repeatFast`",
+            "$t.say(\"say\", 0);",
+        ],
+    );
+
+    // the opcode is written into a comment, a line terminator in it must not end the comment
+    let js = sprite(json!({
+        "flag": {
+            "opcode": "event_whenflagclicked",
+            "next": "odd",
+            "parent": null,
+            "inputs": {},
+            "fields": {},
+            "topLevel": true,
+            "x": 0,
+            "y": 0
+        },
+        "odd": {
+            "opcode": "control_odd\n}) => {\u{2028}}) => {",
+            "next": null,
+            "parent": "flag",
+            "inputs": {},
+            "fields": {},
+            "topLevel": false
+        }
+    }));
+    assert!(has_line(
+        &js,
+        "// unsupported block `control_odd }) => { }) => {`"
+    ));
+    check_syntax(&js);
+}
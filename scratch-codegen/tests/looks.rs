@@ -0,0 +1,30 @@
+mod common;
+
+use common::{check_syntax, has_line, sprite};
+use serde_json::json;
+
+#[test]
+fn go_backward_negative() {
+    let js = sprite(json!({
+        "flag": {
+            "opcode": "event_whenflagclicked",
+            "next": "layers",
+            "parent": null,
+            "inputs": {},
+            "fields": {},
+            "topLevel": true,
+            "x": 0,
+            "y": 0
+        },
+        "layers": {
+            "opcode": "looks_goforwardbackwardlayers",
+            "next": null,
+            "parent": "flag",
+            "inputs": { "NUM": [1, [7, "-1"]] },
+            "fields": { "FORWARD_BACKWARD": ["backward", null] },
+            "topLevel": false
+        }
+    }));
+    assert!(has_line(&js, "$runtime.goForward($t, -(-1));"));
+    check_syntax(&js);
+}
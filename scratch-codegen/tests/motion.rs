@@ -0,0 +1,30 @@
+mod common;
+
+use common::{check_syntax, has_line, sprite};
+use serde_json::json;
+
+#[test]
+fn turn_left_negative() {
+    let js = sprite(json!({
+        "flag": {
+            "opcode": "event_whenflagclicked",
+            "next": "turn",
+            "parent": null,
+            "inputs": {},
+            "fields": {},
+            "topLevel": true,
+            "x": 0,
+            "y": 0
+        },
+        "turn": {
+            "opcode": "motion_turnleft",
+            "next": null,
+            "parent": "flag",
+            "inputs": { "DEGREES": [1, [4, "-15"]] },
+            "fields": {},
+            "topLevel": false
+        }
+    }));
+    assert!(has_line(&js, "$t.turn(-(-15));"));
+    check_syntax(&js);
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::{check_syntax, fixture, has_line, lines, run, sprite_with};
+use serde_json::json;
+
+// a WebAudio stand-in that logs every sample started
+const AUDIO_CONTEXT: &str = "
+const played = [];
+const node = () => ({ connect() {}, disconnect() {} });
+globalThis.AudioContext = class {
+  currentTime = 0;
+  destination = node();
+  createBuffer(channels, length, rate) {
+    const data = new Float32Array(length);
+    return { duration: length / rate, getChannelData: () => data };
+  }
+  createGain() {
+    return { ...node(), gain: { value: 1, setValueAtTime() {}, linearRampToValueAtTime() {} } };
+  }
+  createBufferSource() {
+    const source = { ...node(), playbackRate: { value: 1 }, loop: false };
+    source.start = () => played.push(source);
+    source.stop = (at) => (source.stopAt = at);
+    return source;
+  }
+};
+";
+
+#[test]
+fn samples_bundled_with_music() {
+    let js = fixture("music");
+    assert!(has_line(&js, "$runtime.musicSamples = {"));
+    check_syntax(&js);
+    assert!(!has_line(&fixture("pen"), "$runtime.musicSamples = {"));
+}
+
+// indices of the bundled entries of the `instruments` or `drums` samples, others are `null`
+fn bundled(js: &str, name: &str) -> Vec<usize> {
+    let head = format!("{}: [", name);
+    lines(js)
+        .into_iter()
+        .skip_while(|line| *line != head)
+        .skip(1)
+        .take_while(|line| *line != "],")
+        .enumerate()
+        .filter(|(_, line)| *line != "null,")
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+#[test]
+fn only_played_samples_bundled() {
+    // the fixture plays the snare drum, its instrument is computed so it may be any
+    let js = fixture("music");
+    assert_eq!(bundled(&js, "instruments"), (0..21).collect::<Vec<_>>());
+    assert_eq!(bundled(&js, "drums"), [0]);
+
+    let js = sprite_with(
+        json!({
+            "blocks": {
+                "flag": {
+                    "opcode": "event_whenflagclicked", "next": "set", "parent": null,
+                    "inputs": {}, "fields": {}, "topLevel": true, "x": 0, "y": 0
+                },
+                "set": {
+                    "opcode": "music_setInstrument", "next": "play", "parent": "flag",
+                    "inputs": { "INSTRUMENT": [1, "menu"] }, "fields": {}, "topLevel": false
+                },
+                "menu": {
+                    "opcode": "music_menu_INSTRUMENT", "next": null, "parent": "set",
+                    "inputs": {}, "fields": { "INSTRUMENT": ["8", null] },
+                    "shadow": true, "topLevel": false
+                },
+                "play": {
+                    "opcode": "music_playNoteForBeats", "next": null, "parent": "set",
+                    "inputs": { "NOTE": [1, [4, "60"]], "BEATS": [1, [4, "1"]] },
+                    "fields": {}, "topLevel": false
+                }
+            }
+        }),
+        &["music"],
+    );
+    // the piano plays until the instrument is set
+    assert_eq!(bundled(&js, "instruments"), [0, 7]);
+    assert!(bundled(&js, "drums").is_empty());
+    assert!(has_line(&js, "null,"));
+    check_syntax(&js);
+}
+
+#[test]
+fn samples_played() {
+    let js = fixture("music");
+    let driver = format!(
+        "{}
+        rt.greenFlag();
+        while (rt.threads.length) {{
+          rt.step();
+          await new Promise((resolve) => setTimeout(resolve, 5));
+        }}
+        const max = (buffer) => Math.max(...buffer.getChannelData(0).map(Math.abs));
+        console.log(played.map((s) => [
+          s.buffer.duration.toFixed(2),
+          s.playbackRate.value,
+          s.loop ? s.loopStart : '-',
+          s.stopAt?.toFixed(3) ?? '-',
+          max(s.buffer) > 0.5,
+        ].join(' ')).join(' | '));",
+        AUDIO_CONTEXT
+    );
+    if let Some(out) = run(&js, &driver) {
+        // the snare drum plays whole, the organ note loops and fades out after 0.075 seconds
+        assert_eq!(out, "0.20 1 - - true | 0.67 1 0.4 0.275 true");
+    }
+}
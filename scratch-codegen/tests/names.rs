@@ -0,0 +1,59 @@
+mod common;
+
+use common::{check_syntax, lines, sprite_with};
+use serde_json::json;
+
+// text that breaks the module if it ever leaves a `//` comment
+const INJECTED: &str = "}) => {";
+
+#[test]
+fn line_terminators_stay_in_comments() {
+    let js = sprite_with(
+        json!({
+            "variables": {
+                "var": [format!("a\n{}", INJECTED), 0],
+                "var2": [format!("b\r{}", INJECTED), "x"]
+            },
+            "lists": {
+                "list": [format!("c\u{2028}{}", INJECTED), [1, 2]],
+                "list2": [format!("d\u{2029}{}", INJECTED), []]
+            },
+            "blocks": {
+                "def": {
+                    "opcode": "procedures_definition",
+                    "next": null,
+                    "parent": null,
+                    "inputs": { "custom_block": [1, "proto"] },
+                    "fields": {},
+                    "topLevel": true,
+                    "x": 0,
+                    "y": 0
+                },
+                "proto": {
+                    "opcode": "procedures_prototype",
+                    "next": null,
+                    "parent": "def",
+                    "inputs": {},
+                    "fields": {},
+                    "shadow": true,
+                    "topLevel": false,
+                    "mutation": {
+                        "tagName": "mutation",
+                        "children": [],
+                        "proccode": format!("e\n{}", INJECTED),
+                        "argumentids": "[]",
+                        "argumentnames": "[]",
+                        "argumentdefaults": "[]",
+                        "warp": "false"
+                    }
+                }
+            }
+        }),
+        &[],
+    );
+    for line in lines(&js) {
+        assert!(!line.starts_with(INJECTED), "{}", line);
+        assert!(!line.contains(['\r', '\u{2028}', '\u{2029}']), "{:?}", line);
+    }
+    check_syntax(&js);
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use common::{check_syntax, has_line, run, sprite};
+use serde_json::{json, Map, Value};
+
+fn mutation(proccode: &str, warp: bool) -> Value {
+    json!({
+        "tagName": "mutation",
+        "children": [],
+        "proccode": proccode,
+        "argumentids": "[]",
+        "argumentnames": "[]",
+        "argumentdefaults": "[]",
+        "warp": warp.to_string()
+    })
+}
+
+// a custom block `proccode` whose body is the blocks from `next` on
+fn define(blocks: &mut Map<String, Value>, proccode: &str, warp: bool, next: &str, y: i32) {
+    blocks.insert(
+        format!("{}_def", proccode),
+        json!({
+            "opcode": "procedures_definition",
+            "next": next,
+            "parent": null,
+            "inputs": { "custom_block": [1, format!("{}_proto", proccode)] },
+            "fields": {},
+            "topLevel": true,
+            "x": 0,
+            "y": y
+        }),
+    );
+    blocks.insert(
+        format!("{}_proto", proccode),
+        json!({
+            "opcode": "procedures_prototype",
+            "next": null,
+            "parent": format!("{}_def", proccode),
+            "inputs": {},
+            "fields": {},
+            "shadow": true,
+            "topLevel": false,
+            "mutation": mutation(proccode, warp)
+        }),
+    );
+}
+
+fn call(proccode: &str, parent: &str, next: Option<&str>) -> Value {
+    json!({
+        "opcode": "procedures_call",
+        "next": next,
+        "parent": parent,
+        "inputs": {},
+        "fields": {},
+        "topLevel": false,
+        "mutation": mutation(proccode, false)
+    })
+}
+
+fn flag(blocks: &mut Map<String, Value>, next: &str) {
+    blocks.insert(
+        "flag".into(),
+        json!({
+            "opcode": "event_whenflagclicked",
+            "next": next,
+            "parent": null,
+            "inputs": {},
+            "fields": {},
+            "topLevel": true,
+            "x": 0,
+            "y": 0
+        }),
+    );
+}
+
+fn change_x(parent: &str, dx: &str) -> Value {
+    json!({
+        "opcode": "motion_changexby",
+        "next": null,
+        "parent": parent,
+        "inputs": { "DX": [1, [4, dx]] },
+        "fields": {},
+        "topLevel": false
+    })
+}
+
+// `when flag clicked, fast` where `fast` calls `slow`, which moves in a loop
+fn call_tree(warp: bool) -> String {
+    let mut blocks = Map::new();
+    flag(&mut blocks, "call_fast");
+    blocks.insert("call_fast".into(), call("fast", "flag", None));
+    define(&mut blocks, "fast", warp, "call_slow", 100);
+    blocks.insert("call_slow".into(), call("slow", "fast_def", None));
+    define(&mut blocks, "slow", false, "repeat", 200);
+    blocks.insert(
+        "repeat".into(),
+        json!({
+            "opcode": "control_repeat",
+            "next": null,
+            "parent": "slow_def",
+            "inputs": { "TIMES": [1, [6, "10"]], "SUBSTACK": [2, "move"] },
+            "fields": {},
+            "topLevel": false
+        }),
+    );
+    blocks.insert("move".into(), change_x("repeat", "1"));
+    sprite(Value::Object(blocks))
+}
+
+#[test]
+fn warp_applies_to_called_blocks() {
+    let js = call_tree(true);
+    assert!(has_line(&js, "$runtime.thread.warp++;"));
+    assert!(has_line(&js, "if (!$runtime.thread.warp) yield;"));
+    check_syntax(&js);
+    let driver = "rt.greenFlag(); rt.step(); console.log(rt.targets[1].x);";
+    if let Some(out) = run(&js, driver) {
+        assert_eq!(out, "10");
+    }
+}
+
+#[test]
+fn called_blocks_yield_without_warp() {
+    let js = call_tree(false);
+    assert!(!has_line(&js, "$runtime.thread.warp++;"));
+    let driver = "rt.greenFlag(); rt.step(); console.log(rt.targets[1].x, rt.threads[0].warp);";
+    if let Some(out) = run(&js, driver) {
+        assert_eq!(out, "1 0");
+    }
+}
+
+#[test]
+fn stop_this_script_returns_from_block() {
+    let mut blocks = Map::new();
+    flag(&mut blocks, "call_jump");
+    blocks.insert("call_jump".into(), call("jump", "flag", Some("move")));
+    blocks.insert("move".into(), change_x("call_jump", "5"));
+    define(&mut blocks, "jump", false, "stop", 100);
+    blocks.insert(
+        "stop".into(),
+        json!({
+            "opcode": "control_stop",
+            "next": null,
+            "parent": "jump_def",
+            "inputs": {},
+            "fields": { "STOP_OPTION": ["this script", null] },
+            "topLevel": false,
+            "mutation": { "tagName": "mutation", "children": [], "hasnext": "false" }
+        }),
+    );
+    let js = sprite(Value::Object(blocks));
+    assert!(has_line(&js, "return;"));
+    assert!(!has_line(&js, "yield* $runtime.stopThisScript();"));
+    check_syntax(&js);
+    let driver = "rt.greenFlag(); rt.step(); rt.step(); console.log(rt.targets[1].x);";
+    if let Some(out) = run(&js, driver) {
+        assert_eq!(out, "5");
+    }
+}
@@ -0,0 +1,194 @@
+// conversions between scratch values, matching scratch-vm's `Cast` to the letter.
+// scratch leans on javascript's `Number()` and `String()`, so those are reimplemented here
+
+use std::cmp::Ordering;
+
+use crate::sb3::{Num, ScratchValue};
+
+// `Cast.toNumber`, anything that isn't a number is 0
+pub fn to_number(val: &ScratchValue) -> Num {
+    let num = match val {
+        ScratchValue::Num(num) => *num,
+        ScratchValue::Bool(b) => *b as u8 as Num,
+        ScratchValue::Str(s) => str_to_number(s),
+    };
+    if num.is_nan() {
+        0.0
+    } else {
+        num
+    }
+}
+
+// `Cast.toBoolean`
+pub fn to_boolean(val: &ScratchValue) -> bool {
+    match val {
+        ScratchValue::Bool(b) => *b,
+        ScratchValue::Str(s) => !(s.is_empty() || s == "0" || s.to_lowercase() == "false"),
+        ScratchValue::Num(num) => !(*num == 0.0 || num.is_nan()),
+    }
+}
+
+// `Cast.toString`
+pub fn to_string(val: &ScratchValue) -> String {
+    match val {
+        ScratchValue::Str(s) => s.clone(),
+        ScratchValue::Num(num) => number_to_string(*num),
+        ScratchValue::Bool(b) => b.to_string(),
+    }
+}
+
+// `Cast.compare`, numerically if both sides look like numbers, otherwise case-insensitively
+pub fn compare(a: &ScratchValue, b: &ScratchValue) -> Ordering {
+    let mut n1 = js_number(a);
+    let mut n2 = js_number(b);
+    if n1 == 0.0 && is_white_space(a) {
+        n1 = Num::NAN;
+    } else if n2 == 0.0 && is_white_space(b) {
+        n2 = Num::NAN;
+    }
+    if n1.is_nan() || n2.is_nan() {
+        let s1 = to_string(a).to_lowercase();
+        let s2 = to_string(b).to_lowercase();
+        return cmp_utf16(&s1, &s2);
+    }
+    // also covers equal infinities, which `n1 - n2` would turn into NaN
+    n1.partial_cmp(&n2).unwrap_or(Ordering::Equal)
+}
+
+fn is_white_space(val: &ScratchValue) -> bool {
+    matches!(val, ScratchValue::Str(s) if s.trim_matches(is_js_space).is_empty())
+}
+
+// javascript compares strings by utf-16 code units
+fn cmp_utf16(a: &str, b: &str) -> Ordering {
+    a.encode_utf16().cmp(b.encode_utf16())
+}
+
+// `Number(val)`, NaN included
+fn js_number(val: &ScratchValue) -> Num {
+    match val {
+        ScratchValue::Num(num) => *num,
+        ScratchValue::Bool(b) => *b as u8 as Num,
+        ScratchValue::Str(s) => str_to_number(s),
+    }
+}
+
+// `WhiteSpace` and `LineTerminator` of the ecmascript spec
+fn is_js_space(c: char) -> bool {
+    matches!(
+        c,
+        '\t' | '\n' | '\u{b}' | '\u{c}' | '\r' | ' ' | '\u{a0}' | '\u{1680}' | '\u{2000}'
+            ..='\u{200a}'
+                | '\u{2028}'
+                | '\u{2029}'
+                | '\u{202f}'
+                | '\u{205f}'
+                | '\u{3000}'
+                | '\u{feff}'
+    )
+}
+
+// `StringToNumber` of the ecmascript spec
+pub fn str_to_number(s: &str) -> Num {
+    let s = s.trim_matches(is_js_space);
+    if s.is_empty() {
+        return 0.0;
+    }
+    let radix = match s.get(..2) {
+        Some("0x") | Some("0X") => 16,
+        Some("0o") | Some("0O") => 8,
+        Some("0b") | Some("0B") => 2,
+        _ => 10,
+    };
+    if radix != 10 {
+        return parse_radix(&s[2..], radix);
+    }
+    let (sign, unsigned) = match s.as_bytes()[0] {
+        b'-' => (-1.0, &s[1..]),
+        b'+' => (1.0, &s[1..]),
+        _ => (1.0, s),
+    };
+    if unsigned == "Infinity" {
+        return sign * Num::INFINITY;
+    }
+    // rust's parser also takes `inf`, `nan` and friends, so check the grammar first
+    if !is_decimal_literal(unsigned) {
+        return Num::NAN;
+    }
+    sign * unsigned.parse::<Num>().unwrap_or(Num::NAN)
+}
+
+fn parse_radix(digits: &str, radix: u32) -> Num {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return Num::NAN;
+    }
+    // exact below 2^128, `as` rounds to nearest like javascript does
+    if let Ok(int) = u128::from_str_radix(digits, radix) {
+        return int as Num;
+    }
+    digits.chars().fold(0.0, |acc, c| {
+        acc * radix as Num + c.to_digit(radix).unwrap() as Num
+    })
+}
+
+// digits [`.` digits] [exponent] | `.` digits [exponent]
+fn is_decimal_literal(s: &str) -> bool {
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(idx) => (&s[..idx], Some(&s[idx + 1..])),
+        None => (s, None),
+    };
+    let (int, frac) = match mantissa.split_once('.') {
+        Some((int, frac)) => (int, frac),
+        None => (mantissa, ""),
+    };
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if int.is_empty() && frac.is_empty() || !all_digits(int) || !all_digits(frac) {
+        return false;
+    }
+    match exponent {
+        None => true,
+        Some(exp) => {
+            let exp = exp.strip_prefix(['+', '-']).unwrap_or(exp);
+            !exp.is_empty() && all_digits(exp)
+        }
+    }
+}
+
+// `Number::toString` of the ecmascript spec
+pub fn number_to_string(num: Num) -> String {
+    if num.is_nan() {
+        return "NaN".to_string();
+    }
+    if num == 0.0 {
+        // both 0 and -0
+        return "0".to_string();
+    }
+    if num.is_infinite() {
+        return if num > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    if num < 0.0 {
+        return format!("-{}", number_to_string(-num));
+    }
+    // `{:e}` gives the shortest digits that round trip, e.g. `1.2345e3`
+    let sci = format!("{:e}", num);
+    let (mantissa, exp) = sci.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    let n = exp.parse::<i32>().unwrap() + 1;
+    if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        let (int, frac) = digits.split_at(n as usize);
+        format!("{}.{}", int, frac)
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let sign = if n - 1 < 0 { '-' } else { '+' };
+        let (first, rest) = digits.split_at(1);
+        if rest.is_empty() {
+            format!("{}e{}{}", first, sign, (n - 1).abs())
+        } else {
+            format!("{}.{}e{}{}", first, rest, sign, (n - 1).abs())
+        }
+    }
+}
@@ -1,2 +1,8 @@
+pub mod cast;
+pub mod load_sb2;
 pub mod load_sb3;
+pub mod observer;
+pub mod options;
+pub mod save_sb3;
+pub mod sb2;
 pub mod sb3;
@@ -0,0 +1,1008 @@
+use zip::ZipArchive;
+
+use crate::{
+    load_sb3::{locate_seq, parse_json, read_entry, report_skipped, verify_assets, Sb3LoaderError},
+    observer::{Observer, Phase, Warning},
+    options::LoadOptions,
+    sb2::{Sb2Child, Sb2Costume, Sb2List, Sb2Object, Sb2Script, Sb2Sound, Sb2Variable},
+    sb3::{
+        Block, Blocks, Costume, Field, ImageFormat, Input, InputType, List, Mutation, Position,
+        Project, ProjectMeta, RotationStyle, Sb3File, ScratchValue, Sound, SoundFormat, Target,
+        Variable,
+    },
+};
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Cursor, Read, Seek},
+    path::Path,
+    rc::Rc,
+    time::Instant,
+};
+
+use serde_json::{Map, Value};
+
+use self::specmap::{Arg, SPECMAP};
+
+/// load a `.sb2` archive from the filesystem and convert it into the sb3 model
+pub fn load(src: &str) -> Result<Sb3File, Sb3LoaderError> {
+    load_with(src, &LoadOptions::default())
+}
+
+pub fn load_with(src: &str, options: &LoadOptions) -> Result<Sb3File, Sb3LoaderError> {
+    let file = fs::File::open(src).map_err(Sb3LoaderError::read(Path::new(src)))?;
+    load_reader_with(file, options)
+}
+
+/// load a `.sb2` archive already held in memory
+pub fn load_bytes(src: &[u8]) -> Result<Sb3File, Sb3LoaderError> {
+    load_bytes_with(src, &LoadOptions::default())
+}
+
+pub fn load_bytes_with(src: &[u8], options: &LoadOptions) -> Result<Sb3File, Sb3LoaderError> {
+    load_reader_with(Cursor::new(src), options)
+}
+
+/// load a `.sb2` archive from any seekable reader
+pub fn load_reader<R: Read + Seek>(src: R) -> Result<Sb3File, Sb3LoaderError> {
+    load_reader_with(src, &LoadOptions::default())
+}
+
+pub fn load_reader_with<R: Read + Seek>(
+    src: R,
+    options: &LoadOptions,
+) -> Result<Sb3File, Sb3LoaderError> {
+    let start = Instant::now();
+    let mut archieve = ZipArchive::new(src).map_err(Sb3LoaderError::extract(None))?;
+    options.limits.check_entries(archieve.len())?;
+    let mut total = 0;
+    let data = read_entry(&mut archieve, "project.json", options, &mut total)?
+        .ok_or(Sb3LoaderError::MissProjectJson)?;
+    let stage: Sb2Object = parse_json("project.json", &data, locate)?;
+    let mut referenced = Vec::new();
+    layer_files(&stage, &mut referenced);
+    let mut files = HashMap::new();
+    for fname in referenced {
+        if files.contains_key(&fname) {
+            continue;
+        }
+        if let Some(data) = read_entry(&mut archieve, &fname, options, &mut total)? {
+            // resource, keyed by its layer / sound id, e.g. `3.png`
+            files.insert(fname, data);
+        }
+    }
+    report_skipped(&archieve, |fname| files.contains_key(fname), options);
+    let (project, resources) = Sb2Converter::new(files, options).convert(stage)?;
+    verify_assets(&project, &resources)?;
+    options.observer.timing(Phase::Load, start.elapsed());
+    Ok(Sb3File::new(resources, project))
+}
+
+// archive entries of every costume and sound, named like `Sb2Converter::move_asset` expects
+fn layer_files(obj: &Sb2Object, out: &mut Vec<String>) {
+    let costumes = obj.costumes.iter().map(|c| (c.layer_id, &c.md5));
+    let sounds = obj.sounds.iter().map(|s| (s.sound_id, &s.md5));
+    let pen_layer = obj.pen_layer_md5.iter().map(|md5| (pen_layer_id(obj), md5));
+    for (id, md5) in costumes.chain(sounds).chain(pen_layer) {
+        let ext = md5.rsplit_once('.').map_or("", |(_, ext)| ext);
+        out.push(format!("{}.{}", id, ext));
+    }
+    for child in &obj.children {
+        if let Sb2Child::Sprite(sprite) = child {
+            layer_files(sprite, out);
+        }
+    }
+}
+
+fn pen_layer_id(obj: &Sb2Object) -> i64 {
+    obj.pen_layer_id.unwrap_or(-1)
+}
+
+// only the stage is checked, a sprite that doesn't parse ends up as an unknown child
+fn locate(stage: &Value) -> String {
+    locate_seq::<Sb2Variable>(stage, "variables")
+        .or_else(|| locate_seq::<Sb2List>(stage, "lists"))
+        .or_else(|| locate_seq::<Sb2Script>(stage, "scripts"))
+        .or_else(|| locate_seq::<Sb2Costume>(stage, "costumes"))
+        .or_else(|| locate_seq::<Sb2Sound>(stage, "sounds"))
+        .map(|path| path.trim_start_matches('.').to_string())
+        .unwrap_or_default()
+}
+
+#[derive(Default)]
+struct TargetScope {
+    name: String,
+    is_stage: bool,
+    // name -> id
+    variable_ids: HashMap<String, String>,
+    list_ids: HashMap<String, String>,
+    variables: HashMap<String, Variable>,
+    lists: HashMap<String, List>,
+    // proccode -> argument ids
+    procedures: HashMap<String, Vec<String>>,
+    blocks: HashMap<String, Block>,
+}
+
+struct Sb2Converter {
+    files: HashMap<String, Vec<u8>>,
+    resources: HashMap<String, Vec<u8>>,
+    next_id: usize,
+    // name -> id, broadcasts are global and end up on the stage
+    broadcasts: HashMap<String, String>,
+    extensions: Vec<String>,
+    observer: Rc<dyn Observer>,
+}
+
+impl Sb2Converter {
+    fn new(files: HashMap<String, Vec<u8>>, options: &LoadOptions) -> Self {
+        Self {
+            files,
+            resources: HashMap::new(),
+            next_id: 0,
+            broadcasts: HashMap::new(),
+            extensions: Vec::new(),
+            observer: options.observer.clone(),
+        }
+    }
+
+    fn convert(
+        mut self,
+        mut stage: Sb2Object,
+    ) -> Result<(Project, HashMap<String, Vec<u8>>), Sb3LoaderError> {
+        let mut globals = self.scope(&stage, true);
+        self.convert_scripts(&mut globals, None, &stage)?;
+        let mut targets = Vec::new();
+        let children = std::mem::take(&mut stage.children);
+        for sprite in children.into_iter().filter_map(|child| match child {
+            Sb2Child::Sprite(sprite) => Some(*sprite),
+            Sb2Child::Other(_) => None,
+        }) {
+            let mut scope = self.scope(&sprite, false);
+            self.convert_scripts(&mut scope, Some(&globals), &sprite)?;
+            let layer_order = targets.len() + 1;
+            targets.push(self.target(sprite, scope, layer_order));
+        }
+        let stage = self.target(stage, globals, 0);
+        targets.insert(0, stage);
+        let project = Project {
+            targets,
+            extensions: self.extensions,
+            meta: ProjectMeta {
+                semver: "3.0.0".to_string(),
+                vm: "0.2.0".to_string(),
+                agent: "ira sb2 converter".to_string(),
+                extra: Map::new(),
+            },
+            extra: Map::new(),
+        };
+        Ok((project, self.resources))
+    }
+
+    fn gen_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("sb2-{}", self.next_id)
+    }
+
+    fn scope(&mut self, obj: &Sb2Object, is_stage: bool) -> TargetScope {
+        let mut scope = TargetScope {
+            name: obj.name.clone(),
+            is_stage,
+            ..Default::default()
+        };
+        for var in &obj.variables {
+            let id = self.gen_id();
+            scope.variable_ids.insert(var.name.clone(), id.clone());
+            scope.variables.insert(
+                id,
+                Variable(var.name.clone(), var.value.clone(), var.is_persistent),
+            );
+        }
+        for list in &obj.lists {
+            let id = self.gen_id();
+            scope.list_ids.insert(list.name.clone(), id.clone());
+            scope
+                .lists
+                .insert(id, List(list.name.clone(), list.contents.clone()));
+        }
+        scope
+    }
+
+    fn target(&mut self, obj: Sb2Object, scope: TargetScope, layer_order: usize) -> Target {
+        let costumes = obj
+            .costumes
+            .iter()
+            .map(|costume| {
+                let (asset_id, md5ext) = self.move_asset(costume.layer_id, &costume.md5);
+                let data_format = match md5ext.rsplit('.').next() {
+                    Some("svg") => ImageFormat::ImageSVG,
+                    Some("jpg") | Some("jpeg") => ImageFormat::ImageJPG,
+                    _ => ImageFormat::ImagePNG,
+                };
+                Costume {
+                    name: costume.name.clone(),
+                    data_format,
+                    asset_id,
+                    md5ext,
+                    center_x: costume.center_x,
+                    center_y: costume.center_y,
+                    extra: Map::from_iter([(
+                        "bitmapResolution".to_string(),
+                        costume.resolution.into(),
+                    )]),
+                }
+            })
+            .collect();
+        let sounds = obj
+            .sounds
+            .iter()
+            .map(|sound| {
+                let (asset_id, md5ext) = self.move_asset(sound.sound_id, &sound.md5);
+                let data_format = match md5ext.rsplit('.').next() {
+                    Some("mp3") => SoundFormat::AudioMP3,
+                    _ => SoundFormat::AudioWAV,
+                };
+                Sound {
+                    name: sound.name.clone(),
+                    asset_id,
+                    md5ext,
+                    data_format,
+                    format: sound.format.clone(),
+                    rate: sound.rate,
+                    sample_count: sound.sample_count,
+                }
+            })
+            .collect();
+        let broadcasts = if scope.is_stage {
+            self.broadcasts
+                .iter()
+                .map(|(name, id)| (id.clone(), name.clone()))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        let rotation_style = match obj.rotation_style.as_deref() {
+            Some("leftRight") => RotationStyle::LeftRight,
+            Some("none") => RotationStyle::NoRotation,
+            _ => RotationStyle::AllAround,
+        };
+        let mut extra = Map::new();
+        if let Some(tempo) = obj.tempo {
+            extra.insert("tempo".to_string(), tempo.into());
+        }
+        // sb3 has no pen layer, it is kept on the stage under its sb2 names
+        if let Some(md5) = &obj.pen_layer_md5 {
+            let (_, md5ext) = self.move_asset(pen_layer_id(&obj), md5);
+            extra.insert("penLayerMD5".to_string(), md5ext.into());
+        }
+        if let Some(id) = obj.pen_layer_id {
+            extra.insert("penLayerID".to_string(), id.into());
+        }
+        // the stage has no position, size, ...
+        let sprite = !scope.is_stage;
+        Target {
+            is_stage: scope.is_stage,
+            name: obj.name,
+            variables: scope.variables,
+            lists: scope.lists,
+            broadcasts,
+            blocks: Blocks {
+                blocks: scope.blocks,
+            },
+            costumes,
+            current_costume: obj.current_costume as usize,
+            sounds,
+            volume: 100.0,
+            layer_order,
+            position: Position {
+                x: sprite.then_some(obj.x),
+                y: sprite.then_some(obj.y),
+            },
+            size: sprite.then_some(obj.scale * 100.0),
+            direction: sprite.then_some(obj.direction),
+            draggable: sprite.then_some(obj.draggable),
+            rotation_style: sprite.then_some(rotation_style),
+            visible: sprite.then_some(obj.visible),
+            extra,
+        }
+    }
+
+    // sb2 stores assets by layer id (`3.png`), sb3 by md5 (`<md5>.png`)
+    fn move_asset(&mut self, layer_id: i64, md5: &str) -> (String, String) {
+        let (stem, ext) = md5.rsplit_once('.').unwrap_or((md5, ""));
+        match self.files.get(&format!("{}.{}", layer_id, ext)) {
+            Some(data) => {
+                let asset_id = format!("{:x}", md5::compute(data));
+                let md5ext = format!("{}.{}", asset_id, ext);
+                self.resources.insert(md5ext.clone(), data.clone());
+                (asset_id, md5ext)
+            }
+            None => (stem.to_string(), md5.to_string()),
+        }
+    }
+
+    fn convert_scripts(
+        &mut self,
+        scope: &mut TargetScope,
+        globals: Option<&TargetScope>,
+        obj: &Sb2Object,
+    ) -> Result<(), Sb3LoaderError> {
+        // argument ids have to be known before any `call` is converted
+        for script in &obj.scripts {
+            let head = script.2.first().and_then(Value::as_array);
+            if let Some([Value::String(op), Value::String(spec), ..]) = head.map(Vec::as_slice) {
+                if op == "procDef" {
+                    let ids = spec_args(spec).iter().map(|_| self.gen_id()).collect();
+                    scope.procedures.insert(spec.clone(), ids);
+                }
+            }
+        }
+        for script in &obj.scripts {
+            let first = self.convert_stack(scope, globals, &script.2, None, true)?;
+            if let Some(block) = first.and_then(|id| scope.blocks.get_mut(&id)) {
+                block.x = Some(script.0);
+                block.y = Some(script.1);
+            }
+        }
+        Ok(())
+    }
+
+    fn convert_stack(
+        &mut self,
+        scope: &mut TargetScope,
+        globals: Option<&TargetScope>,
+        blocks: &[Value],
+        parent: Option<&str>,
+        top_level: bool,
+    ) -> Result<Option<String>, Sb3LoaderError> {
+        let mut first = None;
+        let mut prev: Option<String> = None;
+        for block in blocks {
+            let block = block.as_array().ok_or_else(|| invaild_block(block))?;
+            let parent = prev.as_deref().or(parent);
+            let id =
+                self.convert_block(scope, globals, block, parent, top_level && prev.is_none())?;
+            if let Some(prev) = &prev {
+                scope.blocks.get_mut(prev).unwrap().next = Some(id.clone());
+            }
+            first.get_or_insert_with(|| id.clone());
+            prev = Some(id);
+        }
+        Ok(first)
+    }
+
+    fn convert_block(
+        &mut self,
+        scope: &mut TargetScope,
+        globals: Option<&TargetScope>,
+        block: &[Value],
+        parent: Option<&str>,
+        top_level: bool,
+    ) -> Result<String, Sb3LoaderError> {
+        let id = self.gen_id();
+        let selector = block.first().and_then(Value::as_str).unwrap_or_default();
+        let args = block.get(1..).unwrap_or_default();
+        let mut new = Block {
+            opcode: String::new(),
+            next: None,
+            parent: parent.map(String::from),
+            inputs: HashMap::new(),
+            fields: HashMap::new(),
+            shadow: false,
+            top_level,
+            mutation: None,
+            x: None,
+            y: None,
+            comment: None,
+        };
+        match selector {
+            "procDef" => {
+                new.opcode = "procedures_definition".to_string();
+                let proto = self.convert_prototype(scope, &id, args);
+                new.inputs.insert(
+                    "custom_block".to_string(),
+                    Input::NoHidden(1, InputType::Block(proto)),
+                );
+            }
+            "call" => {
+                new.opcode = "procedures_call".to_string();
+                let spec = args.first().map(value_str).unwrap_or_default();
+                let ids = match scope.procedures.get(&spec) {
+                    Some(ids) => ids.clone(),
+                    None => spec_args(&spec).iter().map(|_| self.gen_id()).collect(),
+                };
+                let values = args.get(1..).unwrap_or_default();
+                for ((kind, arg_id), arg) in
+                    spec_args(&spec).into_iter().zip(ids.clone()).zip(values)
+                {
+                    let spec = if kind == 'b' {
+                        Arg::Bool("")
+                    } else {
+                        Arg::Prim("", 10)
+                    };
+                    if let Some(input) = self.convert_input(scope, globals, &id, spec, arg)? {
+                        new.inputs.insert(arg_id, input);
+                    }
+                }
+                new.mutation = Some(Mutation {
+                    proccode: Some(proccode(&spec)),
+                    argument_ids: ids,
+                    ..Default::default()
+                });
+            }
+            "getParam" => {
+                new.opcode = match args.get(1).and_then(Value::as_str) {
+                    Some("b") => "argument_reporter_boolean",
+                    _ => "argument_reporter_string_number",
+                }
+                .to_string();
+                let name = args.first().map(value_str).unwrap_or_default();
+                new.fields.insert("VALUE".to_string(), Field(name, None));
+            }
+            "deleteLine:ofList:" if args.first().and_then(Value::as_str) == Some("all") => {
+                new.opcode = "data_deletealloflist".to_string();
+                let list = args.get(1).unwrap_or(&Value::Null);
+                self.convert_arg(scope, globals, &id, &mut new, Arg::List("LIST"), list)?;
+            }
+            _ => {
+                let (opcode, specs) = match SPECMAP.iter().find(|(sel, _, _)| *sel == selector) {
+                    Some((_, opcode, specs)) => (*opcode, *specs),
+                    None => {
+                        self.observer.warning(&Warning {
+                            target: Some(scope.name.clone()),
+                            block: Some(id.clone()),
+                            message: format!("unknown sb2 block `{}`, kept as is", selector),
+                        });
+                        (selector, &[] as &[Arg])
+                    }
+                };
+                new.opcode = match opcode {
+                    "event_whenthisspriteclicked" if scope.is_stage => "event_whenstageclicked",
+                    _ => opcode,
+                }
+                .to_string();
+                if let Some((ext, _)) = opcode.split_once('_') {
+                    if matches!(ext, "pen" | "music") && !self.extensions.iter().any(|e| e == ext) {
+                        self.extensions.push(ext.to_string());
+                    }
+                }
+                let mut args = args.iter();
+                for spec in specs.iter() {
+                    let arg = match spec {
+                        Arg::Const(_, _) => &Value::Null,
+                        _ => args.next().unwrap_or(&Value::Null),
+                    };
+                    self.convert_arg(scope, globals, &id, &mut new, *spec, arg)?;
+                }
+                // sb3 has no stage-only stop option
+                if let Some(Field(option, _)) = new.fields.get_mut("STOP_OPTION") {
+                    if option == "other scripts in stage" {
+                        *option = "other scripts in sprite".to_string();
+                    }
+                    new.mutation = Some(Mutation {
+                        has_next: option == "other scripts in sprite",
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+        scope.blocks.insert(id.clone(), new);
+        Ok(id)
+    }
+
+    // ["procDef", spec, [arg names], [arg defaults], warp]
+    fn convert_prototype(
+        &mut self,
+        scope: &mut TargetScope,
+        parent: &str,
+        args: &[Value],
+    ) -> String {
+        let id = self.gen_id();
+        let spec = args.first().map(value_str).unwrap_or_default();
+        let names = args.get(1).and_then(Value::as_array);
+        let defaults = args.get(2).and_then(Value::as_array);
+        let warp = match args.get(3) {
+            Some(Value::Bool(warp)) => *warp,
+            Some(Value::String(warp)) => warp == "true",
+            _ => false,
+        };
+        let ids = scope.procedures.get(&spec).cloned().unwrap_or_default();
+        let mut mutation = Mutation {
+            proccode: Some(proccode(&spec)),
+            argument_ids: ids.clone(),
+            warp,
+            prototype: true,
+            ..Default::default()
+        };
+        let mut proto = Block {
+            opcode: "procedures_prototype".to_string(),
+            next: None,
+            parent: Some(parent.to_string()),
+            inputs: HashMap::new(),
+            fields: HashMap::new(),
+            shadow: true,
+            top_level: false,
+            mutation: None,
+            x: None,
+            y: None,
+            comment: None,
+        };
+        for (idx, (kind, arg_id)) in spec_args(&spec).into_iter().zip(ids).enumerate() {
+            let reporter = self.gen_id();
+            let name = names
+                .and_then(|names| names.get(idx))
+                .map(value_str)
+                .unwrap_or_default();
+            let default = defaults
+                .and_then(|defaults| defaults.get(idx))
+                .map(value_to_scratch)
+                .unwrap_or(ScratchValue::Str(String::new()));
+            mutation.argument_names.push(name.clone());
+            mutation.argument_defaults.push(default);
+            let opcode = if kind == 'b' {
+                "argument_reporter_boolean"
+            } else {
+                "argument_reporter_string_number"
+            };
+            scope.blocks.insert(
+                reporter.clone(),
+                Block {
+                    opcode: opcode.to_string(),
+                    next: None,
+                    parent: Some(id.clone()),
+                    inputs: HashMap::new(),
+                    fields: HashMap::from([("VALUE".to_string(), Field(name, None))]),
+                    shadow: true,
+                    top_level: false,
+                    mutation: None,
+                    x: None,
+                    y: None,
+                    comment: None,
+                },
+            );
+            proto
+                .inputs
+                .insert(arg_id, Input::NoHidden(1, InputType::Block(reporter)));
+        }
+        proto.mutation = Some(mutation);
+        scope.blocks.insert(id.clone(), proto);
+        id
+    }
+
+    fn convert_arg(
+        &mut self,
+        scope: &mut TargetScope,
+        globals: Option<&TargetScope>,
+        id: &str,
+        block: &mut Block,
+        spec: Arg,
+        arg: &Value,
+    ) -> Result<(), Sb3LoaderError> {
+        let field = match spec {
+            Arg::Field(name) => Some((name, Field(value_str(arg), None))),
+            Arg::UpperField(name) => {
+                let val = value_str(arg).to_uppercase().replace(' ', "");
+                Some((name, Field(val, None)))
+            }
+            Arg::Const(name, val) => Some((name, Field(val.to_string(), None))),
+            Arg::Var(name) => {
+                let var = value_str(arg);
+                let var_id = Self::lookup_variable(scope, globals, &var, || self.gen_id());
+                Some((name, Field(var, Some(var_id))))
+            }
+            Arg::List(name) => {
+                let list = value_str(arg);
+                let list_id = Self::lookup_list(scope, globals, &list, || self.gen_id());
+                Some((name, Field(list, Some(list_id))))
+            }
+            Arg::BroadcastField(name) => {
+                let broadcast = value_str(arg);
+                let broadcast_id = self.broadcast_id(&broadcast);
+                Some((name, Field(broadcast, Some(broadcast_id))))
+            }
+            _ => None,
+        };
+        if let Some((name, field)) = field {
+            block.fields.insert(name.to_string(), field);
+        } else if let Some(input) = self.convert_input(scope, globals, id, spec, arg)? {
+            block.inputs.insert(spec.name().to_string(), input);
+        }
+        Ok(())
+    }
+
+    fn convert_input(
+        &mut self,
+        scope: &mut TargetScope,
+        globals: Option<&TargetScope>,
+        id: &str,
+        spec: Arg,
+        arg: &Value,
+    ) -> Result<Option<Input>, Sb3LoaderError> {
+        if let Arg::Substack(_) = spec {
+            let blocks = arg.as_array().map(Vec::as_slice).unwrap_or_default();
+            let first = self.convert_stack(scope, globals, blocks, Some(id), false)?;
+            return Ok(first.map(|first| Input::NoHidden(2, InputType::Block(first))));
+        }
+        let reporter = self.convert_reporter(scope, globals, id, arg)?;
+        Ok(match spec {
+            Arg::Prim(_, code) => {
+                let mut val = value_to_scratch(arg);
+                if let (9, ScratchValue::Num(color)) = (code, &val) {
+                    val = ScratchValue::Str(format!("#{:06x}", (*color as i64) & 0xffffff));
+                }
+                match reporter {
+                    Some(reporter) => Some(Input::Hidden(
+                        3,
+                        reporter,
+                        InputType::NumOrStr(code, ScratchValue::Str(String::new())),
+                    )),
+                    None => Some(Input::NoHidden(1, InputType::NumOrStr(code, val))),
+                }
+            }
+            Arg::Bool(_) => reporter.map(|reporter| Input::NoHidden(2, reporter)),
+            Arg::Menu(_, opcode, field) => {
+                let shadow = self.gen_id();
+                let val = match reporter {
+                    Some(_) => String::new(),
+                    None => value_str(arg),
+                };
+                scope.blocks.insert(
+                    shadow.clone(),
+                    Block {
+                        opcode: opcode.to_string(),
+                        next: None,
+                        parent: Some(id.to_string()),
+                        inputs: HashMap::new(),
+                        fields: HashMap::from([(field.to_string(), Field(val, None))]),
+                        shadow: true,
+                        top_level: false,
+                        mutation: None,
+                        x: None,
+                        y: None,
+                        comment: None,
+                    },
+                );
+                let shadow = InputType::Block(shadow);
+                Some(match reporter {
+                    Some(reporter) => Input::Hidden(3, reporter, shadow),
+                    None => Input::NoHidden(1, shadow),
+                })
+            }
+            Arg::Broadcast(_) => Some(match reporter {
+                Some(reporter) => Input::Hidden(
+                    3,
+                    reporter,
+                    InputType::NumOrStr(10, ScratchValue::Str(String::new())),
+                ),
+                None => {
+                    let name = value_str(arg);
+                    let broadcast_id = self.broadcast_id(&name);
+                    Input::NoHidden(1, InputType::BroadcastOrVarOrList(11, name, broadcast_id))
+                }
+            }),
+            _ => None,
+        })
+    }
+
+    // variable and list reporters are compressed into primitives like sb3 does
+    fn convert_reporter(
+        &mut self,
+        scope: &mut TargetScope,
+        globals: Option<&TargetScope>,
+        parent: &str,
+        arg: &Value,
+    ) -> Result<Option<InputType>, Sb3LoaderError> {
+        let block = match arg.as_array() {
+            Some(block) if block.first().is_some_and(Value::is_string) => block,
+            _ => return Ok(None),
+        };
+        let name = block.get(1).map(value_str).unwrap_or_default();
+        Ok(Some(match block[0].as_str() {
+            Some("readVariable") => {
+                let id = Self::lookup_variable(scope, globals, &name, || self.gen_id());
+                InputType::BroadcastOrVarOrList(12, name, id)
+            }
+            Some("contentsOfList:") => {
+                let id = Self::lookup_list(scope, globals, &name, || self.gen_id());
+                InputType::BroadcastOrVarOrList(13, name, id)
+            }
+            _ => {
+                InputType::Block(self.convert_block(scope, globals, block, Some(parent), false)?)
+            }
+        }))
+    }
+
+    // unknown variables are created on the spot, like scratch-vm does
+    fn lookup_variable(
+        scope: &mut TargetScope,
+        globals: Option<&TargetScope>,
+        name: &str,
+        gen_id: impl FnOnce() -> String,
+    ) -> String {
+        if let Some(id) = scope.variable_ids.get(name) {
+            return id.clone();
+        }
+        if let Some(id) = globals.and_then(|g| g.variable_ids.get(name)) {
+            return id.clone();
+        }
+        let id = gen_id();
+        scope.variable_ids.insert(name.to_string(), id.clone());
+        scope.variables.insert(
+            id.clone(),
+            Variable(name.to_string(), ScratchValue::Num(0.0), false),
+        );
+        id
+    }
+
+    fn lookup_list(
+        scope: &mut TargetScope,
+        globals: Option<&TargetScope>,
+        name: &str,
+        gen_id: impl FnOnce() -> String,
+    ) -> String {
+        if let Some(id) = scope.list_ids.get(name) {
+            return id.clone();
+        }
+        if let Some(id) = globals.and_then(|g| g.list_ids.get(name)) {
+            return id.clone();
+        }
+        let id = gen_id();
+        scope.list_ids.insert(name.to_string(), id.clone());
+        scope
+            .lists
+            .insert(id.clone(), List(name.to_string(), Vec::new()));
+        id
+    }
+
+    fn broadcast_id(&mut self, name: &str) -> String {
+        self.broadcasts
+            .entry(name.to_string())
+            .or_insert_with(|| format!("broadcastMsgId-{}", name))
+            .clone()
+    }
+}
+
+// argument kinds of a procedure spec, e.g. `jump %n times %b` -> ['n', 'b']
+fn spec_args(spec: &str) -> Vec<char> {
+    let mut kinds = Vec::new();
+    let mut chars = spec.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '%' {
+            if let Some(kind @ ('n' | 's' | 'b' | 'd' | 'm')) = chars.next() {
+                kinds.push(kind);
+            }
+        }
+    }
+    kinds
+}
+
+// sb3 has no separate number argument
+fn proccode(spec: &str) -> String {
+    spec.replace("%n", "%s")
+}
+
+// scripts are kept as raw json, so a malformed block only shows up while converting
+fn invaild_block(block: &Value) -> Sb3LoaderError {
+    Sb3LoaderError::InvaildProjectJsonFormat {
+        entry: "project.json".to_string(),
+        path: String::new(),
+        line: 0,
+        column: 0,
+        source: serde::de::Error::custom(format!("expected a block, found `{}`", block)),
+    }
+}
+
+fn value_str(val: &Value) -> String {
+    match val {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        _ => val.to_string(),
+    }
+}
+
+fn value_to_scratch(val: &Value) -> ScratchValue {
+    match val {
+        Value::Number(num) => ScratchValue::Num(num.as_f64().unwrap_or_default()),
+        Value::Bool(b) => ScratchValue::Bool(*b),
+        _ => ScratchValue::Str(value_str(val)),
+    }
+}
+
+mod specmap {
+    #[derive(Debug, Clone, Copy)]
+    pub enum Arg {
+        // input backed by a primitive shadow, with its sb3 primitive code
+        Prim(&'static str, u8),
+        Bool(&'static str),
+        Substack(&'static str),
+        // input backed by a menu shadow block: input, shadow opcode, shadow field
+        Menu(&'static str, &'static str, &'static str),
+        Broadcast(&'static str),
+        Field(&'static str),
+        // lower case sb2 menu values, e.g. `day of week` -> `DAYOFWEEK`
+        UpperField(&'static str),
+        Var(&'static str),
+        List(&'static str),
+        BroadcastField(&'static str),
+        // field with a fixed value, consumes no sb2 argument
+        Const(&'static str, &'static str),
+    }
+
+    impl Arg {
+        pub fn name(&self) -> &'static str {
+            match self {
+                Self::Prim(name, _)
+                | Self::Bool(name)
+                | Self::Substack(name)
+                | Self::Menu(name, _, _)
+                | Self::Broadcast(name)
+                | Self::Field(name)
+                | Self::UpperField(name)
+                | Self::Var(name)
+                | Self::List(name)
+                | Self::BroadcastField(name)
+                | Self::Const(name, _) => name,
+            }
+        }
+    }
+
+    use self::Arg::*;
+
+    const NUM: u8 = 4;
+    const POSITIVE: u8 = 5;
+    const WHOLE: u8 = 6;
+    const INTEGER: u8 = 7;
+    const ANGLE: u8 = 8;
+    const COLOR: u8 = 9;
+    const TEXT: u8 = 10;
+
+    // sb2 selector -> sb3 opcode and arguments, in sb2 argument order
+    #[rustfmt::skip]
+    pub static SPECMAP: &[(&str, &str, &[Arg])] = &[
+        // motion
+        ("forward:", "motion_movesteps", &[Prim("STEPS", NUM)]),
+        ("turnRight:", "motion_turnright", &[Prim("DEGREES", NUM)]),
+        ("turnLeft:", "motion_turnleft", &[Prim("DEGREES", NUM)]),
+        ("heading:", "motion_pointindirection", &[Prim("DIRECTION", ANGLE)]),
+        ("pointTowards:", "motion_pointtowards", &[Menu("TOWARDS", "motion_pointtowards_menu", "TOWARDS")]),
+        ("gotoX:y:", "motion_gotoxy", &[Prim("X", NUM), Prim("Y", NUM)]),
+        ("gotoSpriteOrMouse:", "motion_goto", &[Menu("TO", "motion_goto_menu", "TO")]),
+        ("glideSecs:toX:y:elapsed:from:", "motion_glidesecstoxy", &[Prim("SECS", NUM), Prim("X", NUM), Prim("Y", NUM)]),
+        ("changeXposBy:", "motion_changexby", &[Prim("DX", NUM)]),
+        ("xpos:", "motion_setx", &[Prim("X", NUM)]),
+        ("changeYposBy:", "motion_changeyby", &[Prim("DY", NUM)]),
+        ("ypos:", "motion_sety", &[Prim("Y", NUM)]),
+        ("bounceOffEdge", "motion_ifonedgebounce", &[]),
+        ("setRotationStyle", "motion_setrotationstyle", &[Field("STYLE")]),
+        ("xpos", "motion_xposition", &[]),
+        ("ypos", "motion_yposition", &[]),
+        ("heading", "motion_direction", &[]),
+        // looks
+        ("say:duration:elapsed:from:", "looks_sayforsecs", &[Prim("MESSAGE", TEXT), Prim("SECS", NUM)]),
+        ("say:", "looks_say", &[Prim("MESSAGE", TEXT)]),
+        ("think:duration:elapsed:from:", "looks_thinkforsecs", &[Prim("MESSAGE", TEXT), Prim("SECS", NUM)]),
+        ("think:", "looks_think", &[Prim("MESSAGE", TEXT)]),
+        ("show", "looks_show", &[]),
+        ("hide", "looks_hide", &[]),
+        ("lookLike:", "looks_switchcostumeto", &[Menu("COSTUME", "looks_costume", "COSTUME")]),
+        ("nextCostume", "looks_nextcostume", &[]),
+        ("startScene", "looks_switchbackdropto", &[Menu("BACKDROP", "looks_backdrops", "BACKDROP")]),
+        ("startSceneAndWait", "looks_switchbackdroptoandwait", &[Menu("BACKDROP", "looks_backdrops", "BACKDROP")]),
+        ("nextScene", "looks_nextbackdrop", &[]),
+        ("changeGraphicEffect:by:", "looks_changeeffectby", &[UpperField("EFFECT"), Prim("CHANGE", NUM)]),
+        ("setGraphicEffect:to:", "looks_seteffectto", &[UpperField("EFFECT"), Prim("VALUE", NUM)]),
+        ("filterReset", "looks_cleargraphiceffects", &[]),
+        ("changeSizeBy:", "looks_changesizeby", &[Prim("CHANGE", NUM)]),
+        ("setSizeTo:", "looks_setsizeto", &[Prim("SIZE", NUM)]),
+        ("comeToFront", "looks_gotofrontback", &[Const("FRONT_BACK", "front")]),
+        ("goBackByLayers:", "looks_goforwardbackwardlayers", &[Const("FORWARD_BACKWARD", "backward"), Prim("NUM", INTEGER)]),
+        ("costumeIndex", "looks_costumenumbername", &[Const("NUMBER_NAME", "number")]),
+        ("costumeName", "looks_costumenumbername", &[Const("NUMBER_NAME", "name")]),
+        ("backgroundIndex", "looks_backdropnumbername", &[Const("NUMBER_NAME", "number")]),
+        ("sceneName", "looks_backdropnumbername", &[Const("NUMBER_NAME", "name")]),
+        ("scale", "looks_size", &[]),
+        // sound
+        ("playSound:", "sound_play", &[Menu("SOUND_MENU", "sound_sounds_menu", "SOUND_MENU")]),
+        ("doPlaySoundAndWait", "sound_playuntildone", &[Menu("SOUND_MENU", "sound_sounds_menu", "SOUND_MENU")]),
+        ("stopAllSounds", "sound_stopallsounds", &[]),
+        ("changeVolumeBy:", "sound_changevolumeby", &[Prim("VOLUME", NUM)]),
+        ("setVolumeTo:", "sound_setvolumeto", &[Prim("VOLUME", NUM)]),
+        ("volume", "sound_volume", &[]),
+        // music
+        ("playDrum", "music_playDrumForBeats", &[Menu("DRUM", "music_menu_DRUM", "DRUM"), Prim("BEATS", NUM)]),
+        ("rest:elapsed:from:", "music_restForBeats", &[Prim("BEATS", NUM)]),
+        ("noteOn:duration:elapsed:from:", "music_playNoteForBeats", &[Menu("NOTE", "note", "NOTE"), Prim("BEATS", NUM)]),
+        ("instrument:", "music_setInstrument", &[Menu("INSTRUMENT", "music_menu_INSTRUMENT", "INSTRUMENT")]),
+        ("changeTempoBy:", "music_changeTempo", &[Prim("TEMPO", NUM)]),
+        ("setTempoTo:", "music_setTempo", &[Prim("TEMPO", NUM)]),
+        ("tempo", "music_getTempo", &[]),
+        // pen
+        ("clearPenTrails", "pen_clear", &[]),
+        ("stampCostume", "pen_stamp", &[]),
+        ("putPenDown", "pen_penDown", &[]),
+        ("putPenUp", "pen_penUp", &[]),
+        ("penColor:", "pen_setPenColorToColor", &[Prim("COLOR", COLOR)]),
+        ("changePenHueBy:", "pen_changePenHueBy", &[Prim("HUE", NUM)]),
+        ("setPenHueTo:", "pen_setPenHueToNumber", &[Prim("HUE", NUM)]),
+        ("changePenShadeBy:", "pen_changePenShadeBy", &[Prim("SHADE", NUM)]),
+        ("setPenShadeTo:", "pen_setPenShadeToNumber", &[Prim("SHADE", NUM)]),
+        ("changePenSizeBy:", "pen_changePenSizeBy", &[Prim("SIZE", NUM)]),
+        ("penSize:", "pen_setPenSizeTo", &[Prim("SIZE", NUM)]),
+        // events
+        ("whenGreenFlag", "event_whenflagclicked", &[]),
+        ("whenKeyPressed", "event_whenkeypressed", &[Field("KEY_OPTION")]),
+        ("whenClicked", "event_whenthisspriteclicked", &[]),
+        ("whenSceneStarts", "event_whenbackdropswitchesto", &[Field("BACKDROP")]),
+        ("whenSensorGreaterThan", "event_whengreaterthan", &[UpperField("WHENGREATERTHANMENU"), Prim("VALUE", NUM)]),
+        ("whenIReceive", "event_whenbroadcastreceived", &[BroadcastField("BROADCAST_OPTION")]),
+        ("broadcast:", "event_broadcast", &[Broadcast("BROADCAST_INPUT")]),
+        ("doBroadcastAndWait", "event_broadcastandwait", &[Broadcast("BROADCAST_INPUT")]),
+        // control
+        ("wait:elapsed:from:", "control_wait", &[Prim("DURATION", POSITIVE)]),
+        ("doRepeat", "control_repeat", &[Prim("TIMES", WHOLE), Substack("SUBSTACK")]),
+        ("doForever", "control_forever", &[Substack("SUBSTACK")]),
+        ("doIf", "control_if", &[Bool("CONDITION"), Substack("SUBSTACK")]),
+        ("doIfElse", "control_if_else", &[Bool("CONDITION"), Substack("SUBSTACK"), Substack("SUBSTACK2")]),
+        ("doWaitUntil", "control_wait_until", &[Bool("CONDITION")]),
+        ("doUntil", "control_repeat_until", &[Bool("CONDITION"), Substack("SUBSTACK")]),
+        ("doWhile", "control_while", &[Bool("CONDITION"), Substack("SUBSTACK")]),
+        ("doForLoop", "control_for_each", &[Var("VARIABLE"), Prim("VALUE", TEXT), Substack("SUBSTACK")]),
+        ("stopScripts", "control_stop", &[Field("STOP_OPTION")]),
+        ("stopAll", "control_stop", &[Const("STOP_OPTION", "all")]),
+        ("whenCloned", "control_start_as_clone", &[]),
+        ("createCloneOf", "control_create_clone_of", &[Menu("CLONE_OPTION", "control_create_clone_of_menu", "CLONE_OPTION")]),
+        ("deleteClone", "control_delete_this_clone", &[]),
+        // sensing
+        ("touching:", "sensing_touchingobject", &[Menu("TOUCHINGOBJECTMENU", "sensing_touchingobjectmenu", "TOUCHINGOBJECTMENU")]),
+        ("touchingColor:", "sensing_touchingcolor", &[Prim("COLOR", COLOR)]),
+        ("color:sees:", "sensing_coloristouchingcolor", &[Prim("COLOR", COLOR), Prim("COLOR2", COLOR)]),
+        ("distanceTo:", "sensing_distanceto", &[Menu("DISTANCETOMENU", "sensing_distancetomenu", "DISTANCETOMENU")]),
+        ("doAsk", "sensing_askandwait", &[Prim("QUESTION", TEXT)]),
+        ("answer", "sensing_answer", &[]),
+        ("keyPressed:", "sensing_keypressed", &[Menu("KEY_OPTION", "sensing_keyoptions", "KEY_OPTION")]),
+        ("mousePressed", "sensing_mousedown", &[]),
+        ("mouseX", "sensing_mousex", &[]),
+        ("mouseY", "sensing_mousey", &[]),
+        ("soundLevel", "sensing_loudness", &[]),
+        ("timer", "sensing_timer", &[]),
+        ("timerReset", "sensing_resettimer", &[]),
+        ("getAttribute:of:", "sensing_of", &[Field("PROPERTY"), Menu("OBJECT", "sensing_of_object_menu", "OBJECT")]),
+        ("timeAndDate", "sensing_current", &[UpperField("CURRENTMENU")]),
+        ("timestamp", "sensing_dayssince2000", &[]),
+        ("getUserName", "sensing_username", &[]),
+        // operators
+        ("+", "operator_add", &[Prim("NUM1", NUM), Prim("NUM2", NUM)]),
+        ("-", "operator_subtract", &[Prim("NUM1", NUM), Prim("NUM2", NUM)]),
+        ("*", "operator_multiply", &[Prim("NUM1", NUM), Prim("NUM2", NUM)]),
+        ("/", "operator_divide", &[Prim("NUM1", NUM), Prim("NUM2", NUM)]),
+        ("randomFrom:to:", "operator_random", &[Prim("FROM", NUM), Prim("TO", NUM)]),
+        ("<", "operator_lt", &[Prim("OPERAND1", TEXT), Prim("OPERAND2", TEXT)]),
+        (">", "operator_gt", &[Prim("OPERAND1", TEXT), Prim("OPERAND2", TEXT)]),
+        ("=", "operator_equals", &[Prim("OPERAND1", TEXT), Prim("OPERAND2", TEXT)]),
+        ("&", "operator_and", &[Bool("OPERAND1"), Bool("OPERAND2")]),
+        ("|", "operator_or", &[Bool("OPERAND1"), Bool("OPERAND2")]),
+        ("not", "operator_not", &[Bool("OPERAND")]),
+        ("concatenate:with:", "operator_join", &[Prim("STRING1", TEXT), Prim("STRING2", TEXT)]),
+        ("letter:of:", "operator_letter_of", &[Prim("LETTER", WHOLE), Prim("STRING", TEXT)]),
+        ("stringLength:", "operator_length", &[Prim("STRING", TEXT)]),
+        ("%", "operator_mod", &[Prim("NUM1", NUM), Prim("NUM2", NUM)]),
+        ("rounded", "operator_round", &[Prim("NUM", NUM)]),
+        ("computeFunction:of:", "operator_mathop", &[Field("OPERATOR"), Prim("NUM", NUM)]),
+        // data
+        ("readVariable", "data_variable", &[Var("VARIABLE")]),
+        ("setVar:to:", "data_setvariableto", &[Var("VARIABLE"), Prim("VALUE", TEXT)]),
+        ("changeVar:by:", "data_changevariableby", &[Var("VARIABLE"), Prim("VALUE", NUM)]),
+        ("showVariable:", "data_showvariable", &[Var("VARIABLE")]),
+        ("hideVariable:", "data_hidevariable", &[Var("VARIABLE")]),
+        ("contentsOfList:", "data_listcontents", &[List("LIST")]),
+        ("append:toList:", "data_addtolist", &[Prim("ITEM", TEXT), List("LIST")]),
+        ("deleteLine:ofList:", "data_deleteoflist", &[Prim("INDEX", INTEGER), List("LIST")]),
+        ("insert:at:ofList:", "data_insertatlist", &[Prim("ITEM", TEXT), Prim("INDEX", INTEGER), List("LIST")]),
+        ("setLine:ofList:to:", "data_replaceitemoflist", &[Prim("INDEX", INTEGER), List("LIST"), Prim("ITEM", TEXT)]),
+        ("getLine:ofList:", "data_itemoflist", &[Prim("INDEX", INTEGER), List("LIST")]),
+        ("lineCountOfList:", "data_lengthoflist", &[List("LIST")]),
+        ("list:contains:", "data_listcontainsitem", &[List("LIST"), Prim("ITEM", TEXT)]),
+        ("showList:", "data_showlist", &[List("LIST")]),
+        ("hideList:", "data_hidelist", &[List("LIST")]),
+    ];
+}
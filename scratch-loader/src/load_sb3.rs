@@ -1,73 +1,382 @@
-use zip::ZipArchive;
+use serde::de::DeserializeOwned;
+use serde_json::{error::Category, Value};
+use zip::{result::ZipError, ZipArchive};
 
-use crate::sb3::Sb3File;
+use crate::observer::{Phase, Progress};
+use crate::options::{Limit, LoadOptions};
+use crate::sb3::{
+    Blocks, Costume, Field, Input, List, Mutation, Project, ProjectMeta, Sb3File, Sound, Target,
+    Variable,
+};
 
-use std::{collections::HashMap, fmt::Display, fs, io::Read};
-
-use colored::Colorize;
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::Display,
+    fs,
+    io::{self, Cursor, ErrorKind, Read, Seek},
+    path::{Component, Path},
+    time::Instant,
+};
 
 #[derive(Debug)]
 pub enum Sb3LoaderError {
     MissProjectJson,
-    UnableReadFile,
-    UnableExtractFile,
-    InvaildProjectJsonFormat,
+    UnableReadFile {
+        path: String,
+        source: io::Error,
+    },
+    UnableExtractFile {
+        // `None` when the archive itself can't be opened
+        entry: Option<String>,
+        source: ZipError,
+    },
+    InvaildProjectJsonFormat {
+        entry: String,
+        // e.g. `targets[3].blocks["abc"].inputs.STEPS`, empty for syntax errors
+        path: String,
+        line: usize,
+        column: usize,
+        source: serde_json::Error,
+    },
+    AssetChecksumMismatch(String),
+    // an `md5ext` that would leave the project directory
+    InvaildAssetName(String),
+    LimitExceeded {
+        entry: Option<String>,
+        limit: Limit,
+    },
 }
 
 impl Display for Sb3LoaderError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::MissProjectJson => write!(f, "missing `project.json`"),
-            Self::UnableExtractFile => write!(f, "unable to extract file"),
-            Self::UnableReadFile => write!(f, "unable to read file, does the file exist?"),
-            Self::InvaildProjectJsonFormat => write!(f, "invaild `project.json` format"),
+            Self::UnableExtractFile {
+                entry: Some(entry),
+                source,
+            } => write!(f, "unable to extract `{}`: {}", entry, source),
+            Self::UnableExtractFile {
+                entry: None,
+                source,
+            } => write!(f, "unable to open archive: {}", source),
+            Self::UnableReadFile { path, source } => {
+                write!(f, "unable to read `{}`: {}", path, source)
+            }
+            Self::InvaildProjectJsonFormat {
+                entry,
+                path,
+                source,
+                ..
+            } => {
+                write!(f, "invaild `{}` format", entry)?;
+                if !path.is_empty() {
+                    write!(f, " at `{}`", path)?;
+                }
+                // serde_json appends the line and column itself
+                write!(f, ": {}", source)
+            }
+            Self::AssetChecksumMismatch(name) => {
+                write!(f, "md5 of asset `{}` does not match its `assetId`", name)
+            }
+            Self::InvaildAssetName(name) => {
+                write!(
+                    f,
+                    "invaild asset name `{}`, expected a plain file name",
+                    name
+                )
+            }
+            Self::LimitExceeded {
+                entry: Some(entry),
+                limit,
+            } => write!(f, "`{}` exceeds the {}", entry, limit),
+            Self::LimitExceeded { entry: None, limit } => {
+                write!(f, "archive exceeds the {}", limit)
+            }
         }
     }
 }
 
-trait HandleSb3LoaderError<T> {
-    fn handle(self, err: Sb3LoaderError) -> Result<T, Sb3LoaderError>;
+impl Error for Sb3LoaderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::UnableReadFile { source, .. } => Some(source),
+            Self::UnableExtractFile { source, .. } => Some(source),
+            Self::InvaildProjectJsonFormat { source, .. } => Some(source),
+            Self::MissProjectJson
+            | Self::AssetChecksumMismatch(_)
+            | Self::InvaildAssetName(_)
+            | Self::LimitExceeded { .. } => None,
+        }
+    }
 }
 
-impl<T, E: std::fmt::Debug> HandleSb3LoaderError<T> for Result<T, E> {
-    fn handle(self, err: Sb3LoaderError) -> Result<T, Sb3LoaderError> {
-        match self {
-            Ok(val) => Ok(val),
-            Err(_e) => {
-                // eprintln!("{:?}", _e);
-                Err(err)
-            }
+impl Sb3LoaderError {
+    pub(crate) fn read(path: &Path) -> impl FnOnce(io::Error) -> Self {
+        let path = path.display().to_string();
+        move |source| Self::UnableReadFile { path, source }
+    }
+
+    pub(crate) fn extract<E: Into<ZipError>>(entry: Option<&str>) -> impl FnOnce(E) -> Self {
+        let entry = entry.map(String::from);
+        move |source| Self::UnableExtractFile {
+            entry,
+            source: source.into(),
         }
     }
 }
 
+// deserialize a json entry, on failure `locate` walks the raw json to find the failing value,
+// since untagged enums and flattened fields hide where serde gave up
+pub(crate) fn parse_json<T: DeserializeOwned>(
+    entry: &str,
+    data: &[u8],
+    locate: fn(&Value) -> String,
+) -> Result<T, Sb3LoaderError> {
+    serde_json::from_slice(data).map_err(|source| {
+        let path = match source.classify() {
+            Category::Data => serde_json::from_slice(data)
+                .map(|val| locate(&val))
+                .unwrap_or_default(),
+            _ => String::new(),
+        };
+        Sb3LoaderError::InvaildProjectJsonFormat {
+            entry: entry.to_string(),
+            path,
+            line: source.line(),
+            column: source.column(),
+            source,
+        }
+    })
+}
+
+/// load a `.sb3` archive from the filesystem
 pub fn load(src: &str) -> Result<Sb3File, Sb3LoaderError> {
-    let file = fs::File::open(src).handle(Sb3LoaderError::UnableReadFile)?;
-    let mut archieve = ZipArchive::new(file).handle(Sb3LoaderError::UnableExtractFile)?;
+    load_with(src, &LoadOptions::default())
+}
+
+pub fn load_with(src: &str, options: &LoadOptions) -> Result<Sb3File, Sb3LoaderError> {
+    let file = fs::File::open(src).map_err(Sb3LoaderError::read(Path::new(src)))?;
+    load_reader_with(file, options)
+}
+
+/// load a `.sb3` archive already held in memory
+pub fn load_bytes(src: &[u8]) -> Result<Sb3File, Sb3LoaderError> {
+    load_bytes_with(src, &LoadOptions::default())
+}
+
+pub fn load_bytes_with(src: &[u8], options: &LoadOptions) -> Result<Sb3File, Sb3LoaderError> {
+    load_reader_with(Cursor::new(src), options)
+}
+
+/// load a `.sb3` archive from any seekable reader
+pub fn load_reader<R: Read + Seek>(src: R) -> Result<Sb3File, Sb3LoaderError> {
+    load_reader_with(src, &LoadOptions::default())
+}
+
+pub fn load_reader_with<R: Read + Seek>(
+    src: R,
+    options: &LoadOptions,
+) -> Result<Sb3File, Sb3LoaderError> {
+    let start = Instant::now();
+    let mut archieve = ZipArchive::new(src).map_err(Sb3LoaderError::extract(None))?;
+    options.limits.check_entries(archieve.len())?;
+    let mut total = 0;
+    let data = read_entry(&mut archieve, "project.json", options, &mut total)?
+        .ok_or(Sb3LoaderError::MissProjectJson)?;
+    let project: Project = parse_json("project.json", &data, locate)?;
+    // only assets some costume or sound points at are extracted, anything else is skipped
+    let mut resources = HashMap::new();
+    for (_, fname) in assets(&project) {
+        if resources.contains_key(fname) {
+            continue;
+        }
+        if let Some(data) = read_entry(&mut archieve, fname, options, &mut total)? {
+            // resource, keyed by its md5ext
+            resources.insert(fname.clone(), data);
+        }
+    }
+    report_skipped(&archieve, |fname| resources.contains_key(fname), options);
+    verify_assets(&project, &resources)?;
+    options.observer.timing(Phase::Load, start.elapsed());
+    Ok(Sb3File::new(resources, project))
+}
+
+pub(crate) fn report_skipped<R: Read + Seek>(
+    archieve: &ZipArchive<R>,
+    extracted: impl Fn(&str) -> bool,
+    options: &LoadOptions,
+) {
+    for fname in archieve.file_names() {
+        if fname != "project.json" && !extracted(fname) {
+            options.observer.progress(Progress::Skipping(fname));
+        }
+    }
+}
+
+// `None` if the archive has no such entry
+pub(crate) fn read_entry<R: Read + Seek>(
+    archieve: &mut ZipArchive<R>,
+    fname: &str,
+    options: &LoadOptions,
+    total: &mut u64,
+) -> Result<Option<Vec<u8>>, Sb3LoaderError> {
+    let file = match archieve.by_name(fname) {
+        Ok(file) => file,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(err) => return Err(Sb3LoaderError::extract(Some(fname))(err)),
+    };
+    options.observer.progress(Progress::Extracting(fname));
+    let compressed_size = file.compressed_size();
+    options
+        .limits
+        .read(
+            fname,
+            file,
+            Some(compressed_size),
+            total,
+            Sb3LoaderError::extract(Some(fname)),
+        )
+        .map(Some)
+}
+
+/// load an unpacked project, i.e. a directory holding `project.json` and its assets
+pub fn load_dir(src: &str) -> Result<Sb3File, Sb3LoaderError> {
+    load_dir_with(src, &LoadOptions::default())
+}
+
+pub fn load_dir_with(src: &str, options: &LoadOptions) -> Result<Sb3File, Sb3LoaderError> {
+    let start = Instant::now();
+    let limits = &options.limits;
+    let dir = Path::new(src);
+    let pjson = dir.join("project.json");
+    let file = match fs::File::open(&pjson) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Err(Sb3LoaderError::MissProjectJson)
+        }
+        Err(err) => return Err(Sb3LoaderError::read(&pjson)(err)),
+    };
+    let mut total = 0;
+    let data = limits.read(
+        "project.json",
+        file,
+        None,
+        &mut total,
+        Sb3LoaderError::read(&pjson),
+    )?;
+    let project: Project = parse_json("project.json", &data, locate)?;
     let mut resources = HashMap::new();
-    let mut project = None;
-    for id in 0..archieve.len() {
-        let mut file = archieve
-            .by_index(id)
-            .handle(Sb3LoaderError::UnableExtractFile)?;
-        let fname = file.name();
-        println!("  {} `{}`", "Extracting".bright_green(), fname);
-        let mut data = String::with_capacity(file.size() as usize);
-        if fname == "project.json" {
-            file.read_to_string(&mut data).unwrap();
-            let pjson =
-                serde_json::from_str(&data).handle(Sb3LoaderError::InvaildProjectJsonFormat)?;
-            project = Some(pjson);
-        } else {
-            // resource
-            let fname = fname.to_string();
-            file.read_to_string(&mut data).unwrap();
-            resources.insert(fname, data);
+    for (_, fname) in assets(&project) {
+        let mut components = Path::new(fname).components();
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        ) {
+            return Err(Sb3LoaderError::InvaildAssetName(fname.clone()));
+        }
+        let path = dir.join(fname);
+        if !path.is_file() || resources.contains_key(fname) {
+            continue;
         }
+        limits.check_entries(resources.len() + 1)?;
+        options.observer.progress(Progress::Reading(fname));
+        let file = fs::File::open(&path).map_err(Sb3LoaderError::read(&path))?;
+        let data = limits.read(fname, file, None, &mut total, Sb3LoaderError::read(&path))?;
+        resources.insert(fname.clone(), data);
     }
-    if let Some(project) = project {
-        Ok(Sb3File::new(resources, project))
-    } else {
-        Err(Sb3LoaderError::MissProjectJson)
+    verify_assets(&project, &resources)?;
+    options.observer.timing(Phase::Load, start.elapsed());
+    Ok(Sb3File::new(resources, project))
+}
+
+fn fails<T: DeserializeOwned>(val: &Value) -> bool {
+    T::deserialize(val).is_err()
+}
+
+// ids are quoted since they may hold any character, names are not
+fn locate_map<T: DeserializeOwned>(val: &Value, field: &str) -> Option<String> {
+    val.get(field)?
+        .as_object()?
+        .iter()
+        .find(|(_, item)| fails::<T>(item))
+        .map(|(id, _)| format!(".{}[{:?}]", field, id))
+}
+
+pub(crate) fn locate_seq<T: DeserializeOwned>(val: &Value, field: &str) -> Option<String> {
+    val.get(field)?
+        .as_array()?
+        .iter()
+        .position(fails::<T>)
+        .map(|idx| format!(".{}[{}]", field, idx))
+}
+
+fn locate(project: &Value) -> String {
+    if let Some(targets) = project.get("targets").and_then(Value::as_array) {
+        if let Some(idx) = targets.iter().position(fails::<Target>) {
+            return format!("targets[{}]{}", idx, locate_target(&targets[idx]));
+        }
+    }
+    match project.get("meta") {
+        Some(meta) if fails::<ProjectMeta>(meta) => "meta".to_string(),
+        _ => String::new(),
+    }
+}
+
+fn locate_target(target: &Value) -> String {
+    if let Some(blocks) = target.get("blocks").and_then(Value::as_object) {
+        for (id, block) in blocks {
+            let single = serde_json::json!({ "blocks": { id: block } });
+            if fails::<Blocks>(&single) {
+                return format!(".blocks[{:?}]{}", id, locate_block(block));
+            }
+        }
+    }
+    locate_map::<Variable>(target, "variables")
+        .or_else(|| locate_map::<List>(target, "lists"))
+        .or_else(|| locate_map::<String>(target, "broadcasts"))
+        .or_else(|| locate_seq::<Costume>(target, "costumes"))
+        .or_else(|| locate_seq::<Sound>(target, "sounds"))
+        .unwrap_or_default()
+}
+
+fn locate_block(block: &Value) -> String {
+    let named = |field: &str, fails: fn(&Value) -> bool| {
+        let (name, _) = block
+            .get(field)?
+            .as_object()?
+            .iter()
+            .find(|(_, item)| fails(item))?;
+        Some(format!(".{}.{}", field, name))
+    };
+    named("inputs", fails::<Input>)
+        .or_else(|| named("fields", fails::<Field>))
+        .or_else(|| {
+            let mutation = block.get("mutation").filter(|m| !m.is_null())?;
+            fails::<Mutation>(mutation).then(|| ".mutation".to_string())
+        })
+        .unwrap_or_default()
+}
+
+// (assetId, md5ext) of every costume and sound
+fn assets(project: &Project) -> impl Iterator<Item = (&String, &String)> {
+    project.targets.iter().flat_map(|t| {
+        let costumes = t.costumes.iter().map(|c| (&c.asset_id, &c.md5ext));
+        let sounds = t.sounds.iter().map(|s| (&s.asset_id, &s.md5ext));
+        costumes.chain(sounds)
+    })
+}
+
+pub(crate) fn verify_assets(
+    project: &Project,
+    resources: &HashMap<String, Vec<u8>>,
+) -> Result<(), Sb3LoaderError> {
+    for (asset_id, md5ext) in assets(project) {
+        if let Some(data) = resources.get(md5ext) {
+            if format!("{:x}", md5::compute(data)) != *asset_id {
+                return Err(Sb3LoaderError::AssetChecksumMismatch(md5ext.clone()));
+            }
+        }
     }
+    Ok(())
 }
@@ -0,0 +1,63 @@
+use std::{fmt::Display, time::Duration};
+
+// everything ira reports while loading and parsing, the libraries never print themselves.
+// every method does nothing by default, so implementors only pick what they care about
+pub trait Observer {
+    fn progress(&self, _event: Progress) {}
+
+    fn warning(&self, _warning: &Warning) {}
+
+    fn timing(&self, _phase: Phase, _elapsed: Duration) {}
+}
+
+// the default observer, drops everything
+pub struct Silent;
+
+impl Observer for Silent {}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Progress<'a> {
+    // archive entry being decompressed
+    Extracting(&'a str),
+    // file of an unpacked project being read
+    Reading(&'a str),
+    // archive entry no costume or sound refers to
+    Skipping(&'a str),
+    // target whose blocks are being parsed
+    Parsing(&'a str),
+}
+
+#[derive(Debug, Clone)]
+pub struct Warning {
+    // name of the sprite or stage
+    pub target: Option<String>,
+    pub block: Option<String>,
+    pub message: String,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        match (&self.target, &self.block) {
+            (Some(target), Some(block)) => write!(f, " (block `{}` of `{}`)", block, target),
+            (Some(target), None) => write!(f, " (in `{}`)", target),
+            (None, Some(block)) => write!(f, " (block `{}`)", block),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Load,
+    Parse,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Load => write!(f, "load"),
+            Self::Parse => write!(f, "parse"),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::{fmt::Display, io::Read, rc::Rc};
+
+use crate::{
+    load_sb3::Sb3LoaderError,
+    observer::{Observer, Silent},
+};
+
+#[derive(Clone)]
+pub struct LoadOptions {
+    pub limits: Limits,
+    pub observer: Rc<dyn Observer>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            limits: Limits::default(),
+            observer: Rc::new(Silent),
+        }
+    }
+}
+
+// caps on what an archive may expand to, projects from untrusted sources
+// should never be loaded with `Limits::unlimited`
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    // bytes of all extracted entries together
+    pub max_total_size: u64,
+    pub max_entry_size: u64,
+    pub max_entries: usize,
+    // uncompressed / compressed size, only checked for entries above `RATIO_FLOOR`
+    // since tiny files compress well without being a threat
+    pub max_compression_ratio: u64,
+}
+
+const MIB: u64 = 1024 * 1024;
+const RATIO_FLOOR: u64 = MIB;
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_total_size: 256 * MIB,
+            max_entry_size: 64 * MIB,
+            max_entries: 10_000,
+            max_compression_ratio: 100,
+        }
+    }
+}
+
+impl Limits {
+    pub fn unlimited() -> Self {
+        Self {
+            max_total_size: u64::MAX,
+            max_entry_size: u64::MAX,
+            max_entries: usize::MAX,
+            max_compression_ratio: u64::MAX,
+        }
+    }
+
+    pub(crate) fn check_entries(&self, count: usize) -> Result<(), Sb3LoaderError> {
+        if count > self.max_entries {
+            return Err(Sb3LoaderError::LimitExceeded {
+                entry: None,
+                limit: Limit::EntryCount(self.max_entries),
+            });
+        }
+        Ok(())
+    }
+
+    // never trust the size an archive claims, stop reading once a limit is hit instead
+    pub(crate) fn read<R: Read>(
+        &self,
+        name: &str,
+        src: R,
+        compressed_size: Option<u64>,
+        total: &mut u64,
+        on_err: impl FnOnce(std::io::Error) -> Sb3LoaderError,
+    ) -> Result<Vec<u8>, Sb3LoaderError> {
+        let exceeded = |limit| Sb3LoaderError::LimitExceeded {
+            entry: Some(name.to_string()),
+            limit,
+        };
+        let cap = self
+            .max_entry_size
+            .min(self.max_total_size.saturating_sub(*total));
+        let mut data = Vec::new();
+        src.take(cap.saturating_add(1))
+            .read_to_end(&mut data)
+            .map_err(on_err)?;
+        let size = data.len() as u64;
+        if size > self.max_entry_size {
+            return Err(exceeded(Limit::EntrySize(self.max_entry_size)));
+        }
+        if size > cap {
+            return Err(exceeded(Limit::TotalSize(self.max_total_size)));
+        }
+        if let Some(compressed) = compressed_size {
+            if size > RATIO_FLOOR && size / compressed.max(1) > self.max_compression_ratio {
+                return Err(exceeded(Limit::CompressionRatio(
+                    self.max_compression_ratio,
+                )));
+            }
+        }
+        *total += size;
+        Ok(data)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    TotalSize(u64),
+    EntrySize(u64),
+    EntryCount(usize),
+    CompressionRatio(u64),
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TotalSize(max) => write!(f, "total size limit of {} bytes", max),
+            Self::EntrySize(max) => write!(f, "entry size limit of {} bytes", max),
+            Self::EntryCount(max) => write!(f, "limit of {} entries", max),
+            Self::CompressionRatio(max) => write!(f, "compression ratio limit of {}", max),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use zip::{result::ZipError, write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::sb3::Sb3File;
+
+use std::{
+    error::Error,
+    fmt::Display,
+    fs,
+    io::{self, Cursor, Seek, Write},
+};
+
+#[derive(Debug)]
+pub enum Sb3SaverError {
+    UnableWriteFile(io::Error),
+    UnableCompressFile(ZipError),
+    InvaildProjectJsonFormat(serde_json::Error),
+}
+
+impl Display for Sb3SaverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnableWriteFile(err) => write!(f, "unable to write file: {}", err),
+            Self::UnableCompressFile(err) => write!(f, "unable to compress file: {}", err),
+            Self::InvaildProjectJsonFormat(err) => {
+                write!(f, "unable to serialize `project.json`: {}", err)
+            }
+        }
+    }
+}
+
+impl Error for Sb3SaverError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::UnableWriteFile(err) => Some(err),
+            Self::UnableCompressFile(err) => Some(err),
+            Self::InvaildProjectJsonFormat(err) => Some(err),
+        }
+    }
+}
+
+/// save a project as a `.sb3` archive on the filesystem
+pub fn save(file: &Sb3File, dest: &str) -> Result<(), Sb3SaverError> {
+    let out = fs::File::create(dest).map_err(Sb3SaverError::UnableWriteFile)?;
+    save_writer(file, out)?;
+    Ok(())
+}
+
+/// save a project as a `.sb3` archive in memory
+pub fn save_bytes(file: &Sb3File) -> Result<Vec<u8>, Sb3SaverError> {
+    Ok(save_writer(file, Cursor::new(Vec::new()))?.into_inner())
+}
+
+/// save a project as a `.sb3` archive into any seekable writer, handing the writer back
+pub fn save_writer<W: Write + Seek>(file: &Sb3File, dest: W) -> Result<W, Sb3SaverError> {
+    let pjson =
+        serde_json::to_vec(&file.project).map_err(Sb3SaverError::InvaildProjectJsonFormat)?;
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut archieve = ZipWriter::new(dest);
+    archieve
+        .start_file("project.json", options)
+        .map_err(Sb3SaverError::UnableCompressFile)?;
+    archieve
+        .write_all(&pjson)
+        .map_err(Sb3SaverError::UnableWriteFile)?;
+    // stable entry order, hash map order is random
+    let mut resources: Vec<_> = file.resources.iter().collect();
+    resources.sort_by_key(|(fname, _)| *fname);
+    for (fname, data) in resources {
+        archieve
+            .start_file(fname.as_str(), options)
+            .map_err(Sb3SaverError::UnableCompressFile)?;
+        archieve
+            .write_all(data)
+            .map_err(Sb3SaverError::UnableWriteFile)?;
+    }
+    archieve.finish().map_err(Sb3SaverError::UnableCompressFile)
+}
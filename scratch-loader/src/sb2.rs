@@ -0,0 +1,125 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::sb3::{Num, ScratchValue};
+
+// the stage and every sprite share the same object layout in sb2,
+// sprite-only fields are simply absent on the stage
+#[derive(Debug, Deserialize)]
+pub struct Sb2Object {
+    #[serde(rename = "objName")]
+    pub name: String,
+    #[serde(default)]
+    pub variables: Vec<Sb2Variable>,
+    #[serde(default)]
+    pub lists: Vec<Sb2List>,
+    #[serde(default)]
+    pub scripts: Vec<Sb2Script>,
+    #[serde(default)]
+    pub sounds: Vec<Sb2Sound>,
+    #[serde(default)]
+    pub costumes: Vec<Sb2Costume>,
+    #[serde(rename = "currentCostumeIndex", default)]
+    pub current_costume: Num,
+    #[serde(rename = "penLayerMD5")]
+    pub pen_layer_md5: Option<String>,
+    #[serde(rename = "penLayerID")]
+    pub pen_layer_id: Option<i64>,
+    #[serde(rename = "tempoBPM")]
+    pub tempo: Option<Num>,
+    #[serde(default)]
+    pub children: Vec<Sb2Child>,
+    #[serde(rename = "scratchX", default)]
+    pub x: Num,
+    #[serde(rename = "scratchY", default)]
+    pub y: Num,
+    #[serde(default = "default_scale")]
+    pub scale: Num,
+    #[serde(default = "default_direction")]
+    pub direction: Num,
+    #[serde(rename = "rotationStyle")]
+    pub rotation_style: Option<String>,
+    #[serde(rename = "isDraggable", default)]
+    pub draggable: bool,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+}
+
+fn default_scale() -> Num {
+    1.0
+}
+
+fn default_direction() -> Num {
+    90.0
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Sb2Child {
+    Sprite(Box<Sb2Object>),
+    // variable & list watchers, ignored for now
+    Other(Value),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Sb2Variable {
+    pub name: String,
+    pub value: ScratchValue,
+    #[serde(rename = "isPersistent", default)]
+    pub is_persistent: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Sb2List {
+    #[serde(rename = "listName")]
+    pub name: String,
+    #[serde(default)]
+    pub contents: Vec<ScratchValue>,
+}
+
+// [x, y, [block, ...]], blocks are kept as raw json and converted later
+#[derive(Debug, Deserialize)]
+pub struct Sb2Script(pub Num, pub Num, pub Vec<Value>);
+
+#[derive(Debug, Deserialize)]
+pub struct Sb2Costume {
+    #[serde(rename = "costumeName")]
+    pub name: String,
+    #[serde(rename = "baseLayerID", default = "default_layer_id")]
+    pub layer_id: i64,
+    #[serde(rename = "baseLayerMD5")]
+    pub md5: String,
+    #[serde(rename = "bitmapResolution", default = "default_resolution")]
+    pub resolution: Num,
+    #[serde(rename = "rotationCenterX", default)]
+    pub center_x: Num,
+    #[serde(rename = "rotationCenterY", default)]
+    pub center_y: Num,
+}
+
+fn default_layer_id() -> i64 {
+    -1
+}
+
+fn default_resolution() -> Num {
+    1.0
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Sb2Sound {
+    #[serde(rename = "soundName")]
+    pub name: String,
+    #[serde(rename = "soundID", default = "default_layer_id")]
+    pub sound_id: i64,
+    pub md5: String,
+    #[serde(rename = "sampleCount", default)]
+    pub sample_count: Num,
+    #[serde(default)]
+    pub rate: Num,
+    #[serde(default)]
+    pub format: String,
+}
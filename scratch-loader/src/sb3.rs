@@ -1,6 +1,11 @@
 use std::collections::HashMap;
 
-use serde::{Deserialize, Deserializer};
+use serde::{
+    de::DeserializeOwned, ser::SerializeMap, Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_json::{Map, Value};
+
+use crate::cast;
 
 pub type Num = f64;
 
@@ -28,37 +33,48 @@ macro_rules! impl_deser_for_tuple_struct {
 
 #[derive(Debug)]
 pub struct Sb3File {
-    pub resources: HashMap<String, String>,
+    pub resources: HashMap<String, Vec<u8>>,
     pub project: Project,
 }
 
 impl Sb3File {
-    pub fn new(resources: HashMap<String, String>, project: Project) -> Self {
+    pub fn new(resources: HashMap<String, Vec<u8>>, project: Project) -> Self {
         Self { resources, project }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Project {
     pub targets: Vec<Target>,
+    #[serde(default)]
     pub extensions: Vec<String>,
     pub meta: ProjectMeta,
+    // `monitors` and anything else ira doesn't model, written back as is
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ProjectMeta {
     pub semver: String,
+    #[serde(default)]
     pub vm: String,
+    #[serde(default)]
     pub agent: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Target {
     #[serde(rename = "isStage")]
     pub is_stage: bool,
     pub name: String,
+    #[serde(default)]
     pub variables: HashMap<String, Variable>,
+    #[serde(default)]
     pub lists: HashMap<String, List>,
+    #[serde(default)]
     pub broadcasts: HashMap<String, Broadcast>,
     #[serde(flatten)]
     pub blocks: Blocks,
@@ -69,50 +85,72 @@ pub struct Target {
     pub volume: Num,
     #[serde(rename = "layerOrder")]
     pub layer_order: usize,
+    // sprite only, absent on the stage
     #[serde(flatten)]
     pub position: Position,
-    #[serde(default)]
-    pub size: Num,
-    #[serde(default)]
-    pub direction: Num,
-    #[serde(default)]
-    pub draggable: bool,
-    #[serde(rename = "rotationStyle", default)]
-    pub rotation_style: RotationStyle,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<Num>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<Num>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub draggable: Option<bool>,
+    #[serde(rename = "rotationStyle", skip_serializing_if = "Option::is_none")]
+    pub rotation_style: Option<RotationStyle>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>,
+    // `comments`, `tempo`, `videoState`, ...
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum RotationStyle {
     #[serde(rename = "left-right")]
     LeftRight,
     #[serde(rename = "don't rotate")]
     NoRotation,
-    #[serde(other)]
+    #[default]
+    #[serde(rename = "all around", other)]
     AllAround,
 }
 
-impl Default for RotationStyle {
-    fn default() -> Self {
-        Self::AllAround
-    }
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Position {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<Num>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<Num>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct Position {
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Sound {
+    pub name: String,
+    #[serde(rename = "assetId")]
+    pub asset_id: String,
+    pub md5ext: String,
+    #[serde(rename = "dataFormat")]
+    pub data_format: SoundFormat,
+    // `adpcm` for compressed wav, empty otherwise
     #[serde(default)]
-    pub x: Num,
+    pub format: String,
     #[serde(default)]
-    pub y: Num,
+    pub rate: Num,
+    #[serde(rename = "sampleCount", default)]
+    pub sample_count: Num,
 }
 
-// todo! complete this placeholder
-#[derive(Debug, Deserialize)]
-pub struct Sound {}
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub enum SoundFormat {
+    #[serde(rename = "wav")]
+    AudioWAV,
+    #[serde(rename = "mp3")]
+    AudioMP3,
+}
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Costume {
     pub name: String,
-    #[serde(flatten)]
+    #[serde(rename = "dataFormat")]
     pub data_format: ImageFormat,
     #[serde(rename = "assetId")]
     pub asset_id: String,
@@ -121,47 +159,254 @@ pub struct Costume {
     pub center_x: Num,
     #[serde(rename = "rotationCenterY")]
     pub center_y: Num,
+    // `bitmapResolution`
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(tag = "dataFormat")]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
 pub enum ImageFormat {
     #[serde(rename = "png")]
     ImagePNG,
     #[serde(rename = "svg")]
     ImageSVG,
+    #[serde(rename = "jpg")]
+    ImageJPG,
 }
 
+// name, value, is cloud variable
 #[derive(Debug)]
-pub struct Variable(pub String, pub ScratchValue);
+pub struct Variable(pub String, pub ScratchValue, pub bool);
 
-impl_deser_for_tuple_struct!(Variable(0 => String, 1 => ScratchValue));
+impl<'de> Deserialize<'de> for Variable {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawVariable {
+            Cloud(String, ScratchValue, bool),
+            Local(String, ScratchValue),
+        }
 
-#[derive(Debug)]
-pub struct List(pub String, pub Vec<(ScratchValue, ScratchValue)>);
+        Ok(match RawVariable::deserialize(deserializer)? {
+            RawVariable::Cloud(name, val, cloud) => Variable(name, val, cloud),
+            RawVariable::Local(name, val) => Variable(name, val, false),
+        })
+    }
+}
+
+impl Serialize for Variable {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if self.2 {
+            (&self.0, &self.1, true).serialize(serializer)
+        } else {
+            (&self.0, &self.1).serialize(serializer)
+        }
+    }
+}
 
-impl_deser_for_tuple_struct!(List(0 => String, 1 => Vec<(ScratchValue, ScratchValue)>));
+#[derive(Debug, Serialize)]
+pub struct List(pub String, pub Vec<ScratchValue>);
+
+impl_deser_for_tuple_struct!(List(0 => String, 1 => Vec<ScratchValue>));
 
 pub type Broadcast = String;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize)]
 pub struct Blocks {
     pub blocks: HashMap<String, Block>,
 }
 
-#[derive(Debug, Deserialize)]
+impl<'de> Deserialize<'de> for Blocks {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawBlock {
+            Block(Box<Block>),
+            // loose reporters are compressed like inputs, plus their position
+            Primitive(u8, ScratchValue, Option<String>, Option<Num>, Option<Num>),
+        }
+
+        #[derive(Deserialize)]
+        struct RawBlocks {
+            #[serde(default)]
+            blocks: HashMap<String, RawBlock>,
+        }
+
+        let raw = RawBlocks::deserialize(deserializer)?;
+        let mut blocks = HashMap::with_capacity(raw.blocks.len());
+        for (id, block) in raw.blocks {
+            let block = match block {
+                RawBlock::Block(block) => *block,
+                RawBlock::Primitive(kind, val, val_id, x, y) => {
+                    let mut block = Block::from_primitive(kind, val, val_id).ok_or_else(|| {
+                        serde::de::Error::custom(format!("unknown primitive type {}", kind))
+                    })?;
+                    block.x = x;
+                    block.y = y;
+                    block
+                }
+            };
+            blocks.insert(id, block);
+        }
+        Ok(Blocks { blocks })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Block {
     pub opcode: String,
+    #[serde(default)]
     pub next: Option<String>,
+    #[serde(default)]
     pub parent: Option<String>,
+    #[serde(default)]
     pub inputs: HashMap<String, Input>,
+    #[serde(default)]
     pub fields: HashMap<String, Field>,
-    #[serde(rename = "topLevel")]
+    #[serde(default)]
+    pub shadow: bool,
+    #[serde(rename = "topLevel", default)]
     pub top_level: bool,
-    // ignore shadow and position
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mutation: Option<Mutation>,
+    // position of top level blocks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<Num>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<Num>,
+    // id of the attached comment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+// extra state of procedure blocks and `control_stop`
+#[derive(Debug, Default, Clone)]
+pub struct Mutation {
+    pub proccode: Option<String>,
+    pub argument_ids: Vec<String>,
+    pub argument_names: Vec<String>,
+    pub argument_defaults: Vec<ScratchValue>,
+    pub warp: bool,
+    pub has_next: bool,
+    // only the mutations of `procedures_prototype` carry argument names and defaults
+    pub prototype: bool,
 }
 
-#[derive(Debug, Deserialize)]
+impl<'de> Deserialize<'de> for Mutation {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // the argument lists are json encoded into strings, flags are `"true"` / `"false"`
+        #[derive(Deserialize)]
+        struct RawMutation {
+            proccode: Option<String>,
+            argumentids: Option<Value>,
+            argumentnames: Option<Value>,
+            argumentdefaults: Option<Value>,
+            warp: Option<Value>,
+            hasnext: Option<Value>,
+        }
+
+        fn list<T: DeserializeOwned, E: serde::de::Error>(val: Option<Value>) -> Result<Vec<T>, E> {
+            match val {
+                Some(Value::String(s)) => serde_json::from_str(&s).map_err(E::custom),
+                Some(val @ Value::Array(_)) => serde_json::from_value(val).map_err(E::custom),
+                _ => Ok(Vec::new()),
+            }
+        }
+
+        fn flag(val: Option<Value>) -> bool {
+            match val {
+                Some(Value::Bool(b)) => b,
+                Some(Value::String(s)) => s == "true",
+                _ => false,
+            }
+        }
+
+        let raw = RawMutation::deserialize(deserializer)?;
+        Ok(Mutation {
+            prototype: raw.argumentnames.is_some(),
+            proccode: raw.proccode,
+            argument_ids: list(raw.argumentids)?,
+            argument_names: list(raw.argumentnames)?,
+            argument_defaults: list(raw.argumentdefaults)?,
+            warp: flag(raw.warp),
+            has_next: flag(raw.hasnext),
+        })
+    }
+}
+
+impl Serialize for Mutation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        fn json<T: Serialize>(val: &T) -> String {
+            serde_json::to_string(val).unwrap()
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("tagName", "mutation")?;
+        map.serialize_entry("children", &[] as &[Value])?;
+        match &self.proccode {
+            Some(proccode) => {
+                map.serialize_entry("proccode", proccode)?;
+                map.serialize_entry("argumentids", &json(&self.argument_ids))?;
+                if self.prototype {
+                    map.serialize_entry("argumentnames", &json(&self.argument_names))?;
+                    map.serialize_entry("argumentdefaults", &json(&self.argument_defaults))?;
+                }
+                map.serialize_entry("warp", &self.warp.to_string())?;
+            }
+            None => map.serialize_entry("hasnext", &self.has_next.to_string())?,
+        }
+        map.end()
+    }
+}
+
+impl Block {
+    // expand a top level primitive the way scratch-vm does
+    fn from_primitive(kind: u8, val: ScratchValue, id: Option<String>) -> Option<Self> {
+        let (opcode, field) = match kind {
+            4 => ("math_number", "NUM"),
+            5 => ("math_positive_number", "NUM"),
+            6 => ("math_whole_number", "NUM"),
+            7 => ("math_integer", "NUM"),
+            8 => ("math_angle", "NUM"),
+            9 => ("colour_picker", "COLOUR"),
+            10 => ("text", "TEXT"),
+            11 => ("event_broadcast_menu", "BROADCAST_OPTION"),
+            12 => ("data_variable", "VARIABLE"),
+            13 => ("data_listcontents", "LIST"),
+            _ => return None,
+        };
+        Some(Self {
+            opcode: opcode.to_string(),
+            next: None,
+            parent: None,
+            inputs: HashMap::new(),
+            fields: HashMap::from([(field.to_string(), Field(val.force_str(), id))]),
+            shadow: false,
+            top_level: true,
+            mutation: None,
+            x: None,
+            y: None,
+            comment: None,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum Input {
     // 1 -> shadow & 2 -> no_shadow
@@ -170,21 +415,37 @@ pub enum Input {
     Hidden(u8, InputType, InputType),
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum InputType {
     Block(String),
     NumOrStr(u8, ScratchValue),
     BroadcastOrVarOrList(u8, String, String),
     TopVarOrList(u8, String, String, Num, Num),
+    // `null`, e.g. an empty substack or a cleared obscured shadow
+    Empty,
 }
 
-#[derive(Debug)]
+// value, id of the referenced variable / list / broadcast
+#[derive(Debug, Serialize)]
 pub struct Field(pub String, pub Option<String>);
 
-impl_deser_for_tuple_struct!(Field(0 => String, 1 => Option<String>));
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // values are usually strings, but numbers show up in e.g. `note` menus,
+        // and some editors drop the trailing `null` id
+        let raw: Vec<Option<ScratchValue>> = Deserialize::deserialize(deserializer)?;
+        let mut raw = raw.into_iter();
+        let val = raw.next().flatten().map(|v| v.force_str());
+        let id = raw.next().flatten().map(|v| v.force_str());
+        Ok(Field(val.unwrap_or_default(), id))
+    }
+}
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(untagged)]
 pub enum ScratchValue {
     Str(String),
@@ -194,28 +455,14 @@ pub enum ScratchValue {
 
 impl ScratchValue {
     pub fn force_num(&self) -> Num {
-        match self {
-            Self::Num(num) => *num,
-            Self::Bool(true) => 1.0,
-            _ => 0.0,
-        }
+        cast::to_number(self)
     }
 
-    #[allow(illegal_floating_point_literal_pattern)]
     pub fn force_bool(&self) -> bool {
-        match self {
-            Self::Bool(bool) => *bool,
-            Self::Str(s) if s.as_str() == "" => false,
-            Self::Num(0.0) => false,
-            _ => true,
-        }
+        cast::to_boolean(self)
     }
 
     pub fn force_str(&self) -> String {
-        match self {
-            Self::Num(num) => num.to_string(),
-            Self::Bool(bool) => bool.to_string(),
-            Self::Str(str) => str.clone(),
-        }
+        cast::to_string(self)
     }
 }
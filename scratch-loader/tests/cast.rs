@@ -0,0 +1,128 @@
+use std::cmp::Ordering;
+
+use scratch_loader::{
+    cast::{compare, number_to_string, to_boolean, to_number, to_string},
+    sb3::ScratchValue,
+};
+
+fn s(val: &str) -> ScratchValue {
+    ScratchValue::Str(val.to_string())
+}
+
+fn n(val: f64) -> ScratchValue {
+    ScratchValue::Num(val)
+}
+
+// expected values are taken from scratch-vm's `Cast` running in node
+#[test]
+fn string_to_number() {
+    let cases: &[(&str, f64)] = &[
+        ("12", 12.0),
+        (" 12 ", 12.0),
+        ("\t\n12 ", 12.0),
+        ("\u{feff}7", 7.0),
+        ("\u{85} 7", 0.0),
+        ("0x1A", 26.0),
+        ("0XFF", 255.0),
+        ("0b101", 5.0),
+        ("0o17", 15.0),
+        ("-0x10", 0.0),
+        ("0x", 0.0),
+        ("1e3", 1000.0),
+        ("1E-2", 0.01),
+        (".5", 0.5),
+        ("5.", 5.0),
+        ("-.5", -0.5),
+        ("+5", 5.0),
+        ("Infinity", f64::INFINITY),
+        ("-Infinity", f64::NEG_INFINITY),
+        ("1e400", f64::INFINITY),
+        ("infinity", 0.0),
+        ("inf", 0.0),
+        ("nan", 0.0),
+        ("NaN", 0.0),
+        ("1_000", 0.0),
+        ("", 0.0),
+        ("   ", 0.0),
+        ("abc", 0.0),
+        ("12abc", 0.0),
+        ("1e", 0.0),
+        ("e5", 0.0),
+        (".", 0.0),
+        ("0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF", 8.711228593176025e40),
+    ];
+    for (src, expected) in cases {
+        assert_eq!(to_number(&s(src)), *expected, "Number({:?})", src);
+    }
+    assert_eq!(to_number(&n(f64::NAN)), 0.0);
+    assert_eq!(to_number(&ScratchValue::Bool(true)), 1.0);
+}
+
+#[test]
+fn number_formatting() {
+    let cases: &[(f64, &str)] = &[
+        (1e21, "1e+21"),
+        (1e20, "100000000000000000000"),
+        (123456789012345680000.0, "123456789012345680000"),
+        (0.000001, "0.000001"),
+        (0.0000001, "1e-7"),
+        (1.5e-7, "1.5e-7"),
+        (-0.0, "0"),
+        (0.0, "0"),
+        (1.0 / 3.0, "0.3333333333333333"),
+        (100.0, "100"),
+        (1.5, "1.5"),
+        (-2.5e-10, "-2.5e-10"),
+        (9007199254740992.0, "9007199254740992"),
+        (123e-20, "1.23e-18"),
+        (1e100, "1e+100"),
+        (f64::NAN, "NaN"),
+        (f64::INFINITY, "Infinity"),
+        (f64::NEG_INFINITY, "-Infinity"),
+        (0.1 + 0.2, "0.30000000000000004"),
+    ];
+    for (num, expected) in cases {
+        assert_eq!(number_to_string(*num), *expected, "String({})", num);
+    }
+    assert_eq!(to_string(&ScratchValue::Bool(false)), "false");
+    assert_eq!(to_string(&s("  x ")), "  x ");
+}
+
+#[test]
+fn booleans() {
+    for falsy in ["", "0", "false", "FALSE", "False"] {
+        assert!(!to_boolean(&s(falsy)), "{:?}", falsy);
+    }
+    for truthy in ["0.0", " ", "true", "null"] {
+        assert!(to_boolean(&s(truthy)), "{:?}", truthy);
+    }
+    assert!(!to_boolean(&n(0.0)));
+    assert!(!to_boolean(&n(-0.0)));
+    assert!(!to_boolean(&n(f64::NAN)));
+    assert!(to_boolean(&n(1.0)));
+}
+
+#[test]
+fn comparison() {
+    use Ordering::*;
+    let cases = [
+        (s("10"), s("9"), Greater),
+        (s("10"), n(9.0), Greater),
+        (s("abc"), s("ABC"), Equal),
+        (s("a"), s("b"), Less),
+        (s(" "), n(0.0), Less),
+        (n(0.0), s(" "), Greater),
+        (s(""), n(0.0), Less),
+        (s("1e1"), s("10"), Equal),
+        (s("Infinity"), n(f64::INFINITY), Equal),
+        (s("apple"), n(1.0), Greater),
+        (ScratchValue::Bool(true), s("true"), Equal),
+        (ScratchValue::Bool(true), n(1.0), Equal),
+        (s("0x10"), n(16.0), Equal),
+        (s("é"), s("z"), Greater),
+        (s("Z"), s("a"), Greater),
+    ];
+    for (a, b, expected) in cases {
+        assert_eq!(compare(&a, &b), expected, "compare({:?}, {:?})", a, b);
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    error::Error,
+    io::{Cursor, ErrorKind, Write},
+};
+
+use scratch_loader::load_sb3::{load, load_bytes, load_dir, Sb3LoaderError};
+use serde_json::{json, Value};
+use zip::{write::FileOptions, ZipWriter};
+
+fn archive(pjson: &[u8]) -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file("project.json", FileOptions::default())
+        .unwrap();
+    zip.write_all(pjson).unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+fn target(name: &str, blocks: Value) -> Value {
+    json!({
+        "isStage": name == "Stage",
+        "name": name,
+        "variables": { "v": ["my variable", 0] },
+        "blocks": blocks,
+        "costumes": [],
+        "currentCostume": 0,
+        "sounds": [],
+        "volume": 100,
+        "layerOrder": 0
+    })
+}
+
+fn project(sprite: Value) -> Vec<u8> {
+    let project = json!({
+        "targets": [target("Stage", json!({})), sprite],
+        "meta": { "semver": "3.0.0" }
+    });
+    serde_json::to_vec_pretty(&project).unwrap()
+}
+
+#[test]
+fn json_path_to_input() {
+    let sprite = target(
+        "Sprite1",
+        json!({
+            "abc": {
+                "opcode": "motion_movesteps",
+                "inputs": { "STEPS": [1, [4, "10"], "x", "y"] },
+                "fields": {},
+                "topLevel": true
+            }
+        }),
+    );
+    let err = load_bytes(&archive(&project(sprite))).unwrap_err();
+    match &err {
+        Sb3LoaderError::InvaildProjectJsonFormat {
+            entry, path, line, ..
+        } => {
+            assert_eq!(entry, "project.json");
+            assert_eq!(path, r#"targets[1].blocks["abc"].inputs.STEPS"#);
+            assert!(*line > 1);
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+    assert!(err.source().is_some());
+    assert!(err
+        .to_string()
+        .contains(r#"at `targets[1].blocks["abc"].inputs.STEPS`"#));
+}
+
+#[test]
+fn json_path_to_variable() {
+    let mut sprite = target("Sprite1", json!({}));
+    sprite["variables"]["v"] = json!({ "name": "my variable" });
+    let err = load_bytes(&archive(&project(sprite))).unwrap_err();
+    assert!(matches!(
+        err,
+        Sb3LoaderError::InvaildProjectJsonFormat { path, .. } if path == r#"targets[1].variables["v"]"#
+    ));
+}
+
+#[test]
+fn json_syntax_error() {
+    let err = load_bytes(&archive(b"{\n  \"targets\": [,\n}")).unwrap_err();
+    match err {
+        Sb3LoaderError::InvaildProjectJsonFormat {
+            path, line, column, ..
+        } => {
+            assert_eq!(path, "");
+            assert_eq!((line, column), (2, 15));
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[test]
+fn not_an_archive() {
+    let err = load_bytes(b"definitely not a zip").unwrap_err();
+    assert!(matches!(
+        err,
+        Sb3LoaderError::UnableExtractFile { entry: None, .. }
+    ));
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn missing_file() {
+    let err = load("/nonexistent/project.sb3").unwrap_err();
+    match &err {
+        Sb3LoaderError::UnableReadFile { path, source } => {
+            assert_eq!(path, "/nonexistent/project.sb3");
+            assert_eq!(source.kind(), ErrorKind::NotFound);
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[test]
+fn asset_outside_project_dir() {
+    let dir = std::env::temp_dir().join(format!("scratch-loader-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for name in ["../secret.svg", "/etc/passwd", "assets/a.svg", ".."] {
+        let mut sprite = target("Sprite1", json!({}));
+        sprite["costumes"] = json!([{
+            "assetId": "secret",
+            "name": "costume1",
+            "md5ext": name,
+            "dataFormat": "svg",
+            "rotationCenterX": 0,
+            "rotationCenterY": 0
+        }]);
+        std::fs::write(dir.join("project.json"), project(sprite)).unwrap();
+        let err = load_dir(dir.to_str().unwrap()).unwrap_err();
+        assert!(
+            matches!(&err, Sb3LoaderError::InvaildAssetName(n) if n == name),
+            "unexpected error {:?}",
+            err
+        );
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,148 @@
+use std::io::{Cursor, Write};
+
+use scratch_loader::{
+    load_sb3::{load_bytes_with, Sb3LoaderError},
+    options::{Limit, Limits, LoadOptions},
+};
+use serde_json::json;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+const ASSET: &[u8] = br#"<svg xmlns="http://www.w3.org/2000/svg"/>"#;
+
+fn md5ext(data: &[u8]) -> String {
+    format!("{:x}.svg", md5::compute(data))
+}
+
+fn project_json(asset: &[u8]) -> Vec<u8> {
+    let md5ext = md5ext(asset);
+    let project = json!({
+        "targets": [{
+            "isStage": true,
+            "name": "Stage",
+            "costumes": [{
+                "name": "backdrop1",
+                "dataFormat": "svg",
+                "assetId": md5ext.trim_end_matches(".svg"),
+                "md5ext": md5ext,
+                "rotationCenterX": 0,
+                "rotationCenterY": 0
+            }],
+            "currentCostume": 0,
+            "sounds": [],
+            "volume": 100,
+            "layerOrder": 0
+        }],
+        "meta": { "semver": "3.0.0" }
+    });
+    serde_json::to_vec(&project).unwrap()
+}
+
+fn archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in entries {
+        zip.start_file(*name, options).unwrap();
+        zip.write_all(data).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+fn load(src: &[u8], limits: Limits) -> Result<scratch_loader::sb3::Sb3File, Sb3LoaderError> {
+    load_bytes_with(
+        src,
+        &LoadOptions {
+            limits,
+            ..Default::default()
+        },
+    )
+}
+
+fn exceeded(err: Sb3LoaderError) -> (Option<String>, Limit) {
+    match err {
+        Sb3LoaderError::LimitExceeded { entry, limit } => (entry, limit),
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[test]
+fn within_limits() {
+    let pjson = project_json(ASSET);
+    let src = archive(&[("project.json", &pjson), (&md5ext(ASSET), ASSET)]);
+    let file = load(&src, Limits::default()).unwrap();
+    assert_eq!(file.resources[&md5ext(ASSET)], ASSET);
+}
+
+#[test]
+fn unreferenced_entries_are_skipped() {
+    let pjson = project_json(ASSET);
+    let src = archive(&[
+        ("project.json", &pjson),
+        (&md5ext(ASSET), ASSET),
+        ("payload.exe", &[0; 1024]),
+    ]);
+    let limits = Limits {
+        max_total_size: (pjson.len() + ASSET.len()) as u64,
+        ..Limits::default()
+    };
+    let file = load(&src, limits).unwrap();
+    assert_eq!(file.resources.len(), 1);
+    assert!(!file.resources.contains_key("payload.exe"));
+}
+
+#[test]
+fn entry_count() {
+    let pjson = project_json(ASSET);
+    let src = archive(&[("project.json", &pjson), ("a", b"a"), ("b", b"b")]);
+    let limits = Limits {
+        max_entries: 2,
+        ..Limits::default()
+    };
+    assert_eq!(
+        exceeded(load(&src, limits).unwrap_err()),
+        (None, Limit::EntryCount(2))
+    );
+}
+
+#[test]
+fn entry_size() {
+    let asset = vec![b' '; 4096];
+    let pjson = project_json(&asset);
+    let src = archive(&[("project.json", &pjson), (&md5ext(&asset), &asset)]);
+    let limits = Limits {
+        max_entry_size: 1024,
+        ..Limits::default()
+    };
+    assert_eq!(
+        exceeded(load(&src, limits).unwrap_err()),
+        (Some(md5ext(&asset)), Limit::EntrySize(1024))
+    );
+}
+
+#[test]
+fn total_size() {
+    let asset = vec![b' '; 4096];
+    let pjson = project_json(&asset);
+    let src = archive(&[("project.json", &pjson), (&md5ext(&asset), &asset)]);
+    let max = pjson.len() as u64 + 100;
+    let limits = Limits {
+        max_total_size: max,
+        ..Limits::default()
+    };
+    assert_eq!(
+        exceeded(load(&src, limits).unwrap_err()),
+        (Some(md5ext(&asset)), Limit::TotalSize(max))
+    );
+}
+
+#[test]
+fn compression_ratio() {
+    // 16 MiB of zeros deflate to a few kilobytes
+    let bomb = vec![0; 16 * 1024 * 1024];
+    let pjson = project_json(&bomb);
+    let src = archive(&[("project.json", &pjson), (&md5ext(&bomb), &bomb)]);
+    assert_eq!(
+        exceeded(load(&src, Limits::default()).unwrap_err()),
+        (Some(md5ext(&bomb)), Limit::CompressionRatio(100))
+    );
+    assert!(load(&src, Limits::unlimited()).is_ok());
+}
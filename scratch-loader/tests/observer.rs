@@ -0,0 +1,98 @@
+use std::{
+    cell::RefCell,
+    io::{Cursor, Write},
+    rc::Rc,
+    time::Duration,
+};
+
+use scratch_loader::{
+    load_sb3::load_bytes_with,
+    observer::{Observer, Phase, Progress, Warning},
+    options::LoadOptions,
+};
+use serde_json::json;
+use zip::{write::FileOptions, ZipWriter};
+
+#[derive(Default)]
+struct Recorder {
+    events: RefCell<Vec<String>>,
+}
+
+impl Observer for Recorder {
+    fn progress(&self, event: Progress) {
+        self.events.borrow_mut().push(format!("{:?}", event));
+    }
+
+    fn warning(&self, warning: &Warning) {
+        self.events.borrow_mut().push(warning.to_string());
+    }
+
+    fn timing(&self, phase: Phase, _elapsed: Duration) {
+        self.events.borrow_mut().push(format!("{}", phase));
+    }
+}
+
+#[test]
+fn reports_through_the_observer() {
+    const SVG: &[u8] = b"<svg/>";
+    let asset_id = format!("{:x}", md5::compute(SVG));
+    let md5ext = format!("{}.svg", asset_id);
+    let project = json!({
+        "targets": [{
+            "isStage": true,
+            "name": "Stage",
+            "costumes": [{
+                "name": "backdrop1",
+                "dataFormat": "svg",
+                "assetId": asset_id,
+                "md5ext": md5ext,
+                "rotationCenterX": 0,
+                "rotationCenterY": 0
+            }],
+            "currentCostume": 0,
+            "sounds": [],
+            "volume": 100,
+            "layerOrder": 0
+        }],
+        "meta": { "semver": "3.0.0" }
+    });
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file("project.json", FileOptions::default())
+        .unwrap();
+    zip.write_all(project.to_string().as_bytes()).unwrap();
+    zip.start_file(md5ext.as_str(), FileOptions::default())
+        .unwrap();
+    zip.write_all(SVG).unwrap();
+    zip.start_file("notes.txt", FileOptions::default()).unwrap();
+    zip.write_all(b"unused").unwrap();
+    let src = zip.finish().unwrap().into_inner();
+
+    let recorder = Rc::new(Recorder::default());
+    let options = LoadOptions {
+        observer: recorder.clone(),
+        ..Default::default()
+    };
+    load_bytes_with(&src, &options).unwrap();
+    assert_eq!(
+        *recorder.events.borrow(),
+        [
+            "Extracting(\"project.json\")".to_string(),
+            format!("Extracting({:?})", md5ext),
+            "Skipping(\"notes.txt\")".to_string(),
+            "load".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn warning_display() {
+    let warning = Warning {
+        target: Some("Sprite1".to_string()),
+        block: Some("abc".to_string()),
+        message: "unsupported block opcode `foo_bar`".to_string(),
+    };
+    assert_eq!(
+        warning.to_string(),
+        "unsupported block opcode `foo_bar` (block `abc` of `Sprite1`)"
+    );
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use scratch_loader::{
+    load_sb3, save_sb3,
+    sb3::{Project, Sb3File},
+};
+use serde_json::{json, Value};
+
+const SVG: &[u8] = br#"<svg xmlns="http://www.w3.org/2000/svg" width="2" height="2"/>"#;
+
+fn project() -> Value {
+    let asset_id = format!("{:x}", md5::compute(SVG));
+    let costume = json!({
+        "name": "costume1",
+        "bitmapResolution": 1,
+        "dataFormat": "svg",
+        "assetId": asset_id,
+        "md5ext": format!("{}.svg", asset_id),
+        "rotationCenterX": 1,
+        "rotationCenterY": 1
+    });
+    json!({
+        "targets": [
+            {
+                "isStage": true,
+                "name": "Stage",
+                "variables": { "v": ["my variable", 0], "c": ["☁ score", "12", true] },
+                "lists": { "l": ["list", ["a", 1, true]] },
+                "broadcasts": { "b": "message1" },
+                "blocks": {
+                    "p": [12, "my variable", "v", 384, 152]
+                },
+                "comments": {},
+                "currentCostume": 0,
+                "costumes": [costume],
+                "sounds": [],
+                "volume": 100,
+                "layerOrder": 0,
+                "tempo": 60,
+                "videoTransparency": 50,
+                "videoState": "on",
+                "textToSpeechLanguage": null
+            },
+            {
+                "isStage": false,
+                "name": "Sprite1",
+                "variables": {},
+                "lists": {},
+                "broadcasts": {},
+                "blocks": {
+                    "d": {
+                        "opcode": "procedures_definition",
+                        "next": "s",
+                        "parent": null,
+                        "inputs": { "custom_block": [1, "r"] },
+                        "fields": {},
+                        "shadow": false,
+                        "topLevel": true,
+                        "x": 10,
+                        "y": 20,
+                        "comment": "k"
+                    },
+                    "r": {
+                        "opcode": "procedures_prototype",
+                        "next": null,
+                        "parent": "d",
+                        "inputs": {},
+                        "fields": {},
+                        "shadow": true,
+                        "topLevel": false,
+                        "mutation": {
+                            "tagName": "mutation",
+                            "children": [],
+                            "proccode": "jump",
+                            "argumentids": "[]",
+                            "argumentnames": "[]",
+                            "argumentdefaults": "[]",
+                            "warp": "false"
+                        }
+                    },
+                    "s": {
+                        "opcode": "control_stop",
+                        "next": null,
+                        "parent": "d",
+                        "inputs": {},
+                        "fields": { "STOP_OPTION": ["other scripts in sprite", null] },
+                        "shadow": false,
+                        "topLevel": false,
+                        "mutation": { "tagName": "mutation", "children": [], "hasnext": "true" }
+                    },
+                    "c": {
+                        "opcode": "procedures_call",
+                        "next": null,
+                        "parent": null,
+                        "inputs": {},
+                        "fields": {},
+                        "shadow": false,
+                        "topLevel": true,
+                        "x": 0,
+                        "y": 100,
+                        "mutation": {
+                            "tagName": "mutation",
+                            "children": [],
+                            "proccode": "jump",
+                            "argumentids": "[]",
+                            "warp": "false"
+                        }
+                    },
+                    "m": {
+                        "opcode": "motion_movesteps",
+                        "next": null,
+                        "parent": null,
+                        "inputs": { "STEPS": [3, [12, "my variable", "v"], [4, "10"]] },
+                        "fields": {},
+                        "shadow": false,
+                        "topLevel": true,
+                        "x": 0,
+                        "y": 200
+                    }
+                },
+                "comments": {
+                    "k": {
+                        "blockId": "d",
+                        "x": 200, "y": 20, "width": 200, "height": 200,
+                        "minimized": false,
+                        "text": "hello"
+                    }
+                },
+                "currentCostume": 0,
+                "costumes": [costume],
+                "sounds": [],
+                "volume": 100,
+                "layerOrder": 1,
+                "visible": true,
+                "x": 0,
+                "y": 0,
+                "size": 100,
+                "direction": 90,
+                "draggable": false,
+                "rotationStyle": "all around"
+            }
+        ],
+        "monitors": [],
+        "extensions": [],
+        "meta": { "semver": "3.0.0", "vm": "2.3.0", "agent": "" }
+    })
+}
+
+fn round_trip(project: Value) -> (Value, Value) {
+    let project: Project = serde_json::from_value(project).unwrap();
+    let asset_id = format!("{:x}", md5::compute(SVG));
+    let resources = HashMap::from([(format!("{}.svg", asset_id), SVG.to_vec())]);
+    let file = Sb3File::new(resources, project);
+    let saved = save_sb3::save_bytes(&file).unwrap();
+    let loaded = load_sb3::load_bytes(&saved).unwrap();
+    assert_eq!(loaded.resources, file.resources);
+    (
+        serde_json::to_value(&file.project).unwrap(),
+        serde_json::to_value(&loaded.project).unwrap(),
+    )
+}
+
+#[test]
+fn save_then_load() {
+    let (before, after) = round_trip(project());
+    assert_eq!(before, after);
+}
+
+#[test]
+fn saved_project_json() {
+    let (_, saved) = round_trip(project());
+    let stage = &saved["targets"][0];
+    // sprite only state stays off the stage
+    for key in [
+        "x",
+        "y",
+        "size",
+        "direction",
+        "draggable",
+        "rotationStyle",
+        "visible",
+    ] {
+        assert!(stage.get(key).is_none(), "stage has `{}`", key);
+    }
+    assert_eq!(stage["tempo"], 60.0);
+    assert_eq!(stage["variables"]["v"], json!(["my variable", 0.0]));
+    assert_eq!(stage["variables"]["c"], json!(["☁ score", "12", true]));
+    assert_eq!(stage["blocks"]["p"]["opcode"], "data_variable");
+    assert_eq!(stage["blocks"]["p"]["x"], 384.0);
+    assert_eq!(stage["costumes"][0]["bitmapResolution"], 1);
+
+    let sprite = &saved["targets"][1];
+    assert_eq!(sprite["rotationStyle"], "all around");
+    assert_eq!(sprite["comments"]["k"]["text"], "hello");
+    let blocks = &sprite["blocks"];
+    assert_eq!(blocks["d"]["comment"], "k");
+    assert_eq!(blocks["r"]["shadow"], true);
+    assert_eq!(blocks["r"]["mutation"]["tagName"], "mutation");
+    assert_eq!(blocks["r"]["mutation"]["argumentids"], "[]");
+    assert_eq!(blocks["r"]["mutation"]["warp"], "false");
+    assert_eq!(blocks["s"]["mutation"]["hasnext"], "true");
+    assert_eq!(blocks["r"]["mutation"]["argumentnames"], "[]");
+    assert_eq!(blocks["r"]["mutation"]["argumentdefaults"], "[]");
+    // scratch-vm keeps argument names and defaults off procedure calls
+    assert_eq!(blocks["c"]["mutation"]["proccode"], "jump");
+    assert!(blocks["c"]["mutation"].get("argumentnames").is_none());
+    assert!(blocks["c"]["mutation"].get("argumentdefaults").is_none());
+    assert_eq!(
+        blocks["m"]["inputs"]["STEPS"],
+        json!([3, [12, "my variable", "v"], [4, "10"]])
+    );
+    assert_eq!(saved["monitors"], json!([]));
+}
@@ -0,0 +1,175 @@
+use std::io::{Cursor, Write};
+
+use scratch_loader::{
+    load_sb2::load_bytes,
+    sb3::{Block, Input, InputType, RotationStyle, Sb3File, ScratchValue, Target},
+};
+use serde_json::json;
+use zip::{write::FileOptions, ZipWriter};
+
+const BACKDROP: &[u8] = br#"<svg xmlns="http://www.w3.org/2000/svg" width="2" height="2"/>"#;
+const PEN_LAYER: &[u8] = b"\x89PNG\r\n\x1a\n pen layer";
+const COSTUME: &[u8] = br#"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="4"/>"#;
+
+// `tests/fixtures/project.sb2.json` with its assets, stored by layer id like scratch 2 does
+fn load_fixture() -> Sb3File {
+    let pjson = std::fs::read(format!(
+        "{}/tests/fixtures/project.sb2.json",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap();
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (fname, data) in [
+        ("project.json", pjson.as_slice()),
+        ("0.svg", BACKDROP),
+        ("1.png", PEN_LAYER),
+        ("2.svg", COSTUME),
+    ] {
+        zip.start_file(fname, FileOptions::default()).unwrap();
+        zip.write_all(data).unwrap();
+    }
+    load_bytes(&zip.finish().unwrap().into_inner()).unwrap()
+}
+
+fn md5ext(data: &[u8], ext: &str) -> String {
+    format!("{:x}.{}", md5::compute(data), ext)
+}
+
+fn top_level<'a>(target: &'a Target, opcode: &str) -> &'a Block {
+    target
+        .blocks
+        .blocks
+        .values()
+        .find(|b| b.top_level && b.opcode == opcode)
+        .unwrap()
+}
+
+fn next<'a>(target: &'a Target, block: &Block) -> &'a Block {
+    &target.blocks.blocks[block.next.as_deref().unwrap()]
+}
+
+fn input<'a>(target: &'a Target, block: &Block, name: &str) -> &'a Block {
+    match &block.inputs[name] {
+        Input::NoHidden(_, InputType::Block(id)) | Input::Hidden(_, InputType::Block(id), _) => {
+            &target.blocks.blocks[id]
+        }
+        input => panic!("unexpected {:?}", input),
+    }
+}
+
+#[test]
+fn targets() {
+    let project = load_fixture().project;
+    // the variable watcher among the children is left out
+    assert_eq!(project.targets.len(), 2);
+    let stage = &project.targets[0];
+    assert!(stage.is_stage);
+    assert_eq!(stage.name, "Stage");
+    assert_eq!(stage.layer_order, 0);
+    assert!(stage.position.x.is_none() && stage.size.is_none());
+    assert_eq!(stage.extra["tempo"], json!(90.0));
+
+    let sprite = &project.targets[1];
+    assert!(!sprite.is_stage);
+    assert_eq!(sprite.name, "Sprite1");
+    assert_eq!(sprite.layer_order, 1);
+    assert_eq!(
+        (sprite.position.x, sprite.position.y),
+        (Some(12.0), Some(-5.0))
+    );
+    assert_eq!(sprite.size, Some(50.0));
+    assert_eq!(sprite.direction, Some(45.0));
+    assert_eq!(sprite.rotation_style, Some(RotationStyle::LeftRight));
+    assert_eq!(sprite.draggable, Some(true));
+    assert_eq!(sprite.visible, Some(false));
+    assert_eq!(project.extensions, ["pen"]);
+}
+
+#[test]
+fn variables_and_lists() {
+    let project = load_fixture().project;
+    let (stage, sprite) = (&project.targets[0], &project.targets[1]);
+    let score = stage.variables.values().find(|v| v.0 == "score").unwrap();
+    assert!(matches!(score.1, ScratchValue::Num(n) if n == 0.0));
+    let items = stage.lists.values().find(|l| l.0 == "items").unwrap();
+    assert!(matches!(
+        items.1.as_slice(),
+        [ScratchValue::Str(a), ScratchValue::Num(n)] if a == "a" && *n == 1.0
+    ));
+    // locals stay on the sprite
+    assert_eq!(sprite.variables.len(), 1);
+    assert!(matches!(
+        &sprite.variables.values().next().unwrap().1,
+        ScratchValue::Str(speed) if speed == "10"
+    ));
+    assert_eq!(sprite.lists.values().next().unwrap().0, "trail");
+    assert!(stage.broadcasts.values().any(|b| b == "go"));
+}
+
+#[test]
+fn scripts() {
+    let project = load_fixture().project;
+    let (stage, sprite) = (&project.targets[0], &project.targets[1]);
+
+    let flag = top_level(sprite, "event_whenflagclicked");
+    assert_eq!((flag.x, flag.y), (Some(30.0), Some(40.0)));
+    let clear = next(sprite, flag);
+    assert_eq!(clear.opcode, "pen_clear");
+    let repeat = next(sprite, clear);
+    assert_eq!(repeat.opcode, "control_repeat");
+    assert!(matches!(
+        &repeat.inputs["TIMES"],
+        Input::NoHidden(1, InputType::NumOrStr(6, ScratchValue::Num(n))) if *n == 3.0
+    ));
+    // the nested script array becomes the substack
+    let forward = input(sprite, repeat, "SUBSTACK");
+    assert_eq!(forward.opcode, "motion_movesteps");
+    assert_eq!(forward.parent, clear.next);
+    let (speed, _) = sprite
+        .variables
+        .iter()
+        .find(|(_, v)| v.0 == "speed")
+        .unwrap();
+    assert!(matches!(
+        &forward.inputs["STEPS"],
+        Input::Hidden(3, InputType::BroadcastOrVarOrList(12, name, id), _)
+            if name == "speed" && id == speed
+    ));
+    let append = next(sprite, forward);
+    assert_eq!(append.opcode, "data_addtolist");
+    assert_eq!(append.fields["LIST"].0, "trail");
+    assert!(sprite
+        .lists
+        .contains_key(append.fields["LIST"].1.as_deref().unwrap()));
+    assert!(append.next.is_none());
+    let broadcast = next(sprite, repeat);
+    assert_eq!(broadcast.opcode, "event_broadcast");
+
+    // the sprite's broadcast and the stage's hat share the id on the stage
+    let receive = top_level(stage, "event_whenbroadcastreceived");
+    let go = receive.fields["BROADCAST_OPTION"].1.as_deref().unwrap();
+    assert_eq!(stage.broadcasts[go], "go");
+    let set = next(stage, receive);
+    assert_eq!(set.opcode, "data_setvariableto");
+    let score = set.fields["VARIABLE"].1.as_deref().unwrap();
+    assert_eq!(stage.variables[score].0, "score");
+    assert_eq!(input(stage, set, "VALUE").opcode, "operator_add");
+}
+
+#[test]
+fn assets_and_pen_layer() {
+    let file = load_fixture();
+    let stage = &file.project.targets[0];
+    let backdrop = &stage.costumes[0];
+    assert_eq!(backdrop.md5ext, md5ext(BACKDROP, "svg"));
+    assert_eq!((backdrop.center_x, backdrop.center_y), (240.0, 180.0));
+    assert_eq!(file.resources[&backdrop.md5ext], BACKDROP);
+    let costume = &file.project.targets[1].costumes[0];
+    assert_eq!(file.resources[&costume.md5ext], COSTUME);
+
+    // sb3 has no pen layer, it is kept on the stage
+    let pen_layer = md5ext(PEN_LAYER, "png");
+    assert_eq!(stage.extra["penLayerMD5"], json!(pen_layer));
+    assert_eq!(stage.extra["penLayerID"], json!(1));
+    assert_eq!(file.resources[&pen_layer], PEN_LAYER);
+}
@@ -0,0 +1,250 @@
+use scratch_loader::sb3::{Blocks, Field, Input, InputType, ScratchValue, Target, Variable};
+
+fn input(json: &str) -> Input {
+    serde_json::from_str(json).unwrap()
+}
+
+fn blocks(json: &str) -> Blocks {
+    serde_json::from_str(&format!(r#"{{ "blocks": {} }}"#, json)).unwrap()
+}
+
+#[test]
+fn input_shadow_primitive() {
+    assert!(matches!(
+        input(r#"[1, [4, "10"]]"#),
+        Input::NoHidden(1, InputType::NumOrStr(4, ScratchValue::Str(s))) if s == "10"
+    ));
+    assert!(matches!(
+        input(r#"[1, [10, 5]]"#),
+        Input::NoHidden(1, InputType::NumOrStr(10, ScratchValue::Num(n))) if n == 5.0
+    ));
+    assert!(matches!(
+        input(r##"[1, [9, "#ff0000"]]"##),
+        Input::NoHidden(1, InputType::NumOrStr(9, ScratchValue::Str(s))) if s == "#ff0000"
+    ));
+}
+
+#[test]
+fn input_empty_slot() {
+    assert!(matches!(
+        input(r#"[1, null]"#),
+        Input::NoHidden(1, InputType::Empty)
+    ));
+    assert!(matches!(
+        input(r#"[2, null]"#),
+        Input::NoHidden(2, InputType::Empty)
+    ));
+}
+
+#[test]
+fn input_block() {
+    assert!(matches!(
+        input(r#"[2, "Wl|f8?7oxTz?^y#5tsv="]"#),
+        Input::NoHidden(2, InputType::Block(id)) if id == "Wl|f8?7oxTz?^y#5tsv="
+    ));
+    // menus are shadow blocks
+    assert!(matches!(
+        input(r#"[1, "_Qcl#zM]9Kr:cqUbzGxY"]"#),
+        Input::NoHidden(1, InputType::Block(_))
+    ));
+}
+
+#[test]
+fn input_obscured_shadow() {
+    assert!(matches!(
+        input(r#"[3, "a", [4, "10"]]"#),
+        Input::Hidden(3, InputType::Block(_), InputType::NumOrStr(4, _))
+    ));
+    assert!(matches!(
+        input(r#"[3, "a", "b"]"#),
+        Input::Hidden(3, InputType::Block(_), InputType::Block(_))
+    ));
+    assert!(matches!(
+        input(r#"[3, "a", null]"#),
+        Input::Hidden(3, InputType::Block(_), InputType::Empty)
+    ));
+}
+
+#[test]
+fn input_variable_list_broadcast() {
+    assert!(matches!(
+        input(r#"[3, [12, "my variable", "`jEk@4|i[#Fk?(8x)AV.-my variable"], [10, ""]]"#),
+        Input::Hidden(3, InputType::BroadcastOrVarOrList(12, name, _), InputType::NumOrStr(10, _))
+            if name == "my variable"
+    ));
+    assert!(matches!(
+        input(r#"[3, [13, "list", "kXWYOwYe?Cq.ZSSxO;p;"], [10, ""]]"#),
+        Input::Hidden(3, InputType::BroadcastOrVarOrList(13, _, _), _)
+    ));
+    assert!(matches!(
+        input(r#"[1, [11, "message1", "cZSUj0h!0iaU.5p4Tdn!"]]"#),
+        Input::NoHidden(1, InputType::BroadcastOrVarOrList(11, name, _)) if name == "message1"
+    ));
+}
+
+#[test]
+fn field_values() {
+    let field: Field = serde_json::from_str(r#"["_mouse_", null]"#).unwrap();
+    assert_eq!(field.0, "_mouse_");
+    assert_eq!(field.1, None);
+    let field: Field = serde_json::from_str(r#"["my variable", "abc"]"#).unwrap();
+    assert_eq!(field.1.as_deref(), Some("abc"));
+    // numeric menu value, e.g. `note`
+    let field: Field = serde_json::from_str(r#"[60, null]"#).unwrap();
+    assert_eq!(field.0, "60");
+    let field: Field = serde_json::from_str(r#"["front"]"#).unwrap();
+    assert_eq!(field.0, "front");
+}
+
+#[test]
+fn variables() {
+    let var: Variable = serde_json::from_str(r#"["my variable", 0]"#).unwrap();
+    assert!(!var.2);
+    let var: Variable = serde_json::from_str(r#"["☁ score", "12", true]"#).unwrap();
+    assert_eq!(var.0, "☁ score");
+    assert!(var.2);
+}
+
+#[test]
+fn top_level_primitive() {
+    let blocks = blocks(
+        r#"{
+            "v": [12, "my variable", "`jEk@4|i[#Fk?(8x)AV.-my variable", 384, 152],
+            "l": [13, "list", "kXWYOwYe?Cq.ZSSxO;p;", 391, 229]
+        }"#,
+    );
+    let var = &blocks.blocks["v"];
+    assert_eq!(var.opcode, "data_variable");
+    assert!(var.top_level);
+    assert_eq!(var.fields["VARIABLE"].0, "my variable");
+    assert_eq!(
+        var.fields["VARIABLE"].1.as_deref(),
+        Some("`jEk@4|i[#Fk?(8x)AV.-my variable")
+    );
+    assert_eq!(blocks.blocks["l"].opcode, "data_listcontents");
+}
+
+#[test]
+fn regular_block() {
+    let blocks = blocks(
+        r#"{
+            "d": {
+                "opcode": "motion_movesteps",
+                "next": null,
+                "parent": "c",
+                "inputs": { "STEPS": [1, [4, "10"]] },
+                "fields": {},
+                "shadow": false,
+                "topLevel": false
+            },
+            "m": {
+                "opcode": "motion_goto_menu",
+                "next": null,
+                "parent": "g",
+                "inputs": {},
+                "fields": { "TO": ["_random_", null] },
+                "shadow": true,
+                "topLevel": false
+            }
+        }"#,
+    );
+    assert_eq!(blocks.blocks["d"].parent.as_deref(), Some("c"));
+    assert!(blocks.blocks["d"].inputs.contains_key("STEPS"));
+    assert_eq!(blocks.blocks["m"].fields["TO"].0, "_random_");
+}
+
+#[test]
+fn stage_target() {
+    let target: Target = serde_json::from_str(
+        r#"{
+            "isStage": true,
+            "name": "Stage",
+            "variables": {
+                "`jEk@4|i[#Fk?(8x)AV.-my variable": ["my variable", 0],
+                "cloud": ["☁ high score", 100, true]
+            },
+            "lists": { "kXWYOwYe?Cq.ZSSxO;p;": ["list", ["a", 1, true]] },
+            "broadcasts": { "cZSUj0h!0iaU.5p4Tdn!": "message1" },
+            "blocks": {
+                "v": [12, "my variable", "`jEk@4|i[#Fk?(8x)AV.-my variable", 10, 10]
+            },
+            "comments": {},
+            "currentCostume": 0,
+            "costumes": [
+                {
+                    "name": "backdrop1",
+                    "dataFormat": "svg",
+                    "assetId": "cd21514d0531fdffb22204e0ec5ed84a",
+                    "md5ext": "cd21514d0531fdffb22204e0ec5ed84a.svg",
+                    "rotationCenterX": 240,
+                    "rotationCenterY": 180
+                }
+            ],
+            "sounds": [
+                {
+                    "name": "pop",
+                    "assetId": "83a9787d4cb6f3b7632b4ddfebf74367",
+                    "dataFormat": "wav",
+                    "format": "",
+                    "rate": 48000,
+                    "sampleCount": 1123,
+                    "md5ext": "83a9787d4cb6f3b7632b4ddfebf74367.wav"
+                }
+            ],
+            "volume": 100,
+            "layerOrder": 0,
+            "tempo": 60,
+            "videoTransparency": 50,
+            "videoState": "on",
+            "textToSpeechLanguage": null
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(target.variables.len(), 2);
+    assert_eq!(target.lists["kXWYOwYe?Cq.ZSSxO;p;"].1.len(), 3);
+    assert_eq!(target.blocks.blocks["v"].opcode, "data_variable");
+    assert_eq!(target.sounds[0].rate, 48000.0);
+}
+
+#[test]
+fn procedure_mutation() {
+    let blocks = blocks(
+        r#"{
+            "p": {
+                "opcode": "procedures_prototype",
+                "next": null,
+                "parent": "d",
+                "inputs": { "a": [1, "r"] },
+                "fields": {},
+                "shadow": true,
+                "topLevel": false,
+                "mutation": {
+                    "tagName": "mutation",
+                    "children": [],
+                    "proccode": "jump %s %b",
+                    "argumentids": "[\"a\",\"b\"]",
+                    "argumentnames": "[\"height\",\"fast\"]",
+                    "argumentdefaults": "[\"\",\"false\"]",
+                    "warp": "true"
+                }
+            },
+            "s": {
+                "opcode": "control_stop",
+                "next": null,
+                "parent": null,
+                "inputs": {},
+                "fields": { "STOP_OPTION": ["other scripts in sprite", null] },
+                "shadow": false,
+                "topLevel": true,
+                "mutation": { "tagName": "mutation", "children": [], "hasnext": "true" }
+            }
+        }"#,
+    );
+    let mutation = blocks.blocks["p"].mutation.as_ref().unwrap();
+    assert_eq!(mutation.proccode.as_deref(), Some("jump %s %b"));
+    assert_eq!(mutation.argument_ids, ["a", "b"]);
+    assert_eq!(mutation.argument_names, ["height", "fast"]);
+    assert_eq!(mutation.argument_defaults.len(), 2);
+    assert!(mutation.warp);
+    assert!(blocks.blocks["s"].mutation.as_ref().unwrap().has_next);
+}
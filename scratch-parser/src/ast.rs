@@ -1,10 +1,10 @@
 use std::{collections::HashMap, rc::Rc};
 
-use scratch_loader::sb3::ScratchValue;
+use scratch_loader::sb3::{ImageFormat, Num, RotationStyle, ScratchValue, SoundFormat};
 
 #[derive(Debug)]
 pub struct ParsedScratchProject {
-    pub resources: HashMap<ResourcePath, String>,
+    pub resources: HashMap<ResourcePath, Vec<u8>>,
     pub sprites: Vec<Sprite>,
     pub background: Background,
     pub extensions: Vec<String>,
@@ -57,14 +57,25 @@ impl ResourcePath {
 #[derive(Debug)]
 pub struct Sprite {
     pub name: String,
+    pub position: (Num, Num),
+    pub direction: Num,
+    pub rotation_style: RotationStyle,
+    pub size: Num,
+    pub visible: bool,
+    pub draggable: bool,
+    // index into `costumes`
+    pub current_costume: usize,
+    pub volume: Num,
     pub variables: HashMap<String, Variable>,
     pub lists: HashMap<String, List>,
     pub blocks: Vec<BlockItem>,
-    pub definions: HashMap<String, ResourcePath>,
+    pub definions: HashMap<String, Definion>,
+    pub costumes: Vec<Costume>,
+    pub sounds: Vec<Sound>,
 }
 
 pub type Variable = (ResourcePath, ScratchValue);
-pub type List = (ResourcePath, Vec<(ScratchValue, ScratchValue)>);
+pub type List = (ResourcePath, Vec<ScratchValue>);
 
 #[derive(Debug)]
 pub struct Background {
@@ -74,47 +85,694 @@ pub struct Background {
     pub broadcasts: HashMap<String, ResourcePath>,
     pub blocks: Vec<BlockItem>,
     // actually, sratch identify a definion by its display name?!
-    pub definions: HashMap<String, ResourcePath>,
+    pub definions: HashMap<String, Definion>,
+    pub costumes: Vec<Costume>,
+    pub current_costume: usize,
+    pub sounds: Vec<Sound>,
+    pub volume: Num,
+    // beats per minute of the music extension
+    pub tempo: Num,
+}
+
+#[derive(Debug)]
+pub struct Costume {
+    pub name: String,
+    // key of the asset in `ParsedScratchProject::resources`
+    pub asset: ResourcePath,
+    pub format: ImageFormat,
+    pub center: (Num, Num),
+}
+
+#[derive(Debug)]
+pub struct Sound {
+    pub name: String,
+    pub asset: ResourcePath,
+    pub format: SoundFormat,
+    pub rate: Num,
+    pub sample_count: Num,
 }
 
 #[derive(Debug)]
 pub enum BlockItem {
     EvWhenGreenFlagClicked(BlockStack),
-    EvWhenKeyPressed(KeyId /* I'm not sure for this */, BlockStack),
+    EvWhenKeyPressed(KeyId, BlockStack),
+    EvWhenThisSpriteClicked(BlockStack),
+    EvWhenStageClicked(BlockStack),
+    // by backdrop name
+    EvWhenBackdropSwitchesTo(String, BlockStack),
+    // fires once the value rises above the threshold
+    EvWhenGreaterThan(GreaterThanMenu, Expr, BlockStack),
     EWhenRecieveBroadcast(ResourcePath, BlockStack),
-    /* Def(ResourcePath, DefArgs, BlockStack), */
+    ControlStartAsClone(BlockStack),
+    Def(Definion, BlockStack),
     // ...
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GreaterThanMenu {
+    Loudness,
+    Timer,
+}
+
+// a custom block, keyed by its proccode, e.g. `jump %s %b`
+#[derive(Debug, Clone)]
+pub struct Definion {
+    pub path: ResourcePath,
+    pub args: DefArgs,
+    // run without screen refresh
+    pub warp: bool,
+}
+
+pub type DefArgs = Vec<DefArg>;
+
+#[derive(Debug, Clone)]
+pub struct DefArg {
+    // named after the argument, `ProcArg` refers to it
+    pub path: ResourcePath,
+    pub kind: DefArgKind,
+    pub default: Option<ScratchValue>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefArgKind {
+    StrNum,
+    Bool,
+}
+
 #[derive(Debug)]
 pub struct BlockStack {
-    blocks: Vec<Block>,
+    blocks: Vec<Stmt>,
 }
 
 impl BlockStack {
-    pub fn new(blocks: Vec<Block>) -> Self {
+    pub fn new(blocks: Vec<Stmt>) -> Self {
         Self { blocks }
     }
+
+    pub fn blocks(&self) -> &[Stmt] {
+        &self.blocks
+    }
 }
 
-#[derive(Debug)]
+// a key as scratch-vm names it, see `KeyId::from_scratch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum KeyId {
-    A,
-    B,
-    C,
-    D, // ...
+    // only valid for hats and `key pressed?`, matches whatever key is down
+    Any,
+    Space,
+    Left,
+    Up,
+    Right,
+    Down,
+    Enter,
+    // turbowarp extras
+    Backspace,
+    Delete,
+    Shift,
+    CapsLock,
+    ScrollLock,
+    Control,
+    Escape,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    // always uppercase
+    Letter(char),
+    Digit(u8),
+    // any other printable key, e.g. `-` or `,`
+    Symbol(char),
+}
+
+const KEY_NAMES: &[(&str, KeyId)] = &[
+    ("any", KeyId::Any),
+    ("space", KeyId::Space),
+    ("left arrow", KeyId::Left),
+    ("up arrow", KeyId::Up),
+    ("right arrow", KeyId::Right),
+    ("down arrow", KeyId::Down),
+    ("enter", KeyId::Enter),
+    ("backspace", KeyId::Backspace),
+    ("delete", KeyId::Delete),
+    ("shift", KeyId::Shift),
+    ("caps lock", KeyId::CapsLock),
+    ("scroll lock", KeyId::ScrollLock),
+    ("control", KeyId::Control),
+    ("escape", KeyId::Escape),
+    ("insert", KeyId::Insert),
+    ("home", KeyId::Home),
+    ("end", KeyId::End),
+    ("page up", KeyId::PageUp),
+    ("page down", KeyId::PageDown),
+];
+
+impl KeyId {
+    // same as scratch-vm's `_keyArgToScratchKey`: named keys are kept, anything
+    // else is reduced to its first character, uppercased
+    pub fn from_scratch(name: &str) -> Option<Self> {
+        if let Some((_, key)) = KEY_NAMES.iter().find(|(n, _)| *n == name) {
+            return Some(*key);
+        }
+        let c = name.chars().next()?;
+        Some(match c {
+            ' ' => KeyId::Space,
+            'a'..='z' | 'A'..='Z' => KeyId::Letter(c.to_ascii_uppercase()),
+            '0'..='9' => KeyId::Digit(c as u8 - b'0'),
+            _ => KeyId::Symbol(c.to_uppercase().next().unwrap_or(c)),
+        })
+    }
+
+    // the name scratch-vm uses at runtime, e.g. `left arrow` or `A`
+    pub fn name(&self) -> String {
+        match self {
+            KeyId::Letter(c) | KeyId::Symbol(c) => c.to_string(),
+            KeyId::Digit(d) => d.to_string(),
+            key => {
+                let (name, _) = KEY_NAMES.iter().find(|(_, k)| k == key).unwrap();
+                name.to_string()
+            }
+        }
+    }
+}
+
+// an entry of the menus that pick a sprite, e.g. the one of `go to`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetMenu {
+    // only in the menu of `create clone of`
+    Myself,
+    Mouse,
+    Random,
+    Edge,
+    Stage,
+    // by name, sprites may be parsed after the blocks refering to them
+    Sprite(String),
+}
+
+impl TargetMenu {
+    pub fn from_scratch(name: &str) -> Self {
+        match name {
+            "_myself_" => Self::Myself,
+            "_mouse_" => Self::Mouse,
+            "_random_" => Self::Random,
+            "_edge_" => Self::Edge,
+            "_stage_" => Self::Stage,
+            name => Self::Sprite(name.to_string()),
+        }
+    }
+
+    // the menu value scratch-vm expects at runtime
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Myself => "_myself_",
+            Self::Mouse => "_mouse_",
+            Self::Random => "_random_",
+            Self::Edge => "_edge_",
+            Self::Stage => "_stage_",
+            Self::Sprite(name) => name,
+        }
+    }
+}
+
+// an entry of the costume or backdrop menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostumeMenu {
+    // index into the costumes of the target, the stage's for backdrops
+    Costume(usize),
+    Next,
+    Previous,
+    Random,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberName {
+    Number,
+    Name,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicEffect {
+    Color,
+    Fisheye,
+    Whirl,
+    Pixelate,
+    Mosaic,
+    Brightness,
+    Ghost,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundEffect {
+    Pitch,
+    Pan,
+}
+
+// the functions of `operator_mathop`, trigonometry works in degrees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathOp {
+    Abs,
+    Floor,
+    Ceiling,
+    Sqrt,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Ln,
+    Log,
+    PowE,
+    Pow10,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurrentMenu {
+    Year,
+    Month,
+    Date,
+    DayOfWeek,
+    Hour,
+    Minute,
+    Second,
+}
+
+// what `sensing_of` reads, the backdrop ones only make sense for the stage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SensingOfProperty {
+    XPosition,
+    YPosition,
+    Direction,
+    CostumeNumber,
+    CostumeName,
+    Size,
+    Volume,
+    BackdropNumber,
+    BackdropName,
+    // a variable of the sprite, or a global one for the stage
+    Variable(ResourcePath),
+    // a variable the parser could not resolve, e.g. of a computed object, looked up by name
+    VariableName(String),
+}
+
+// the words list blocks accept in place of an index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListIndex {
+    Last,
+    // also written `any`
+    Random,
+    // only for `delete`
+    All,
+}
+
+impl ListIndex {
+    pub fn from_scratch(index: &str) -> Option<Self> {
+        match index {
+            "last" => Some(Self::Last),
+            "random" | "any" => Some(Self::Random),
+            "all" => Some(Self::All),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Last => "last",
+            Self::Random => "random",
+            Self::All => "all",
+        }
+    }
+}
+
+// the menu of `set pen (color) to` and `change pen (color) by`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PenColorParam {
+    Color,
+    Saturation,
+    Brightness,
+    Transparency,
+}
+
+impl PenColorParam {
+    pub fn from_scratch(param: &str) -> Option<Self> {
+        match param {
+            "color" => Some(Self::Color),
+            "saturation" => Some(Self::Saturation),
+            "brightness" => Some(Self::Brightness),
+            "transparency" => Some(Self::Transparency),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Color => "color",
+            Self::Saturation => "saturation",
+            Self::Brightness => "brightness",
+            Self::Transparency => "transparency",
+        }
+    }
+}
+
+// the menu of `set instrument to`, in the order of its numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instrument {
+    Piano,
+    ElectricPiano,
+    Organ,
+    Guitar,
+    ElectricGuitar,
+    Bass,
+    Pizzicato,
+    Cello,
+    Trombone,
+    Clarinet,
+    Saxophone,
+    Flute,
+    WoodenFlute,
+    Bassoon,
+    Choir,
+    Vibraphone,
+    MusicBox,
+    SteelDrum,
+    Marimba,
+    SynthLead,
+    SynthPad,
+}
+
+impl Instrument {
+    const ALL: [Self; 21] = [
+        Self::Piano,
+        Self::ElectricPiano,
+        Self::Organ,
+        Self::Guitar,
+        Self::ElectricGuitar,
+        Self::Bass,
+        Self::Pizzicato,
+        Self::Cello,
+        Self::Trombone,
+        Self::Clarinet,
+        Self::Saxophone,
+        Self::Flute,
+        Self::WoodenFlute,
+        Self::Bassoon,
+        Self::Choir,
+        Self::Vibraphone,
+        Self::MusicBox,
+        Self::SteelDrum,
+        Self::Marimba,
+        Self::SynthLead,
+        Self::SynthPad,
+    ];
+
+    // the menu holds the number, starting from 1
+    pub fn from_scratch(number: &str) -> Option<Self> {
+        let number: usize = number.trim().parse().ok()?;
+        Self::ALL.get(number.checked_sub(1)?).copied()
+    }
+
+    pub fn number(&self) -> usize {
+        *self as usize + 1
+    }
+}
+
+// the menu of `play drum`, in the order of its numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Drum {
+    SnareDrum,
+    BassDrum,
+    SideStick,
+    CrashCymbal,
+    OpenHiHat,
+    ClosedHiHat,
+    Tambourine,
+    HandClap,
+    Claves,
+    WoodBlock,
+    Cowbell,
+    Triangle,
+    Bongo,
+    Conga,
+    Cabasa,
+    Guiro,
+    Vibraslap,
+    Cuica,
+}
+
+impl Drum {
+    const ALL: [Self; 18] = [
+        Self::SnareDrum,
+        Self::BassDrum,
+        Self::SideStick,
+        Self::CrashCymbal,
+        Self::OpenHiHat,
+        Self::ClosedHiHat,
+        Self::Tambourine,
+        Self::HandClap,
+        Self::Claves,
+        Self::WoodBlock,
+        Self::Cowbell,
+        Self::Triangle,
+        Self::Bongo,
+        Self::Conga,
+        Self::Cabasa,
+        Self::Guiro,
+        Self::Vibraslap,
+        Self::Cuica,
+    ];
+
+    pub fn from_scratch(number: &str) -> Option<Self> {
+        let number: usize = number.trim().parse().ok()?;
+        Self::ALL.get(number.checked_sub(1)?).copied()
+    }
+
+    pub fn number(&self) -> usize {
+        *self as usize + 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopOption {
+    All,
+    ThisScript,
+    // `other scripts in stage` for the stage
+    OtherScriptsInSprite,
+}
+
+// a block the parser does not know, kept with `UnsupportedPolicy::Fallback` and run by the
+// shims of the runtime, fields and inputs are sorted by name
+#[derive(Debug)]
+pub struct FallbackBlock {
+    pub opcode: String,
+    pub fields: Vec<(String, String)>,
+    pub inputs: Vec<(String, FallbackInput)>,
 }
 
 #[derive(Debug)]
-pub enum Block {
+pub enum FallbackInput {
+    Expr(Expr),
+    // inputs named `SUBSTACK...`
+    Stack(BlockStack),
+}
+
+// stack blocks, C-blocks hold their substacks
+#[derive(Debug)]
+pub enum Stmt {
+    MotionMove(Expr),
+    MotionTurnRight(Expr),
+    MotionTurnLeft(Expr),
+    MotionGoTo(Expr),
+    MotionGoToXY(Expr, Expr),
+    // seconds, then where to
+    MotionGlideTo(Expr, Expr),
+    MotionGlideToXY(Expr, Expr, Expr),
+    MotionPointInDirection(Expr),
+    MotionPointTowards(Expr),
+    MotionChangeX(Expr),
+    MotionSetX(Expr),
+    MotionChangeY(Expr),
+    MotionSetY(Expr),
+    MotionIfOnEdgeBounce,
+    MotionSetRotationStyle(RotationStyle),
+    LooksSay(Expr),
+    // message, then seconds
+    LooksSayForSecs(Expr, Expr),
+    LooksThink(Expr),
+    LooksThinkForSecs(Expr, Expr),
+    LooksSwitchCostume(Expr),
+    LooksNextCostume,
+    LooksSwitchBackdrop(Expr),
+    LooksSwitchBackdropAndWait(Expr),
+    LooksNextBackdrop,
+    LooksChangeSize(Expr),
+    LooksSetSize(Expr),
+    LooksChangeEffect(GraphicEffect, Expr),
+    LooksSetEffect(GraphicEffect, Expr),
+    LooksClearEffects,
+    LooksShow,
+    LooksHide,
+    LooksGoToFront,
+    LooksGoToBack,
+    LooksGoForwardLayers(Expr),
+    LooksGoBackwardLayers(Expr),
+    SoundPlay(Expr),
+    SoundPlayUntilDone(Expr),
+    SoundStopAll,
+    SoundChangeVolume(Expr),
+    SoundSetVolume(Expr),
+    SoundChangeEffect(SoundEffect, Expr),
+    SoundSetEffect(SoundEffect, Expr),
+    SoundClearEffects,
+    // a broadcast name, either an `LlBroadcast` or computed
+    EventBroadcast(Expr),
+    EventBroadcastAndWait(Expr),
+    ControlForever(BlockStack),
+    ControlIf(BoolExpr, BlockStack),
+    ControlIfElse(BoolExpr, BlockStack, BlockStack),
+    ControlRepeat(Expr, BlockStack),
+    ControlRepeatUntil(BoolExpr, BlockStack),
+    ControlWhile(BoolExpr, BlockStack),
+    // counts the variable from 1 up to the value
+    ControlForEach(ResourcePath, Expr, BlockStack),
+    ControlWait(Expr),
+    ControlWaitUntil(BoolExpr),
+    ControlStop(StopOption),
+    // a sprite name, usually an `LlTarget`
+    ControlCreateClone(Expr),
+    ControlDeleteThisClone,
+    SensingAskAndWait(Expr),
+    SensingResetTimer,
+    // draggable or not
+    SensingSetDragMode(bool),
+    DataSetVariable(ResourcePath, Expr),
+    DataChangeVariable(ResourcePath, Expr),
+    DataShowVariable(ResourcePath),
+    DataHideVariable(ResourcePath),
+    DataAddToList(ResourcePath, Expr),
+    // the index, may be an `LlListIndex`
+    DataDeleteOfList(ResourcePath, Expr),
+    DataDeleteAllOfList(ResourcePath),
+    // the index, then the item
+    DataInsertAtList(ResourcePath, Expr, Expr),
+    DataReplaceItemOfList(ResourcePath, Expr, Expr),
+    DataShowList(ResourcePath),
+    DataHideList(ResourcePath),
+    PenClear,
+    PenStamp,
+    PenDown,
+    PenUp,
+    PenSetColor(Expr),
+    // the param, usually an `LlPenColorParam`, then the value
+    PenChangeColorParam(Expr, Expr),
+    PenSetColorParam(Expr, Expr),
+    PenChangeSize(Expr),
+    PenSetSize(Expr),
+    // shade and hue are the scratch 2 color model, kept for old projects
+    PenChangeShade(Expr),
+    PenSetShade(Expr),
+    PenChangeHue(Expr),
+    PenSetHue(Expr),
+    // the drum, usually an `LlDrum`, then beats
+    MusicPlayDrumForBeats(Expr, Expr),
+    MusicRestForBeats(Expr),
+    // the note as a midi number, then beats
+    MusicPlayNoteForBeats(Expr, Expr),
+    // usually an `LlInstrument`
+    MusicSetInstrument(Expr),
+    MusicSetTempo(Expr),
+    MusicChangeTempo(Expr),
+    // arguments in the order of `Definion::args`
+    ProcCall(ResourcePath, Vec<Expr>),
+    // the opcode of an unsupported block kept in place with `UnsupportedPolicy::Stub`
+    Stub(String),
+    Fallback(FallbackBlock),
+    // ...
+}
+
+// round reporters, their value is a number or a string
+#[derive(Debug)]
+pub enum Expr {
     LlScratchValue(ScratchValue),
     LlBroadcast(ResourcePath),
     LlVar(ResourcePath),
     LlList(ResourcePath),
-    BlockStack(BlockStack),
-    MotionMove(Box<Block>),
-    ControlForever(Box<Block>),
-    OperatorAdd(Box<Block>, Box<Block>),
-    PenClear,
+    LlKey(KeyId),
+    LlTarget(TargetMenu),
+    MotionXPosition,
+    MotionYPosition,
+    MotionDirection,
+    LlCostume(CostumeMenu),
+    LooksCostume(NumberName),
+    LooksBackdrop(NumberName),
+    LooksSize,
+    // index into the sounds of the target
+    LlSound(usize),
+    SoundVolume,
+    // a sprite name or `_mouse_`
+    SensingDistanceTo(Box<Expr>),
+    SensingAnswer,
+    SensingMouseX,
+    SensingMouseY,
+    SensingLoudness,
+    SensingTimer,
+    // the property, then the sprite name or `_stage_`
+    SensingOf(SensingOfProperty, Box<Expr>),
+    SensingCurrent(CurrentMenu),
+    SensingDaysSince2000,
+    SensingUsername,
+    OperatorAdd(Box<Expr>, Box<Expr>),
+    OperatorSubtract(Box<Expr>, Box<Expr>),
+    OperatorMultiply(Box<Expr>, Box<Expr>),
+    OperatorDivide(Box<Expr>, Box<Expr>),
+    // from, then to
+    OperatorRandom(Box<Expr>, Box<Expr>),
+    OperatorJoin(Box<Expr>, Box<Expr>),
+    // the 1-based index, then the string
+    OperatorLetterOf(Box<Expr>, Box<Expr>),
+    OperatorLength(Box<Expr>),
+    OperatorMod(Box<Expr>, Box<Expr>),
+    OperatorRound(Box<Expr>),
+    OperatorMathOp(MathOp, Box<Expr>),
+    LlListIndex(ListIndex),
+    LlPenColorParam(PenColorParam),
+    LlDrum(Drum),
+    LlInstrument(Instrument),
+    MusicGetTempo,
+    Fallback(FallbackBlock),
+    DataItemOfList(ResourcePath, Box<Expr>),
+    // the index of the first item equal to the value, 0 if there is none
+    DataItemNumOfList(ResourcePath, Box<Expr>),
+    DataLengthOfList(ResourcePath),
+    ProcArg(ResourcePath),
+    // a boolean reporter dropped into a round input
+    Bool(Box<BoolExpr>),
+    // ...
+}
+
+// hexagonal reporters
+#[derive(Debug)]
+pub enum BoolExpr {
+    LlBool(bool),
+    // an input nothing was dropped into, false when evaluated
+    Empty,
+    OperatorLt(Expr, Expr),
+    OperatorGt(Expr, Expr),
+    OperatorEquals(Expr, Expr),
+    OperatorAnd(Box<BoolExpr>, Box<BoolExpr>),
+    OperatorOr(Box<BoolExpr>, Box<BoolExpr>),
+    OperatorNot(Box<BoolExpr>),
+    // the string, then the part it is searched for
+    OperatorContains(Expr, Expr),
+    DataListContainsItem(ResourcePath, Expr),
+    // a sprite name, `_mouse_` or `_edge_`
+    SensingTouchingObject(Expr),
+    // colors are usually `#rrggbb` literals
+    SensingTouchingColor(Expr),
+    SensingColorIsTouchingColor(Expr, Expr),
+    SensingKeyPressed(Expr),
+    SensingMouseDown,
+    ProcArg(ResourcePath),
+    // anything else in a boolean input, cast like `Cast.toBoolean`
+    Cast(Box<Expr>),
     // ...
 }
@@ -1,13 +1,19 @@
-use std::{collections::HashMap, fmt::Display, rc::Rc};
+use std::{collections::HashMap, fmt::Display, rc::Rc, time::Instant};
 
-use colored::Colorize;
-use scratch_loader::sb3::{
-    Block as Sb3Block, Blocks, Broadcast, Input, InputType, List as Sb3List, Sb3File, ScratchValue,
-    Target, Variable as Sb3Variable,
+use scratch_loader::{
+    observer::{Observer, Phase, Progress, Silent, Warning},
+    sb3::{
+        Block as Sb3Block, Blocks, Broadcast, Costume as Sb3Costume, Input, InputType,
+        List as Sb3List, RotationStyle, Sb3File, ScratchValue, Sound as Sb3Sound, Target,
+        Variable as Sb3Variable,
+    },
 };
 
 use crate::ast::{
-    Background, Block, BlockItem, BlockStack, List, ParsedScratchProject, ResourcePath, Sprite,
+    Background, BlockItem, BlockStack, BoolExpr, Costume, CostumeMenu, CurrentMenu, DefArg,
+    DefArgKind, Definion, Drum, Expr, FallbackBlock, FallbackInput, GraphicEffect, GreaterThanMenu,
+    Instrument, KeyId, List, ListIndex, MathOp, NumberName, ParsedScratchProject, PenColorParam,
+    ResourcePath, SensingOfProperty, Sound, SoundEffect, Sprite, Stmt, StopOption, TargetMenu,
     Variable,
 };
 
@@ -15,7 +21,15 @@ use crate::ast::{
 pub enum ParseSb3Error {
     InvaildSb3InputFormat,
     CannotResolveVariableOrList(String),
-    Unsupported(String),
+    CannotResolveBroadcast(String),
+    CannotResolveBlock(String),
+    // a block the parser does not know, of an undeclared extension or with an unknown
+    // menu value, with `UnsupportedPolicy::Error`
+    Unsupported {
+        opcode: String,
+        block: String,
+        target: String,
+    },
 }
 
 impl Display for ParseSb3Error {
@@ -25,14 +39,40 @@ impl Display for ParseSb3Error {
             ParseSb3Error::CannotResolveVariableOrList(name) => {
                 write!(f, "cannot resolve variable or list `{}`", name)
             }
-            ParseSb3Error::Unsupported(msg) => write!(f, "unsupported: {}", msg),
+            ParseSb3Error::CannotResolveBroadcast(name) => {
+                write!(f, "cannot resolve broadcast `{}`", name)
+            }
+            ParseSb3Error::CannotResolveBlock(id) => write!(f, "cannot resolve block `{}`", id),
+            ParseSb3Error::Unsupported {
+                opcode,
+                block,
+                target,
+            } => write!(
+                f,
+                "unsupported block `{}` (block `{}` of `{}`)",
+                opcode, block, target
+            ),
         }
     }
 }
 
+// what becomes of blocks with an opcode the parser does not know
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnsupportedPolicy {
+    // fail with `ParseSb3Error::Unsupported`
+    Error,
+    // warn, then leave stack blocks out and replace reporters by `0`
+    #[default]
+    Stub,
+    // warn, then hand the block to the shims of the runtime, hats are still skipped
+    Fallback,
+}
+
 pub struct Sb3FormatParser {
     src: Sb3File,
     proj: ParsedScratchProject,
+    observer: Rc<dyn Observer>,
+    unsupported: UnsupportedPolicy,
 }
 
 impl Sb3FormatParser {
@@ -48,27 +88,69 @@ impl Sb3FormatParser {
                     broadcasts: HashMap::new(),
                     blocks: Vec::new(),
                     definions: HashMap::new(),
+                    costumes: Vec::new(),
+                    current_costume: 0,
+                    sounds: Vec::new(),
+                    volume: 100.0,
+                    tempo: 60.0,
                 },
                 extensions: Vec::new(),
             },
+            observer: Rc::new(Silent),
+            unsupported: UnsupportedPolicy::default(),
         }
     }
 
+    pub fn observer(mut self, observer: Rc<dyn Observer>) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    pub fn unsupported(mut self, policy: UnsupportedPolicy) -> Self {
+        self.unsupported = policy;
+        self
+    }
+
     pub fn parse(mut self) -> Result<ParsedScratchProject, ParseSb3Error> {
+        let start = Instant::now();
+        let observer = &*self.observer;
         Self::move_resources(&mut self.proj, self.src.resources)?;
-        for target in self.src.project.targets {
+        self.proj.extensions = self.src.project.extensions;
+        // `sensing_of` reads variables of other sprites, so every target is set up first
+        let mut scripts = Vec::new();
+        for mut target in self.src.project.targets {
+            let blocks = std::mem::replace(
+                &mut target.blocks,
+                Blocks {
+                    blocks: HashMap::new(),
+                },
+            );
             if target.is_stage {
+                scripts.push((None, target.name.clone(), blocks));
                 Self::parse_stage(&mut self.proj, target)?;
             } else {
+                scripts.push((Some(self.proj.sprites.len()), target.name.clone(), blocks));
                 Self::parse_sprite(&mut self.proj, target)?;
             }
         }
+        for (sprite, name, blocks) in scripts {
+            observer.progress(Progress::Parsing(&name));
+            Self::parse_scripts(
+                &mut self.proj,
+                sprite,
+                &name,
+                &blocks,
+                observer,
+                self.unsupported,
+            )?;
+        }
+        observer.timing(Phase::Parse, start.elapsed());
         Ok(self.proj)
     }
 
     fn move_resources(
         proj: &mut ParsedScratchProject,
-        resources: HashMap<String, String>,
+        resources: HashMap<String, Vec<u8>>,
     ) -> Result<(), ParseSb3Error> {
         for (id, content) in resources {
             let path = ResourcePath::new(Rc::new(id));
@@ -77,45 +159,110 @@ impl Sb3FormatParser {
         Ok(())
     }
 
+    // menus refer to costumes and broadcasts, so they are set up before the blocks
     fn parse_stage(proj: &mut ParsedScratchProject, stage: Target) -> Result<(), ParseSb3Error> {
         proj.background.variables = Self::parse_variables(stage.variables)?;
         proj.background.lists = Self::parse_lists(stage.lists)?;
-        let mut definions = HashMap::new();
-        proj.background.blocks = Self::parse_blocks(
-            &proj.background,
-            &proj.background.variables,
-            &proj.background.lists,
-            stage.blocks,
-            &mut definions,
-        )?;
-        proj.background.definions = definions;
+        proj.background.costumes = Self::parse_costumes(proj, stage.costumes)?;
+        proj.background.sounds = Self::parse_sounds(proj, stage.sounds)?;
+        proj.background.current_costume = stage.current_costume;
+        proj.background.volume = stage.volume;
+        proj.background.tempo = stage
+            .extra
+            .get("tempo")
+            .and_then(|tempo| tempo.as_f64())
+            .unwrap_or(60.0);
         proj.background.broadcasts = Self::parse_broadcasts(stage.broadcasts)?;
         Ok(())
     }
 
     fn parse_sprite(proj: &mut ParsedScratchProject, sprite: Target) -> Result<(), ParseSb3Error> {
-        let name = sprite.name;
         let variables = Self::parse_variables(sprite.variables)?;
         let lists = Self::parse_lists(sprite.lists)?;
-        let mut definions = HashMap::new();
-        let blocks = Self::parse_blocks(
-            &proj.background,
-            &variables,
-            &lists,
-            sprite.blocks,
-            &mut definions,
-        )?;
+        let costumes = Self::parse_costumes(proj, sprite.costumes)?;
+        let sounds = Self::parse_sounds(proj, sprite.sounds)?;
         let sprite = Sprite {
-            name,
+            name: sprite.name,
+            position: (
+                sprite.position.x.unwrap_or_default(),
+                sprite.position.y.unwrap_or_default(),
+            ),
+            direction: sprite.direction.unwrap_or(90.0),
+            rotation_style: sprite.rotation_style.unwrap_or_default(),
+            size: sprite.size.unwrap_or(100.0),
+            visible: sprite.visible.unwrap_or(true),
+            draggable: sprite.draggable.unwrap_or_default(),
+            current_costume: sprite.current_costume,
+            volume: sprite.volume,
             variables,
             lists,
-            blocks,
-            definions,
+            blocks: Vec::new(),
+            definions: HashMap::new(),
+            costumes,
+            sounds,
         };
         proj.sprites.push(sprite);
         Ok(())
     }
 
+    // the blocks of the stage, or of the sprite at index `sprite`
+    fn parse_scripts(
+        proj: &mut ParsedScratchProject,
+        sprite: Option<usize>,
+        name: &str,
+        blocks: &Blocks,
+        observer: &dyn Observer,
+        unsupported: UnsupportedPolicy,
+    ) -> Result<(), ParseSb3Error> {
+        let definions = Self::parse_definions(blocks)?;
+        let (variable, list, costumes, sounds) = match sprite {
+            Some(idx) => {
+                let sprite = &proj.sprites[idx];
+                (
+                    &sprite.variables,
+                    &sprite.lists,
+                    &sprite.costumes,
+                    &sprite.sounds,
+                )
+            }
+            None => {
+                let stage = &proj.background;
+                (
+                    &stage.variables,
+                    &stage.lists,
+                    &stage.costumes,
+                    &stage.sounds,
+                )
+            }
+        };
+        let items = Self::parse_blocks(&TargetContext {
+            background: &proj.background,
+            sprites: &proj.sprites,
+            extensions: &proj.extensions,
+            variable,
+            list,
+            costumes,
+            sounds,
+            blocks,
+            definions: &definions,
+            args: None,
+            target: name,
+            observer,
+            unsupported,
+        })?;
+        match sprite {
+            Some(idx) => {
+                proj.sprites[idx].blocks = items;
+                proj.sprites[idx].definions = definions;
+            }
+            None => {
+                proj.background.blocks = items;
+                proj.background.definions = definions;
+            }
+        }
+        Ok(())
+    }
+
     fn parse_variables(
         variables: HashMap<String, Sb3Variable>,
     ) -> Result<HashMap<String, Variable>, ParseSb3Error> {
@@ -143,178 +290,1243 @@ impl Sb3FormatParser {
             .collect())
     }
 
-    fn parse_blocks(
-        background: &Background,
-        variable: &HashMap<String, Variable>,
-        list: &HashMap<String, List>,
-        blocks: Blocks,
-        definions: &mut HashMap<String, ResourcePath>,
-    ) -> Result<Vec<BlockItem>, ParseSb3Error> {
+    fn parse_costumes(
+        proj: &ParsedScratchProject,
+        costumes: Vec<Sb3Costume>,
+    ) -> Result<Vec<Costume>, ParseSb3Error> {
+        Ok(costumes
+            .into_iter()
+            .map(|c| Costume {
+                asset: Self::lookup_resource(proj, c.md5ext),
+                name: c.name,
+                format: c.data_format,
+                center: (c.center_x, c.center_y),
+            })
+            .collect())
+    }
+
+    fn parse_sounds(
+        proj: &ParsedScratchProject,
+        sounds: Vec<Sb3Sound>,
+    ) -> Result<Vec<Sound>, ParseSb3Error> {
+        Ok(sounds
+            .into_iter()
+            .map(|s| Sound {
+                asset: Self::lookup_resource(proj, s.md5ext),
+                name: s.name,
+                format: s.data_format,
+                rate: s.rate,
+                sample_count: s.sample_count,
+            })
+            .collect())
+    }
+
+    // assets missing from the archive still get a path, codegen may load them elsewhere
+    fn lookup_resource(proj: &ParsedScratchProject, md5ext: String) -> ResourcePath {
+        match proj.resources.keys().find(|path| **path.name() == md5ext) {
+            Some(path) => path.clone(),
+            None => ResourcePath::new(Rc::new(md5ext)),
+        }
+    }
+
+    fn parse_definions(blocks: &Blocks) -> Result<HashMap<String, Definion>, ParseSb3Error> {
+        let mut definions = HashMap::new();
+        for b in blocks.blocks.values() {
+            if b.opcode != "procedures_definition" {
+                continue;
+            }
+            let proto = match b.inputs.get("custom_block") {
+                Some(Input::NoHidden(_, InputType::Block(id))) => Self::block(blocks, id)?,
+                _ => return Err(ParseSb3Error::InvaildSb3InputFormat),
+            };
+            let mutation = proto
+                .mutation
+                .as_ref()
+                .ok_or(ParseSb3Error::InvaildSb3InputFormat)?;
+            let proccode = mutation
+                .proccode
+                .clone()
+                .ok_or(ParseSb3Error::InvaildSb3InputFormat)?;
+            let kinds = proccode
+                .split('%')
+                .skip(1)
+                .filter_map(|arg| match arg.chars().next() {
+                    Some('b') => Some(DefArgKind::Bool),
+                    Some('s') | Some('n') => Some(DefArgKind::StrNum),
+                    _ => None,
+                });
+            let args = mutation
+                .argument_names
+                .iter()
+                .zip(kinds)
+                .enumerate()
+                .map(|(idx, (name, kind))| DefArg {
+                    path: ResourcePath::new(Rc::new(name.clone())),
+                    kind,
+                    default: mutation.argument_defaults.get(idx).cloned(),
+                })
+                .collect();
+            let definion = Definion {
+                path: ResourcePath::new(Rc::new(proccode.clone())),
+                args,
+                warp: mutation.warp,
+            };
+            definions.insert(proccode, definion);
+        }
+        Ok(definions)
+    }
+
+    fn parse_blocks(ctx: &TargetContext) -> Result<Vec<BlockItem>, ParseSb3Error> {
         let mut items = Vec::new();
-        for (_, b) in blocks.blocks.iter().filter(|(_, b)| b.top_level) {
+        // scripts are read top to bottom, then left to right, so the output is stable
+        let mut top_level: Vec<_> = ctx
+            .blocks
+            .blocks
+            .iter()
+            .filter(|(_, b)| b.top_level)
+            .collect();
+        top_level.sort_by(|(a_id, a), (b_id, b)| {
+            let pos = |b: &Sb3Block| (b.y.unwrap_or(0.0), b.x.unwrap_or(0.0));
+            let ((a_y, a_x), (b_y, b_x)) = (pos(a), pos(b));
+            a_y.total_cmp(&b_y)
+                .then(a_x.total_cmp(&b_x))
+                .then(a_id.cmp(b_id))
+        });
+        for (id, b) in top_level {
             match b.opcode.as_str() {
                 "event_whenflagclicked" => {
-                    let bs = Self::parse_block_stack(background, variable, list, &blocks, b)?;
+                    let bs = Self::parse_block_stack(ctx, id)?;
                     items.push(BlockItem::EvWhenGreenFlagClicked(bs));
                 }
-                _ => { /* should return an Err here */ }
+                "event_whenkeypressed" => {
+                    let Some(key) = KeyId::from_scratch(Self::field(b, "KEY_OPTION")?) else {
+                        // an empty key never fires
+                        Self::warn(ctx, id, "key hat without a key is ignored".to_string());
+                        continue;
+                    };
+                    let bs = Self::parse_block_stack(ctx, id)?;
+                    items.push(BlockItem::EvWhenKeyPressed(key, bs));
+                }
+                "event_whenthisspriteclicked" => {
+                    let bs = Self::parse_block_stack(ctx, id)?;
+                    items.push(BlockItem::EvWhenThisSpriteClicked(bs));
+                }
+                "event_whenstageclicked" => {
+                    let bs = Self::parse_block_stack(ctx, id)?;
+                    items.push(BlockItem::EvWhenStageClicked(bs));
+                }
+                "event_whenbackdropswitchesto" => {
+                    let backdrop = Self::field(b, "BACKDROP")?.to_string();
+                    let bs = Self::parse_block_stack(ctx, id)?;
+                    items.push(BlockItem::EvWhenBackdropSwitchesTo(backdrop, bs));
+                }
+                "event_whengreaterthan" => {
+                    let menu = match Self::field(b, "WHENGREATERTHANMENU")?
+                        .to_lowercase()
+                        .as_str()
+                    {
+                        "timer" => GreaterThanMenu::Timer,
+                        _ => GreaterThanMenu::Loudness,
+                    };
+                    let value = Self::parse_input(ctx, b, "VALUE")?;
+                    let bs = Self::parse_block_stack(ctx, id)?;
+                    items.push(BlockItem::EvWhenGreaterThan(menu, value, bs));
+                }
+                "event_whenbroadcastreceived" => {
+                    let field = b
+                        .fields
+                        .get("BROADCAST_OPTION")
+                        .ok_or(ParseSb3Error::InvaildSb3InputFormat)?;
+                    let broadcast = Self::lookup_broadcast(ctx, &field.0, field.1.as_deref())?;
+                    let bs = Self::parse_block_stack(ctx, id)?;
+                    items.push(BlockItem::EWhenRecieveBroadcast(broadcast, bs));
+                }
+                "control_start_as_clone" => {
+                    let bs = Self::parse_block_stack(ctx, id)?;
+                    items.push(BlockItem::ControlStartAsClone(bs));
+                }
+                "procedures_definition" => {
+                    let proccode = match b.inputs.get("custom_block") {
+                        Some(Input::NoHidden(_, InputType::Block(id))) => {
+                            Self::block(ctx.blocks, id)?
+                                .mutation
+                                .as_ref()
+                                .and_then(|m| m.proccode.as_ref())
+                        }
+                        _ => None,
+                    };
+                    let definion = proccode
+                        .and_then(|proccode| ctx.definions.get(proccode))
+                        .ok_or(ParseSb3Error::InvaildSb3InputFormat)?;
+                    let ctx = TargetContext {
+                        args: Some(definion),
+                        ..*ctx
+                    };
+                    let bs = Self::parse_block_stack(&ctx, id)?;
+                    items.push(BlockItem::Def(definion.clone(), bs));
+                }
+                opcode if Self::is_hat(opcode) => {
+                    if ctx.unsupported == UnsupportedPolicy::Error {
+                        return Err(Self::unsupported_block(ctx, id, b));
+                    }
+                    let message = format!("unsupported hat block `{}`, script skipped", opcode);
+                    Self::warn(ctx, id, message);
+                }
+                // scripts without a hat never run
+                _ => {}
             }
         }
         Ok(items)
     }
 
+    // hats are named `when...`, e.g. `event_whenflagclicked` or `pen_whenX` of extensions
+    fn is_hat(opcode: &str) -> bool {
+        opcode
+            .split_once('_')
+            .is_some_and(|(_, name)| name.to_lowercase().starts_with("when"))
+    }
+
     fn parse_block_stack_from_here(
-        background: &Background,
-        variable: &HashMap<String, Variable>,
-        list: &HashMap<String, List>,
-        blocks: &Blocks,
-        tl_block: &Sb3Block,
+        ctx: &TargetContext,
+        tl_id: &str,
     ) -> Result<BlockStack, ParseSb3Error> {
         let mut bs = Vec::new();
-        let mut block_p = tl_block;
-        bs.push(Self::parse_block(
-            blocks, background, variable, list, block_p,
-        )?);
-        while let Some(next) = &block_p.next {
-            let block = &blocks.blocks[next.as_str()];
-            block_p = block;
-            bs.push(Self::parse_block(
-                blocks, background, variable, list, block_p,
-            )?);
+        let mut next = Some(tl_id);
+        while let Some(id) = next {
+            let block = Self::block(ctx.blocks, id)?;
+            if let Some(stmt) = Self::parse_stmt(ctx, id, block)? {
+                bs.push(stmt);
+            }
+            next = block.next.as_deref();
         }
         Ok(BlockStack::new(bs))
     }
 
-    fn parse_block_stack(
-        background: &Background,
-        variable: &HashMap<String, Variable>,
-        list: &HashMap<String, List>,
-        blocks: &Blocks,
-        tl_block: &Sb3Block,
+    // the stack below a hat, without the hat itself
+    fn parse_block_stack(ctx: &TargetContext, hat_id: &str) -> Result<BlockStack, ParseSb3Error> {
+        match &Self::block(ctx.blocks, hat_id)?.next {
+            Some(next) => Self::parse_block_stack_from_here(ctx, next),
+            None => Ok(BlockStack::new(Vec::new())),
+        }
+    }
+
+    // the stack inside a C-block, scratch leaves the input out when it is empty
+    fn parse_substack(
+        ctx: &TargetContext,
+        block: &Sb3Block,
+        name: &str,
     ) -> Result<BlockStack, ParseSb3Error> {
-        let mut bs = Vec::new();
-        let mut block_p = tl_block;
-        while let Some(next) = &block_p.next {
-            let block = &blocks.blocks[next.as_str()];
-            block_p = block;
-            bs.push(Self::parse_block(
-                blocks, background, variable, list, block_p,
-            )?);
+        match block.inputs.get(name) {
+            Some(Input::NoHidden(_, InputType::Block(id)))
+            | Some(Input::Hidden(_, InputType::Block(id), _)) => {
+                Self::parse_block_stack_from_here(ctx, id)
+            }
+            Some(Input::NoHidden(_, InputType::Empty)) | None => Ok(BlockStack::new(Vec::new())),
+            Some(_) => Err(ParseSb3Error::InvaildSb3InputFormat),
         }
-        Ok(BlockStack::new(bs))
     }
 
-    fn parse_block(
-        blocks: &Blocks,
-        background: &Background,
-        variable: &HashMap<String, Variable>,
-        list: &HashMap<String, List>,
+    fn unsupported_block(ctx: &TargetContext, id: &str, block: &Sb3Block) -> ParseSb3Error {
+        ParseSb3Error::Unsupported {
+            opcode: block.opcode.clone(),
+            block: id.to_string(),
+            target: ctx.target.to_string(),
+        }
+    }
+
+    // the inputs are parsed as usual, so only the unknown block itself is left to the runtime
+    fn parse_fallback(
+        ctx: &TargetContext,
+        block: &Sb3Block,
+    ) -> Result<FallbackBlock, ParseSb3Error> {
+        let mut fields: Vec<_> = block
+            .fields
+            .iter()
+            .map(|(name, field)| (name.clone(), field.0.clone()))
+            .collect();
+        fields.sort();
+        let mut inputs = Vec::new();
+        for (name, input) in &block.inputs {
+            let input = if name.starts_with("SUBSTACK") {
+                FallbackInput::Stack(Self::parse_substack(ctx, block, name)?)
+            } else {
+                FallbackInput::Expr(Self::parse_expr(ctx, input)?)
+            };
+            inputs.push((name.clone(), input));
+        }
+        inputs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(FallbackBlock {
+            opcode: block.opcode.clone(),
+            fields,
+            inputs,
+        })
+    }
+
+    // blocks of `pen` and `music` only load when the project lists the extension
+    fn undeclared_extension<'b>(ctx: &TargetContext, opcode: &'b str) -> Option<&'b str> {
+        let (extension, _) = opcode.split_once('_')?;
+        let known = matches!(extension, "pen" | "music");
+        (known && !ctx.extensions.iter().any(|e| e == extension)).then_some(extension)
+    }
+
+    fn warn(ctx: &TargetContext, id: &str, message: String) {
+        ctx.observer.warning(&Warning {
+            target: Some(ctx.target.to_string()),
+            block: Some(id.to_string()),
+            message,
+        });
+    }
+
+    // `None` for blocks that do nothing and are left out of the stack
+    fn parse_stmt(
+        ctx: &TargetContext,
+        id: &str,
+        block: &Sb3Block,
+    ) -> Result<Option<Stmt>, ParseSb3Error> {
+        if let Some(extension) = Self::undeclared_extension(ctx, &block.opcode) {
+            if ctx.unsupported == UnsupportedPolicy::Error {
+                return Err(Self::unsupported_block(ctx, id, block));
+            }
+            let message = format!(
+                "`{}` needs the `{}` extension, which is not declared, skipped",
+                block.opcode, extension
+            );
+            Self::warn(ctx, id, message);
+            return Ok(None);
+        }
+        let stmt = match block.opcode.as_str() {
+            "motion_movesteps" => Stmt::MotionMove(Self::parse_input(ctx, block, "STEPS")?),
+            "motion_turnright" => Stmt::MotionTurnRight(Self::parse_input(ctx, block, "DEGREES")?),
+            "motion_turnleft" => Stmt::MotionTurnLeft(Self::parse_input(ctx, block, "DEGREES")?),
+            "motion_goto" => Stmt::MotionGoTo(Self::parse_input(ctx, block, "TO")?),
+            "motion_gotoxy" => Stmt::MotionGoToXY(
+                Self::parse_input(ctx, block, "X")?,
+                Self::parse_input(ctx, block, "Y")?,
+            ),
+            "motion_glideto" => Stmt::MotionGlideTo(
+                Self::parse_input(ctx, block, "SECS")?,
+                Self::parse_input(ctx, block, "TO")?,
+            ),
+            "motion_glidesecstoxy" => Stmt::MotionGlideToXY(
+                Self::parse_input(ctx, block, "SECS")?,
+                Self::parse_input(ctx, block, "X")?,
+                Self::parse_input(ctx, block, "Y")?,
+            ),
+            "motion_pointindirection" => {
+                Stmt::MotionPointInDirection(Self::parse_input(ctx, block, "DIRECTION")?)
+            }
+            "motion_pointtowards" => {
+                Stmt::MotionPointTowards(Self::parse_input(ctx, block, "TOWARDS")?)
+            }
+            "motion_changexby" => Stmt::MotionChangeX(Self::parse_input(ctx, block, "DX")?),
+            "motion_setx" => Stmt::MotionSetX(Self::parse_input(ctx, block, "X")?),
+            "motion_changeyby" => Stmt::MotionChangeY(Self::parse_input(ctx, block, "DY")?),
+            "motion_sety" => Stmt::MotionSetY(Self::parse_input(ctx, block, "Y")?),
+            "motion_ifonedgebounce" => Stmt::MotionIfOnEdgeBounce,
+            "motion_setrotationstyle" => {
+                let style = match Self::field(block, "STYLE")? {
+                    "left-right" => RotationStyle::LeftRight,
+                    "don't rotate" => RotationStyle::NoRotation,
+                    _ => RotationStyle::AllAround,
+                };
+                Stmt::MotionSetRotationStyle(style)
+            }
+            "looks_say" => Stmt::LooksSay(Self::parse_input(ctx, block, "MESSAGE")?),
+            "looks_sayforsecs" => Stmt::LooksSayForSecs(
+                Self::parse_input(ctx, block, "MESSAGE")?,
+                Self::parse_input(ctx, block, "SECS")?,
+            ),
+            "looks_think" => Stmt::LooksThink(Self::parse_input(ctx, block, "MESSAGE")?),
+            "looks_thinkforsecs" => Stmt::LooksThinkForSecs(
+                Self::parse_input(ctx, block, "MESSAGE")?,
+                Self::parse_input(ctx, block, "SECS")?,
+            ),
+            "looks_switchcostumeto" => {
+                Stmt::LooksSwitchCostume(Self::parse_input(ctx, block, "COSTUME")?)
+            }
+            "looks_nextcostume" => Stmt::LooksNextCostume,
+            "looks_switchbackdropto" => {
+                Stmt::LooksSwitchBackdrop(Self::parse_input(ctx, block, "BACKDROP")?)
+            }
+            "looks_switchbackdroptoandwait" => {
+                Stmt::LooksSwitchBackdropAndWait(Self::parse_input(ctx, block, "BACKDROP")?)
+            }
+            "looks_nextbackdrop" => Stmt::LooksNextBackdrop,
+            "looks_changesizeby" => Stmt::LooksChangeSize(Self::parse_input(ctx, block, "CHANGE")?),
+            "looks_setsizeto" => Stmt::LooksSetSize(Self::parse_input(ctx, block, "SIZE")?),
+            "looks_changeeffectby" => match Self::parse_effect(block)? {
+                Some(effect) => {
+                    Stmt::LooksChangeEffect(effect, Self::parse_input(ctx, block, "CHANGE")?)
+                }
+                None => {
+                    let what = format!("graphic effect `{}`", Self::field(block, "EFFECT")?);
+                    Self::unsupported_stmt(ctx, id, block, &what)?
+                }
+            },
+            "looks_seteffectto" => match Self::parse_effect(block)? {
+                Some(effect) => {
+                    Stmt::LooksSetEffect(effect, Self::parse_input(ctx, block, "VALUE")?)
+                }
+                None => {
+                    let what = format!("graphic effect `{}`", Self::field(block, "EFFECT")?);
+                    Self::unsupported_stmt(ctx, id, block, &what)?
+                }
+            },
+            "looks_cleargraphiceffects" => Stmt::LooksClearEffects,
+            "looks_show" => Stmt::LooksShow,
+            "looks_hide" => Stmt::LooksHide,
+            "looks_gotofrontback" => match Self::field(block, "FRONT_BACK")? {
+                "back" => Stmt::LooksGoToBack,
+                _ => Stmt::LooksGoToFront,
+            },
+            "looks_goforwardbackwardlayers" => {
+                let layers = Self::parse_input(ctx, block, "NUM")?;
+                match Self::field(block, "FORWARD_BACKWARD")? {
+                    "backward" => Stmt::LooksGoBackwardLayers(layers),
+                    _ => Stmt::LooksGoForwardLayers(layers),
+                }
+            }
+            "sound_play" => Stmt::SoundPlay(Self::parse_input(ctx, block, "SOUND_MENU")?),
+            "sound_playuntildone" => {
+                Stmt::SoundPlayUntilDone(Self::parse_input(ctx, block, "SOUND_MENU")?)
+            }
+            "sound_stopallsounds" => Stmt::SoundStopAll,
+            "sound_changevolumeby" => {
+                Stmt::SoundChangeVolume(Self::parse_input(ctx, block, "VOLUME")?)
+            }
+            "sound_setvolumeto" => Stmt::SoundSetVolume(Self::parse_input(ctx, block, "VOLUME")?),
+            "sound_changeeffectby" => match Self::parse_sound_effect(block)? {
+                Some(effect) => {
+                    Stmt::SoundChangeEffect(effect, Self::parse_input(ctx, block, "VALUE")?)
+                }
+                None => {
+                    let what = format!("sound effect `{}`", Self::field(block, "EFFECT")?);
+                    Self::unsupported_stmt(ctx, id, block, &what)?
+                }
+            },
+            "sound_seteffectto" => match Self::parse_sound_effect(block)? {
+                Some(effect) => {
+                    Stmt::SoundSetEffect(effect, Self::parse_input(ctx, block, "VALUE")?)
+                }
+                None => {
+                    let what = format!("sound effect `{}`", Self::field(block, "EFFECT")?);
+                    Self::unsupported_stmt(ctx, id, block, &what)?
+                }
+            },
+            "sound_cleareffects" => Stmt::SoundClearEffects,
+            "event_broadcast" => {
+                Stmt::EventBroadcast(Self::parse_input(ctx, block, "BROADCAST_INPUT")?)
+            }
+            "event_broadcastandwait" => {
+                Stmt::EventBroadcastAndWait(Self::parse_input(ctx, block, "BROADCAST_INPUT")?)
+            }
+            "pen_clear" => Stmt::PenClear,
+            "pen_stamp" => Stmt::PenStamp,
+            "pen_penDown" => Stmt::PenDown,
+            "pen_penUp" => Stmt::PenUp,
+            "pen_setPenColorToColor" => Stmt::PenSetColor(Self::parse_input(ctx, block, "COLOR")?),
+            "pen_changePenColorParamBy" => Stmt::PenChangeColorParam(
+                Self::parse_input(ctx, block, "COLOR_PARAM")?,
+                Self::parse_input(ctx, block, "VALUE")?,
+            ),
+            "pen_setPenColorParamTo" => Stmt::PenSetColorParam(
+                Self::parse_input(ctx, block, "COLOR_PARAM")?,
+                Self::parse_input(ctx, block, "VALUE")?,
+            ),
+            "pen_changePenSizeBy" => Stmt::PenChangeSize(Self::parse_input(ctx, block, "SIZE")?),
+            "pen_setPenSizeTo" => Stmt::PenSetSize(Self::parse_input(ctx, block, "SIZE")?),
+            "pen_changePenShadeBy" => Stmt::PenChangeShade(Self::parse_input(ctx, block, "SHADE")?),
+            "pen_setPenShadeToNumber" => Stmt::PenSetShade(Self::parse_input(ctx, block, "SHADE")?),
+            "pen_changePenHueBy" => Stmt::PenChangeHue(Self::parse_input(ctx, block, "HUE")?),
+            "pen_setPenHueToNumber" => Stmt::PenSetHue(Self::parse_input(ctx, block, "HUE")?),
+            "music_playDrumForBeats" => Stmt::MusicPlayDrumForBeats(
+                Self::parse_input(ctx, block, "DRUM")?,
+                Self::parse_input(ctx, block, "BEATS")?,
+            ),
+            "music_restForBeats" => {
+                Stmt::MusicRestForBeats(Self::parse_input(ctx, block, "BEATS")?)
+            }
+            "music_playNoteForBeats" => Stmt::MusicPlayNoteForBeats(
+                Self::parse_input(ctx, block, "NOTE")?,
+                Self::parse_input(ctx, block, "BEATS")?,
+            ),
+            "music_setInstrument" => {
+                Stmt::MusicSetInstrument(Self::parse_input(ctx, block, "INSTRUMENT")?)
+            }
+            "music_setTempo" => Stmt::MusicSetTempo(Self::parse_input(ctx, block, "TEMPO")?),
+            "music_changeTempo" => Stmt::MusicChangeTempo(Self::parse_input(ctx, block, "TEMPO")?),
+            "control_forever" => {
+                Stmt::ControlForever(Self::parse_substack(ctx, block, "SUBSTACK")?)
+            }
+            "control_if" => Stmt::ControlIf(
+                Self::parse_bool(ctx, block.inputs.get("CONDITION"))?,
+                Self::parse_substack(ctx, block, "SUBSTACK")?,
+            ),
+            "control_if_else" => Stmt::ControlIfElse(
+                Self::parse_bool(ctx, block.inputs.get("CONDITION"))?,
+                Self::parse_substack(ctx, block, "SUBSTACK")?,
+                Self::parse_substack(ctx, block, "SUBSTACK2")?,
+            ),
+            "control_repeat" => Stmt::ControlRepeat(
+                Self::parse_input(ctx, block, "TIMES")?,
+                Self::parse_substack(ctx, block, "SUBSTACK")?,
+            ),
+            "control_repeat_until" => Stmt::ControlRepeatUntil(
+                Self::parse_bool(ctx, block.inputs.get("CONDITION"))?,
+                Self::parse_substack(ctx, block, "SUBSTACK")?,
+            ),
+            "control_while" => Stmt::ControlWhile(
+                Self::parse_bool(ctx, block.inputs.get("CONDITION"))?,
+                Self::parse_substack(ctx, block, "SUBSTACK")?,
+            ),
+            "control_for_each" => Stmt::ControlForEach(
+                Self::field_variable(ctx, block)?,
+                Self::parse_input(ctx, block, "VALUE")?,
+                Self::parse_substack(ctx, block, "SUBSTACK")?,
+            ),
+            "control_wait" => Stmt::ControlWait(Self::parse_input(ctx, block, "DURATION")?),
+            "control_wait_until" => {
+                Stmt::ControlWaitUntil(Self::parse_bool(ctx, block.inputs.get("CONDITION"))?)
+            }
+            "control_stop" => {
+                let option = match Self::field(block, "STOP_OPTION")? {
+                    "all" => StopOption::All,
+                    "this script" => StopOption::ThisScript,
+                    "other scripts in sprite" | "other scripts in stage" => {
+                        StopOption::OtherScriptsInSprite
+                    }
+                    option => {
+                        let what = format!("stop option `{}`", option);
+                        return Self::unsupported_stmt(ctx, id, block, &what).map(Some);
+                    }
+                };
+                Stmt::ControlStop(option)
+            }
+            "control_create_clone_of" => {
+                Stmt::ControlCreateClone(Self::parse_input(ctx, block, "CLONE_OPTION")?)
+            }
+            "control_delete_this_clone" => Stmt::ControlDeleteThisClone,
+            "sensing_askandwait" => {
+                Stmt::SensingAskAndWait(Self::parse_input(ctx, block, "QUESTION")?)
+            }
+            "sensing_resettimer" => Stmt::SensingResetTimer,
+            "sensing_setdragmode" => {
+                Stmt::SensingSetDragMode(Self::field(block, "DRAG_MODE")? == "draggable")
+            }
+            "data_setvariableto" => Stmt::DataSetVariable(
+                Self::field_variable(ctx, block)?,
+                Self::parse_input(ctx, block, "VALUE")?,
+            ),
+            "data_changevariableby" => Stmt::DataChangeVariable(
+                Self::field_variable(ctx, block)?,
+                Self::parse_input(ctx, block, "VALUE")?,
+            ),
+            "data_showvariable" => Stmt::DataShowVariable(Self::field_variable(ctx, block)?),
+            "data_hidevariable" => Stmt::DataHideVariable(Self::field_variable(ctx, block)?),
+            "data_addtolist" => Stmt::DataAddToList(
+                Self::field_list(ctx, block)?,
+                Self::parse_input(ctx, block, "ITEM")?,
+            ),
+            "data_deleteoflist" => Stmt::DataDeleteOfList(
+                Self::field_list(ctx, block)?,
+                Self::parse_list_index(ctx, block)?,
+            ),
+            "data_deletealloflist" => Stmt::DataDeleteAllOfList(Self::field_list(ctx, block)?),
+            "data_insertatlist" => Stmt::DataInsertAtList(
+                Self::field_list(ctx, block)?,
+                Self::parse_list_index(ctx, block)?,
+                Self::parse_input(ctx, block, "ITEM")?,
+            ),
+            "data_replaceitemoflist" => Stmt::DataReplaceItemOfList(
+                Self::field_list(ctx, block)?,
+                Self::parse_list_index(ctx, block)?,
+                Self::parse_input(ctx, block, "ITEM")?,
+            ),
+            "data_showlist" => Stmt::DataShowList(Self::field_list(ctx, block)?),
+            "data_hidelist" => Stmt::DataHideList(Self::field_list(ctx, block)?),
+            "procedures_call" => return Self::parse_call(ctx, id, block),
+            opcode => {
+                let what = format!("block opcode `{}`", opcode);
+                Self::unsupported_stmt(ctx, id, block, &what)?
+            }
+        };
+        Ok(Some(stmt))
+    }
+
+    // `what` is the opcode or the menu value the parser does not know
+    fn unsupported_stmt(
+        ctx: &TargetContext,
+        id: &str,
+        block: &Sb3Block,
+        what: &str,
+    ) -> Result<Stmt, ParseSb3Error> {
+        match ctx.unsupported {
+            UnsupportedPolicy::Error => Err(Self::unsupported_block(ctx, id, block)),
+            UnsupportedPolicy::Stub => {
+                Self::warn(ctx, id, format!("unsupported {}, stubbed", what));
+                Ok(Stmt::Stub(block.opcode.clone()))
+            }
+            UnsupportedPolicy::Fallback => {
+                Self::warn(
+                    ctx,
+                    id,
+                    format!("unsupported {}, left to the runtime", what),
+                );
+                Ok(Stmt::Fallback(Self::parse_fallback(ctx, block)?))
+            }
+        }
+    }
+
+    fn unsupported_reporter(
+        ctx: &TargetContext,
+        id: &str,
         block: &Sb3Block,
-    ) -> Result<Block, ParseSb3Error> {
+        what: &str,
+    ) -> Result<Expr, ParseSb3Error> {
+        match ctx.unsupported {
+            UnsupportedPolicy::Error => Err(Self::unsupported_block(ctx, id, block)),
+            UnsupportedPolicy::Stub => {
+                Self::warn(ctx, id, format!("unsupported {}, replaced by `0`", what));
+                Ok(Expr::LlScratchValue(ScratchValue::Num(0.0)))
+            }
+            UnsupportedPolicy::Fallback => {
+                Self::warn(
+                    ctx,
+                    id,
+                    format!("unsupported {}, left to the runtime", what),
+                );
+                // the menus of unknown blocks are shadows with a single field, passed as is
+                match block.fields.values().next() {
+                    Some(field)
+                        if block.shadow && block.inputs.is_empty() && block.fields.len() == 1 =>
+                    {
+                        Ok(Expr::LlScratchValue(ScratchValue::Str(field.0.clone())))
+                    }
+                    _ => Ok(Expr::Fallback(Self::parse_fallback(ctx, block)?)),
+                }
+            }
+        }
+    }
+
+    fn parse_reporter(
+        ctx: &TargetContext,
+        id: &str,
+        block: &Sb3Block,
+    ) -> Result<Expr, ParseSb3Error> {
+        if let Some(cond) = Self::parse_bool_reporter(ctx, block)? {
+            return Ok(Expr::Bool(Box::new(cond)));
+        }
         match block.opcode.as_str() {
-            "motion_movesteps" => Ok(Block::MotionMove(Box::new(Self::parse_input(
-                blocks,
-                background,
-                variable,
-                list,
-                &block.inputs["STEPS"],
+            "operator_add" => {
+                let (a, b) = Self::parse_operands(ctx, block, "NUM1", "NUM2")?;
+                Ok(Expr::OperatorAdd(a, b))
+            }
+            "operator_subtract" => {
+                let (a, b) = Self::parse_operands(ctx, block, "NUM1", "NUM2")?;
+                Ok(Expr::OperatorSubtract(a, b))
+            }
+            "operator_multiply" => {
+                let (a, b) = Self::parse_operands(ctx, block, "NUM1", "NUM2")?;
+                Ok(Expr::OperatorMultiply(a, b))
+            }
+            "operator_divide" => {
+                let (a, b) = Self::parse_operands(ctx, block, "NUM1", "NUM2")?;
+                Ok(Expr::OperatorDivide(a, b))
+            }
+            "operator_random" => {
+                let (from, to) = Self::parse_operands(ctx, block, "FROM", "TO")?;
+                Ok(Expr::OperatorRandom(from, to))
+            }
+            "operator_join" => {
+                let (a, b) = Self::parse_operands(ctx, block, "STRING1", "STRING2")?;
+                Ok(Expr::OperatorJoin(a, b))
+            }
+            "operator_letter_of" => {
+                let (letter, string) = Self::parse_operands(ctx, block, "LETTER", "STRING")?;
+                Ok(Expr::OperatorLetterOf(letter, string))
+            }
+            "operator_length" => Ok(Expr::OperatorLength(Box::new(Self::parse_input(
+                ctx, block, "STRING",
             )?))),
-            "pen_clear" => Ok(Block::PenClear),
-            "control_forever" => Ok(Block::ControlForever(Box::new(Self::parse_input(
-                blocks,
-                background,
-                variable,
-                list,
-                &block.inputs["SUBSTACK"],
+            "operator_mod" => {
+                let (a, b) = Self::parse_operands(ctx, block, "NUM1", "NUM2")?;
+                Ok(Expr::OperatorMod(a, b))
+            }
+            "operator_round" => Ok(Expr::OperatorRound(Box::new(Self::parse_input(
+                ctx, block, "NUM",
             )?))),
-            "operator_add" => Ok(Block::OperatorAdd(
-                Box::new(Self::parse_input(
-                    blocks,
-                    background,
-                    variable,
-                    list,
-                    &block.inputs["NUM1"],
-                )?),
-                Box::new(Self::parse_input(
-                    blocks,
-                    background,
-                    variable,
-                    list,
-                    &block.inputs["NUM2"],
-                )?),
+            "operator_mathop" => {
+                let op = match Self::field(block, "OPERATOR")? {
+                    "abs" => MathOp::Abs,
+                    "floor" => MathOp::Floor,
+                    "ceiling" => MathOp::Ceiling,
+                    "sqrt" => MathOp::Sqrt,
+                    "sin" => MathOp::Sin,
+                    "cos" => MathOp::Cos,
+                    "tan" => MathOp::Tan,
+                    "asin" => MathOp::Asin,
+                    "acos" => MathOp::Acos,
+                    "atan" => MathOp::Atan,
+                    "ln" => MathOp::Ln,
+                    "log" => MathOp::Log,
+                    "e ^" => MathOp::PowE,
+                    "10 ^" => MathOp::Pow10,
+                    op => {
+                        let what = format!("math operator `{}`", op);
+                        return Self::unsupported_reporter(ctx, id, block, &what);
+                    }
+                };
+                Ok(Expr::OperatorMathOp(
+                    op,
+                    Box::new(Self::parse_input(ctx, block, "NUM")?),
+                ))
+            }
+            "motion_xposition" => Ok(Expr::MotionXPosition),
+            "motion_yposition" => Ok(Expr::MotionYPosition),
+            "motion_direction" => Ok(Expr::MotionDirection),
+            "motion_goto_menu" | "motion_glideto_menu" => Ok(Expr::LlTarget(
+                TargetMenu::from_scratch(Self::field(block, "TO")?),
+            )),
+            "control_create_clone_of_menu" => Ok(Expr::LlTarget(TargetMenu::from_scratch(
+                Self::field(block, "CLONE_OPTION")?,
+            ))),
+            "motion_pointtowards_menu" => Ok(Expr::LlTarget(TargetMenu::from_scratch(
+                Self::field(block, "TOWARDS")?,
+            ))),
+            "looks_costume" => Ok(Self::parse_costume_menu(
+                ctx.costumes,
+                Self::field(block, "COSTUME")?,
             )),
-            _ => {
-                println!(
-                    "{}: unsupported block opcode `{}` (rewrite -> {})",
-                    "warning".bright_yellow(),
-                    block.opcode,
-                    "0".bold().italic(),
+            "looks_backdrops" => Ok(Self::parse_costume_menu(
+                &ctx.background.costumes,
+                Self::field(block, "BACKDROP")?,
+            )),
+            "looks_costumenumbername" => Ok(Expr::LooksCostume(Self::parse_number_name(block)?)),
+            "looks_backdropnumbername" => Ok(Expr::LooksBackdrop(Self::parse_number_name(block)?)),
+            "looks_size" => Ok(Expr::LooksSize),
+            "sound_sounds_menu" => {
+                let name = Self::field(block, "SOUND_MENU")?;
+                match ctx.sounds.iter().position(|s| s.name == name) {
+                    Some(idx) => Ok(Expr::LlSound(idx)),
+                    // numbers pick a sound by position at runtime
+                    None => Ok(Expr::LlScratchValue(ScratchValue::Str(name.to_string()))),
+                }
+            }
+            "sound_volume" => Ok(Expr::SoundVolume),
+            "sensing_touchingobjectmenu" => Ok(Expr::LlTarget(TargetMenu::from_scratch(
+                Self::field(block, "TOUCHINGOBJECTMENU")?,
+            ))),
+            "sensing_distancetomenu" => Ok(Expr::LlTarget(TargetMenu::from_scratch(Self::field(
+                block,
+                "DISTANCETOMENU",
+            )?))),
+            "sensing_of_object_menu" => Ok(Expr::LlTarget(TargetMenu::from_scratch(Self::field(
+                block, "OBJECT",
+            )?))),
+            "sensing_distanceto" => Ok(Expr::SensingDistanceTo(Box::new(Self::parse_input(
+                ctx,
+                block,
+                "DISTANCETOMENU",
+            )?))),
+            "sensing_answer" => Ok(Expr::SensingAnswer),
+            "sensing_mousex" => Ok(Expr::SensingMouseX),
+            "sensing_mousey" => Ok(Expr::SensingMouseY),
+            "sensing_loudness" => Ok(Expr::SensingLoudness),
+            "sensing_timer" => Ok(Expr::SensingTimer),
+            "sensing_of" => Self::parse_sensing_of(ctx, id, block),
+            "sensing_current" => {
+                let menu = match Self::field(block, "CURRENTMENU")?.to_lowercase().as_str() {
+                    "year" => CurrentMenu::Year,
+                    "month" => CurrentMenu::Month,
+                    "date" => CurrentMenu::Date,
+                    "dayofweek" => CurrentMenu::DayOfWeek,
+                    "hour" => CurrentMenu::Hour,
+                    "minute" => CurrentMenu::Minute,
+                    "second" => CurrentMenu::Second,
+                    menu => {
+                        let what = format!("current time menu `{}`", menu);
+                        return Self::unsupported_reporter(ctx, id, block, &what);
+                    }
+                };
+                Ok(Expr::SensingCurrent(menu))
+            }
+            "sensing_dayssince2000" => Ok(Expr::SensingDaysSince2000),
+            "sensing_username" => Ok(Expr::SensingUsername),
+            "data_variable" => Ok(Expr::LlVar(Self::field_variable(ctx, block)?)),
+            "data_listcontents" => Ok(Expr::LlList(Self::field_list(ctx, block)?)),
+            "data_itemoflist" => Ok(Expr::DataItemOfList(
+                Self::field_list(ctx, block)?,
+                Box::new(Self::parse_list_index(ctx, block)?),
+            )),
+            "data_itemnumoflist" => Ok(Expr::DataItemNumOfList(
+                Self::field_list(ctx, block)?,
+                Box::new(Self::parse_input(ctx, block, "ITEM")?),
+            )),
+            "data_lengthoflist" => Ok(Expr::DataLengthOfList(Self::field_list(ctx, block)?)),
+            "pen_menu_colorParam" => {
+                let param = Self::field(block, "colorParam")?;
+                match PenColorParam::from_scratch(param) {
+                    Some(param) => Ok(Expr::LlPenColorParam(param)),
+                    None => {
+                        let what = format!("pen color param `{}`", param);
+                        Self::unsupported_reporter(ctx, id, block, &what)
+                    }
+                }
+            }
+            "music_menu_DRUM" => {
+                let drum = Self::field(block, "DRUM")?;
+                match Drum::from_scratch(drum) {
+                    Some(drum) => Ok(Expr::LlDrum(drum)),
+                    None => {
+                        let what = format!("drum `{}`", drum);
+                        Self::unsupported_reporter(ctx, id, block, &what)
+                    }
+                }
+            }
+            "music_menu_INSTRUMENT" => {
+                let instrument = Self::field(block, "INSTRUMENT")?;
+                match Instrument::from_scratch(instrument) {
+                    Some(instrument) => Ok(Expr::LlInstrument(instrument)),
+                    None => {
+                        let what = format!("instrument `{}`", instrument);
+                        Self::unsupported_reporter(ctx, id, block, &what)
+                    }
+                }
+            }
+            // the piano keyboard of `play note`
+            "note" => Ok(Expr::LlScratchValue(ScratchValue::Str(
+                Self::field(block, "NOTE")?.to_string(),
+            ))),
+            "music_getTempo" => Ok(Expr::MusicGetTempo),
+            "event_broadcast_menu" => {
+                let field = block
+                    .fields
+                    .get("BROADCAST_OPTION")
+                    .ok_or(ParseSb3Error::InvaildSb3InputFormat)?;
+                Ok(Expr::LlBroadcast(Self::lookup_broadcast(
+                    ctx,
+                    &field.0,
+                    field.1.as_deref(),
+                )?))
+            }
+            "sensing_keyoptions" => match KeyId::from_scratch(Self::field(block, "KEY_OPTION")?) {
+                Some(key) => Ok(Expr::LlKey(key)),
+                None => Ok(Expr::LlScratchValue(ScratchValue::Str(String::new()))),
+            },
+            "argument_reporter_string_number" => match Self::lookup_arg(ctx, block)? {
+                Some(path) => Ok(Expr::ProcArg(path)),
+                // used outside of its definion
+                None => Ok(Expr::LlScratchValue(ScratchValue::Num(0.0))),
+            },
+            opcode => {
+                let what = format!("block opcode `{}`", opcode);
+                Self::unsupported_reporter(ctx, id, block, &what)
+            }
+        }
+    }
+
+    // `None` if the block is not a boolean reporter
+    fn parse_bool_reporter(
+        ctx: &TargetContext,
+        block: &Sb3Block,
+    ) -> Result<Option<BoolExpr>, ParseSb3Error> {
+        let cond = match block.opcode.as_str() {
+            "operator_lt" => BoolExpr::OperatorLt(
+                Self::parse_input(ctx, block, "OPERAND1")?,
+                Self::parse_input(ctx, block, "OPERAND2")?,
+            ),
+            "operator_gt" => BoolExpr::OperatorGt(
+                Self::parse_input(ctx, block, "OPERAND1")?,
+                Self::parse_input(ctx, block, "OPERAND2")?,
+            ),
+            "operator_equals" => BoolExpr::OperatorEquals(
+                Self::parse_input(ctx, block, "OPERAND1")?,
+                Self::parse_input(ctx, block, "OPERAND2")?,
+            ),
+            "operator_and" => BoolExpr::OperatorAnd(
+                Box::new(Self::parse_bool(ctx, block.inputs.get("OPERAND1"))?),
+                Box::new(Self::parse_bool(ctx, block.inputs.get("OPERAND2"))?),
+            ),
+            "operator_or" => BoolExpr::OperatorOr(
+                Box::new(Self::parse_bool(ctx, block.inputs.get("OPERAND1"))?),
+                Box::new(Self::parse_bool(ctx, block.inputs.get("OPERAND2"))?),
+            ),
+            "operator_not" => BoolExpr::OperatorNot(Box::new(Self::parse_bool(
+                ctx,
+                block.inputs.get("OPERAND"),
+            )?)),
+            "operator_contains" => BoolExpr::OperatorContains(
+                Self::parse_input(ctx, block, "STRING1")?,
+                Self::parse_input(ctx, block, "STRING2")?,
+            ),
+            "sensing_touchingobject" => BoolExpr::SensingTouchingObject(Self::parse_input(
+                ctx,
+                block,
+                "TOUCHINGOBJECTMENU",
+            )?),
+            "sensing_touchingcolor" => {
+                BoolExpr::SensingTouchingColor(Self::parse_input(ctx, block, "COLOR")?)
+            }
+            "sensing_coloristouchingcolor" => BoolExpr::SensingColorIsTouchingColor(
+                Self::parse_input(ctx, block, "COLOR")?,
+                Self::parse_input(ctx, block, "COLOR2")?,
+            ),
+            "sensing_mousedown" => BoolExpr::SensingMouseDown,
+            "data_listcontainsitem" => BoolExpr::DataListContainsItem(
+                Self::field_list(ctx, block)?,
+                Self::parse_input(ctx, block, "ITEM")?,
+            ),
+            "sensing_keypressed" => {
+                BoolExpr::SensingKeyPressed(Self::parse_input(ctx, block, "KEY_OPTION")?)
+            }
+            "argument_reporter_boolean" => match Self::lookup_arg(ctx, block)? {
+                Some(path) => BoolExpr::ProcArg(path),
+                None => BoolExpr::LlBool(false),
+            },
+            _ => return Ok(None),
+        };
+        Ok(Some(cond))
+    }
+
+    // variables are resolved by name against the target picked in the menu, which has to
+    // be a literal since the variables of a computed one are unknown until runtime
+    fn parse_sensing_of(
+        ctx: &TargetContext,
+        id: &str,
+        block: &Sb3Block,
+    ) -> Result<Expr, ParseSb3Error> {
+        let object = Self::parse_input(ctx, block, "OBJECT")?;
+        let property = match Self::field(block, "PROPERTY")? {
+            "x position" => SensingOfProperty::XPosition,
+            "y position" => SensingOfProperty::YPosition,
+            "direction" => SensingOfProperty::Direction,
+            "costume #" => SensingOfProperty::CostumeNumber,
+            "costume name" => SensingOfProperty::CostumeName,
+            "size" => SensingOfProperty::Size,
+            "volume" => SensingOfProperty::Volume,
+            "backdrop #" | "background #" => SensingOfProperty::BackdropNumber,
+            "backdrop name" => SensingOfProperty::BackdropName,
+            name => {
+                let variables = match &object {
+                    Expr::LlTarget(TargetMenu::Stage) => Some(&ctx.background.variables),
+                    Expr::LlTarget(TargetMenu::Sprite(sprite)) => ctx
+                        .sprites
+                        .iter()
+                        .find(|s| s.name == *sprite)
+                        .map(|s| &s.variables),
+                    _ => None,
+                };
+                let var =
+                    variables.and_then(|vars| vars.values().find(|v| v.0.name().as_str() == name));
+                match (var, variables) {
+                    (Some(var), _) => SensingOfProperty::Variable(var.0.clone()),
+                    // the object is computed, scratch-vm looks the variable up when it runs
+                    (None, None) => SensingOfProperty::VariableName(name.to_string()),
+                    (None, Some(_)) => {
+                        let message = format!(
+                            "cannot resolve variable `{}` of `sensing_of`, looked up at runtime",
+                            name
+                        );
+                        Self::warn(ctx, id, message);
+                        SensingOfProperty::VariableName(name.to_string())
+                    }
+                }
+            }
+        };
+        Ok(Expr::SensingOf(property, Box::new(object)))
+    }
+
+    // names are resolved here, anything else is left to the runtime like scratch-vm does
+    fn parse_costume_menu(costumes: &[Costume], name: &str) -> Expr {
+        if let Some(idx) = costumes.iter().position(|c| c.name == name) {
+            return Expr::LlCostume(CostumeMenu::Costume(idx));
+        }
+        match name {
+            "next backdrop" => Expr::LlCostume(CostumeMenu::Next),
+            "previous backdrop" => Expr::LlCostume(CostumeMenu::Previous),
+            "random backdrop" => Expr::LlCostume(CostumeMenu::Random),
+            name => Expr::LlScratchValue(ScratchValue::Str(name.to_string())),
+        }
+    }
+
+    fn parse_number_name(block: &Sb3Block) -> Result<NumberName, ParseSb3Error> {
+        match Self::field(block, "NUMBER_NAME")? {
+            "name" => Ok(NumberName::Name),
+            _ => Ok(NumberName::Number),
+        }
+    }
+
+    // `None` for an effect the parser does not know
+    fn parse_effect(block: &Sb3Block) -> Result<Option<GraphicEffect>, ParseSb3Error> {
+        match Self::field(block, "EFFECT")?.to_lowercase().as_str() {
+            "color" => Ok(Some(GraphicEffect::Color)),
+            "fisheye" => Ok(Some(GraphicEffect::Fisheye)),
+            "whirl" => Ok(Some(GraphicEffect::Whirl)),
+            "pixelate" => Ok(Some(GraphicEffect::Pixelate)),
+            "mosaic" => Ok(Some(GraphicEffect::Mosaic)),
+            "brightness" => Ok(Some(GraphicEffect::Brightness)),
+            "ghost" => Ok(Some(GraphicEffect::Ghost)),
+            _ => Ok(None),
+        }
+    }
+
+    // `None` for an effect the parser does not know
+    fn parse_sound_effect(block: &Sb3Block) -> Result<Option<SoundEffect>, ParseSb3Error> {
+        match Self::field(block, "EFFECT")?.to_lowercase().as_str() {
+            "pitch" => Ok(Some(SoundEffect::Pitch)),
+            "pan" => Ok(Some(SoundEffect::Pan)),
+            _ => Ok(None),
+        }
+    }
+
+    fn lookup_arg(
+        ctx: &TargetContext,
+        block: &Sb3Block,
+    ) -> Result<Option<ResourcePath>, ParseSb3Error> {
+        let name = Self::field(block, "VALUE")?;
+        Ok(ctx
+            .args
+            .and_then(|def| def.args.iter().find(|arg| **arg.path.name() == name))
+            .map(|arg| arg.path.clone()))
+    }
+
+    fn parse_call(
+        ctx: &TargetContext,
+        id: &str,
+        block: &Sb3Block,
+    ) -> Result<Option<Stmt>, ParseSb3Error> {
+        let mutation = block
+            .mutation
+            .as_ref()
+            .ok_or(ParseSb3Error::InvaildSb3InputFormat)?;
+        let definion = match mutation
+            .proccode
+            .as_ref()
+            .and_then(|proccode| ctx.definions.get(proccode))
+        {
+            Some(definion) => definion,
+            // calling an undefined block does nothing
+            None => {
+                let proccode = mutation.proccode.as_deref().unwrap_or_default();
+                Self::warn(
+                    ctx,
+                    id,
+                    format!("call to undefined custom block `{}`", proccode),
                 );
-                Ok(Block::LlScratchValue(ScratchValue::Num(0.0)))
-            } // placeholder
+                return Ok(None);
+            }
+        };
+        let mut args = Vec::with_capacity(definion.args.len());
+        for (idx, arg) in definion.args.iter().enumerate() {
+            let input = mutation
+                .argument_ids
+                .get(idx)
+                .and_then(|id| block.inputs.get(id));
+            args.push(match (input, arg.kind) {
+                (input, DefArgKind::Bool) => Expr::Bool(Box::new(Self::parse_bool(ctx, input)?)),
+                (Some(input), DefArgKind::StrNum) => Self::parse_expr(ctx, input)?,
+                (None, DefArgKind::StrNum) => {
+                    Expr::LlScratchValue(ScratchValue::Str(String::new()))
+                }
+            });
         }
+        Ok(Some(Stmt::ProcCall(definion.path.clone(), args)))
     }
 
+    fn block<'b>(blocks: &'b Blocks, id: &str) -> Result<&'b Sb3Block, ParseSb3Error> {
+        blocks
+            .blocks
+            .get(id)
+            .ok_or_else(|| ParseSb3Error::CannotResolveBlock(id.to_string()))
+    }
+
+    fn field<'b>(block: &'b Sb3Block, name: &str) -> Result<&'b str, ParseSb3Error> {
+        match block.fields.get(name) {
+            Some(field) => Ok(&field.0),
+            None => Err(ParseSb3Error::InvaildSb3InputFormat),
+        }
+    }
+
+    // both inputs of a binary operator, missing ones are empty strings
+    fn parse_operands(
+        ctx: &TargetContext,
+        block: &Sb3Block,
+        a: &str,
+        b: &str,
+    ) -> Result<(Box<Expr>, Box<Expr>), ParseSb3Error> {
+        Ok((
+            Box::new(Self::parse_input(ctx, block, a)?),
+            Box::new(Self::parse_input(ctx, block, b)?),
+        ))
+    }
+
+    // a missing input reads as an empty string, like an empty text field
     fn parse_input(
-        blocks: &Blocks,
-        background: &Background,
-        variable: &HashMap<String, Variable>,
-        list: &HashMap<String, List>,
-        input: &Input,
-    ) -> Result<Block, ParseSb3Error> {
+        ctx: &TargetContext,
+        block: &Sb3Block,
+        name: &str,
+    ) -> Result<Expr, ParseSb3Error> {
+        match block.inputs.get(name) {
+            Some(input) => Self::parse_expr(ctx, input),
+            None => Ok(Expr::LlScratchValue(ScratchValue::Str(String::new()))),
+        }
+    }
+
+    fn parse_expr(ctx: &TargetContext, input: &Input) -> Result<Expr, ParseSb3Error> {
         match input {
-            Input::NoHidden(_, real) | Input::Hidden(_, real, _) => match real {
-                InputType::NumOrStr(4..=10, val) => Ok(Block::LlScratchValue(val.clone())),
-                InputType::BroadcastOrVarOrList(11, _, id) => Ok(Block::LlBroadcast(
-                    background.broadcasts[id.as_str()].clone(),
-                )),
-                InputType::BroadcastOrVarOrList(12, name, id)
-                | InputType::TopVarOrList(12, name, id, _, _) => Ok(Block::LlVar(
-                    Self::lookup_variable(background, variable, name, id)?,
-                )),
-                InputType::BroadcastOrVarOrList(13, name, id)
-                | InputType::TopVarOrList(13, name, id, _, _) => Ok(Block::LlList(
-                    Self::lookup_list(background, list, name, id)?,
-                )),
-                InputType::Block(id) => Ok(Block::BlockStack(Self::parse_block_stack_from_here(
-                    background,
-                    variable,
-                    list,
-                    blocks,
-                    &blocks.blocks[id.as_str()],
-                )?)),
-                _ => Err(ParseSb3Error::InvaildSb3InputFormat),
-            },
+            // the reporter covering the shadow was removed, the shadow shows again
+            Input::Hidden(_, InputType::Empty, shadow) => Self::parse_input_type(ctx, shadow),
+            Input::NoHidden(_, real) | Input::Hidden(_, real, _) => {
+                Self::parse_input_type(ctx, real)
+            }
+        }
+    }
+
+    fn parse_input_type(ctx: &TargetContext, input: &InputType) -> Result<Expr, ParseSb3Error> {
+        match input {
+            InputType::NumOrStr(4..=10, val) => Ok(Expr::LlScratchValue(val.clone())),
+            InputType::BroadcastOrVarOrList(11, name, id) => Ok(Expr::LlBroadcast(
+                Self::lookup_broadcast(ctx, name, Some(id))?,
+            )),
+            InputType::BroadcastOrVarOrList(12, name, id)
+            | InputType::TopVarOrList(12, name, id, _, _) => {
+                Ok(Expr::LlVar(Self::lookup_variable(ctx, name, id)?))
+            }
+            InputType::BroadcastOrVarOrList(13, name, id)
+            | InputType::TopVarOrList(13, name, id, _, _) => {
+                Ok(Expr::LlList(Self::lookup_list(ctx, name, id)?))
+            }
+            InputType::Block(id) => Self::parse_reporter(ctx, id, Self::block(ctx.blocks, id)?),
+            InputType::Empty => Ok(Expr::LlScratchValue(ScratchValue::Str(String::new()))),
+            _ => Err(ParseSb3Error::InvaildSb3InputFormat),
+        }
+    }
+
+    // boolean inputs have no shadow, so they may be missing entirely
+    fn parse_bool(ctx: &TargetContext, input: Option<&Input>) -> Result<BoolExpr, ParseSb3Error> {
+        match input {
+            Some(Input::NoHidden(_, InputType::Block(id)))
+            | Some(Input::Hidden(_, InputType::Block(id), _)) => {
+                let block = Self::block(ctx.blocks, id)?;
+                match Self::parse_bool_reporter(ctx, block)? {
+                    Some(cond) => Ok(cond),
+                    None => Ok(BoolExpr::Cast(Box::new(Self::parse_reporter(
+                        ctx, id, block,
+                    )?))),
+                }
+            }
+            Some(Input::NoHidden(_, InputType::Empty)) | None => Ok(BoolExpr::Empty),
+            Some(input) => Ok(BoolExpr::Cast(Box::new(Self::parse_expr(ctx, input)?))),
+        }
+    }
+
+    // `last`, `random` and `all` typed into the index, computed ones are left to the runtime
+    fn parse_list_index(ctx: &TargetContext, block: &Sb3Block) -> Result<Expr, ParseSb3Error> {
+        let index = Self::parse_input(ctx, block, "INDEX")?;
+        if let Expr::LlScratchValue(ScratchValue::Str(val)) = &index {
+            if let Some(index) = ListIndex::from_scratch(val) {
+                return Ok(Expr::LlListIndex(index));
+            }
         }
+        Ok(index)
+    }
+
+    // variable and list fields are `[name, id]`
+    fn data_field<'b>(
+        block: &'b Sb3Block,
+        name: &str,
+    ) -> Result<(&'b String, &'b str), ParseSb3Error> {
+        let field = block
+            .fields
+            .get(name)
+            .ok_or(ParseSb3Error::InvaildSb3InputFormat)?;
+        let id = field
+            .1
+            .as_deref()
+            .ok_or(ParseSb3Error::InvaildSb3InputFormat)?;
+        Ok((&field.0, id))
+    }
+
+    fn field_variable(
+        ctx: &TargetContext,
+        block: &Sb3Block,
+    ) -> Result<ResourcePath, ParseSb3Error> {
+        let (name, id) = Self::data_field(block, "VARIABLE")?;
+        Self::lookup_variable(ctx, name, id)
+    }
+
+    fn field_list(ctx: &TargetContext, block: &Sb3Block) -> Result<ResourcePath, ParseSb3Error> {
+        let (name, id) = Self::data_field(block, "LIST")?;
+        Self::lookup_list(ctx, name, id)
     }
 
     fn lookup_variable(
-        background: &Background,
-        variable: &HashMap<String, Variable>,
-        name: &String,
+        ctx: &TargetContext,
+        name: &str,
         id: &str,
     ) -> Result<ResourcePath, ParseSb3Error> {
-        if let Some(var) = variable.get(id) {
+        if let Some(var) = ctx.variable.get(id) {
             return Ok(var.0.clone());
         }
-        if let Some(var) = background.variables.get(id) {
+        if let Some(var) = ctx.background.variables.get(id) {
             return Ok(var.0.clone());
         }
-        Err(ParseSb3Error::CannotResolveVariableOrList(name.clone()))
+        Err(ParseSb3Error::CannotResolveVariableOrList(name.to_string()))
+    }
+
+    // all broadcasts live on the stage, older projects only get the name right
+    fn lookup_broadcast(
+        ctx: &TargetContext,
+        name: &str,
+        id: Option<&str>,
+    ) -> Result<ResourcePath, ParseSb3Error> {
+        if let Some(broadcast) = id.and_then(|id| ctx.background.broadcasts.get(id)) {
+            return Ok(broadcast.clone());
+        }
+        match ctx
+            .background
+            .broadcasts
+            .values()
+            .find(|broadcast| broadcast.name().eq_ignore_ascii_case(name))
+        {
+            Some(broadcast) => Ok(broadcast.clone()),
+            None => Err(ParseSb3Error::CannotResolveBroadcast(name.to_string())),
+        }
     }
 
     fn lookup_list(
-        background: &Background,
-        list: &HashMap<String, List>,
-        name: &String,
+        ctx: &TargetContext,
+        name: &str,
         id: &str,
     ) -> Result<ResourcePath, ParseSb3Error> {
-        if let Some(lst) = list.get(id) {
+        if let Some(lst) = ctx.list.get(id) {
             return Ok(lst.0.clone());
         }
-        if let Some(lst) = background.lists.get(id) {
+        if let Some(lst) = ctx.background.lists.get(id) {
             return Ok(lst.0.clone());
         }
-        Err(ParseSb3Error::CannotResolveVariableOrList(name.clone()))
+        Err(ParseSb3Error::CannotResolveVariableOrList(name.to_string()))
     }
 }
+
+// everything the blocks of one target can refer to
+#[derive(Clone, Copy)]
+struct TargetContext<'a> {
+    background: &'a Background,
+    // every sprite, their blocks may not be parsed yet
+    sprites: &'a [Sprite],
+    // extensions the project declares
+    extensions: &'a [String],
+    variable: &'a HashMap<String, Variable>,
+    list: &'a HashMap<String, List>,
+    costumes: &'a [Costume],
+    sounds: &'a [Sound],
+    blocks: &'a Blocks,
+    definions: &'a HashMap<String, Definion>,
+    // the definion whose body is being parsed
+    args: Option<&'a Definion>,
+    // name of the target, for warnings
+    target: &'a str,
+    observer: &'a dyn Observer,
+    unsupported: UnsupportedPolicy,
+}
@@ -0,0 +1,98 @@
+// every test binary uses its own part of these helpers
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use scratch_loader::{
+    cast,
+    sb3::{Project, Sb3File},
+};
+use scratch_parser::{
+    ast::{BlockItem, Expr, ParsedScratchProject, Stmt},
+    parser::{ParseSb3Error, Sb3FormatParser, UnsupportedPolicy},
+};
+use serde_json::json;
+
+// parses `blocks` as the scripts of an otherwise empty stage
+pub fn parse(blocks: serde_json::Value) -> Vec<BlockItem> {
+    try_parse(blocks).unwrap()
+}
+
+pub fn try_parse(blocks: serde_json::Value) -> Result<Vec<BlockItem>, ParseSb3Error> {
+    try_parse_with(blocks, UnsupportedPolicy::default())
+}
+
+pub fn try_parse_with(
+    blocks: serde_json::Value,
+    unsupported: UnsupportedPolicy,
+) -> Result<Vec<BlockItem>, ParseSb3Error> {
+    let project: Project = serde_json::from_value(json!({
+        "targets": [{
+            "isStage": true,
+            "name": "Stage",
+            "blocks": blocks,
+            "costumes": [],
+            "currentCostume": 0,
+            "sounds": [],
+            "volume": 100,
+            "layerOrder": 0
+        }],
+        "meta": { "semver": "3.0.0" }
+    }))
+    .unwrap();
+    let src = Sb3File {
+        resources: HashMap::new(),
+        project,
+    };
+    let parser = Sb3FormatParser::new(src).unsupported(unsupported);
+    Ok(parser.parse()?.background.blocks)
+}
+
+// `tests/fixtures/<name>.json`, assets are left out
+pub fn fixture(name: &str) -> ParsedScratchProject {
+    try_fixture_with(name, UnsupportedPolicy::default()).unwrap()
+}
+
+pub fn try_fixture_with(
+    name: &str,
+    unsupported: UnsupportedPolicy,
+) -> Result<ParsedScratchProject, ParseSb3Error> {
+    let path = format!(
+        "{}/tests/fixtures/{}.json",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    let project: Project = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+    let src = Sb3File {
+        resources: HashMap::new(),
+        project,
+    };
+    Sb3FormatParser::new(src).unsupported(unsupported).parse()
+}
+
+// the body of the only green flag script of a sprite
+pub fn flag_script<'a>(proj: &'a ParsedScratchProject, sprite: &str) -> &'a [Stmt] {
+    let sprite = proj.sprites.iter().find(|s| s.name == sprite).unwrap();
+    match sprite.blocks.as_slice() {
+        [BlockItem::EvWhenGreenFlagClicked(bs)] => bs.blocks(),
+        items => panic!("unexpected items {:?}", items),
+    }
+}
+
+// the green flag script of a sprite that has other scripts too
+pub fn find_flag_script<'a>(proj: &'a ParsedScratchProject, sprite: &str) -> &'a [Stmt] {
+    let sprite = proj.sprites.iter().find(|s| s.name == sprite).unwrap();
+    sprite
+        .blocks
+        .iter()
+        .find_map(|item| match item {
+            BlockItem::EvWhenGreenFlagClicked(bs) => Some(bs.blocks()),
+            _ => None,
+        })
+        .unwrap()
+}
+
+// a literal input holding `val`
+pub fn is_lit(expr: &Expr, val: &str) -> bool {
+    matches!(expr, Expr::LlScratchValue(v) if cast::to_string(v) == val)
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::{find_flag_script, fixture, is_lit};
+use scratch_parser::ast::{BlockItem, BoolExpr, Expr, Stmt, StopOption, TargetMenu};
+
+#[test]
+fn loops() {
+    let proj = fixture("control");
+    let stmts = find_flag_script(&proj, "Sprite1");
+    match &stmts[0] {
+        Stmt::ControlRepeat(times, body) => {
+            assert!(is_lit(times, "3"));
+            assert!(matches!(body.blocks(), [Stmt::MotionChangeX(_)]));
+        }
+        stmt => panic!("unexpected {:?}", stmt),
+    }
+    let counter = &proj.sprites[0].variables["vid"].0;
+    match &stmts[1] {
+        Stmt::ControlForEach(var, value, body) => {
+            assert_eq!(var, counter);
+            assert!(is_lit(value, "4"));
+            assert!(matches!(
+                body.blocks(),
+                [Stmt::MotionChangeY(Expr::LlVar(v))] if v == counter
+            ));
+        }
+        stmt => panic!("unexpected {:?}", stmt),
+    }
+    assert!(matches!(
+        &stmts[4],
+        Stmt::ControlRepeatUntil(BoolExpr::SensingKeyPressed(_), body) if body.blocks().is_empty()
+    ));
+    // an empty condition, false at runtime
+    assert!(matches!(
+        &stmts[5],
+        Stmt::ControlWhile(BoolExpr::Empty, body) if body.blocks().len() == 1
+    ));
+}
+
+#[test]
+fn conditionals() {
+    let proj = fixture("control");
+    let stmts = find_flag_script(&proj, "Sprite1");
+    match &stmts[2] {
+        Stmt::ControlIfElse(BoolExpr::SensingKeyPressed(_), body, else_body) => {
+            assert!(matches!(body.blocks(), [Stmt::MotionSetX(_)]));
+            assert!(matches!(else_body.blocks(), [Stmt::MotionChangeX(_)]));
+        }
+        stmt => panic!("unexpected {:?}", stmt),
+    }
+    // a missing substack is an empty one
+    assert!(matches!(
+        &stmts[3],
+        Stmt::ControlIf(_, body) if body.blocks().is_empty()
+    ));
+}
+
+#[test]
+fn waits() {
+    let proj = fixture("control");
+    let stmts = find_flag_script(&proj, "Sprite1");
+    assert!(matches!(&stmts[8], Stmt::ControlWait(secs) if is_lit(secs, "0")));
+    assert!(matches!(
+        &stmts[9],
+        Stmt::ControlWaitUntil(BoolExpr::SensingKeyPressed(_))
+    ));
+}
+
+#[test]
+fn stop() {
+    let proj = fixture("control");
+    let stmts = find_flag_script(&proj, "Sprite1");
+    assert!(matches!(
+        stmts.last(),
+        Some(Stmt::ControlStop(StopOption::ThisScript))
+    ));
+    let other = proj.sprites[0].blocks.iter().any(|i| {
+        matches!(i, BlockItem::EvWhenKeyPressed(_, bs)
+            if matches!(bs.blocks(), [Stmt::ControlStop(StopOption::OtherScriptsInSprite)]))
+    });
+    assert!(other);
+    assert!(matches!(
+        proj.background.blocks.as_slice(),
+        [BlockItem::EvWhenStageClicked(bs)]
+            if matches!(bs.blocks(), [Stmt::ControlStop(StopOption::All)])
+    ));
+}
+
+#[test]
+fn clones() {
+    let proj = fixture("control");
+    let stmts = find_flag_script(&proj, "Sprite1");
+    assert!(matches!(
+        &stmts[6],
+        Stmt::ControlCreateClone(Expr::LlTarget(TargetMenu::Myself))
+    ));
+    assert!(matches!(
+        &stmts[7],
+        Stmt::ControlCreateClone(Expr::LlTarget(TargetMenu::Sprite(name))) if name == "Sprite2"
+    ));
+    let clone_script = proj.sprites[0]
+        .blocks
+        .iter()
+        .find_map(|i| match i {
+            BlockItem::ControlStartAsClone(bs) => Some(bs.blocks()),
+            _ => None,
+        })
+        .unwrap();
+    assert!(matches!(clone_script[1], Stmt::ControlDeleteThisClone));
+}
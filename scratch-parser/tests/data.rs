@@ -0,0 +1,130 @@
+mod common;
+
+use common::{fixture, flag_script, is_lit};
+use scratch_parser::ast::{BoolExpr, Expr, ListIndex, ParsedScratchProject, ResourcePath, Stmt};
+
+struct Paths {
+    n: ResourcePath,
+    score: ResourcePath,
+    items: ResourcePath,
+    letters: ResourcePath,
+}
+
+fn paths(proj: &ParsedScratchProject) -> Paths {
+    let sprite = &proj.sprites[0];
+    Paths {
+        n: sprite.variables["vid"].0.clone(),
+        score: proj.background.variables["sid"].0.clone(),
+        items: sprite.lists["lid"].0.clone(),
+        letters: proj.background.lists["gid"].0.clone(),
+    }
+}
+
+fn said(proj: &ParsedScratchProject) -> Vec<&Expr> {
+    flag_script(proj, "Sprite1")
+        .iter()
+        .filter_map(|s| match s {
+            Stmt::LooksSay(expr) => Some(expr),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn variables() {
+    let proj = fixture("data");
+    let p = paths(&proj);
+    let stmts = flag_script(&proj, "Sprite1");
+    assert!(
+        matches!(&stmts[0], Stmt::DataSetVariable(v, value) if *v == p.n && is_lit(value, "5"))
+    );
+    assert!(matches!(&stmts[1], Stmt::DataChangeVariable(v, by) if *v == p.n && is_lit(by, "2")));
+    assert!(matches!(&stmts[2], Stmt::DataShowVariable(v) if *v == p.n));
+    assert!(matches!(&stmts[3], Stmt::DataHideVariable(v) if *v == p.score));
+    let said = said(&proj);
+    // the `variable` reporter and a variable dropped into an input are the same
+    assert!(said
+        .iter()
+        .any(|e| matches!(e, Expr::LlVar(v) if *v == p.n)));
+    assert!(said
+        .iter()
+        .any(|e| matches!(e, Expr::LlVar(v) if *v == p.score)));
+}
+
+#[test]
+fn list_stmts() {
+    let proj = fixture("data");
+    let p = paths(&proj);
+    let stmts = flag_script(&proj, "Sprite1");
+    assert!(
+        matches!(&stmts[4], Stmt::DataAddToList(l, item) if *l == p.items && is_lit(item, "a"))
+    );
+    assert!(matches!(
+        &stmts[8],
+        Stmt::DataInsertAtList(l, index, item)
+            if *l == p.items && is_lit(index, "1") && is_lit(item, "x")
+    ));
+    assert!(matches!(
+        &stmts[10],
+        Stmt::DataReplaceItemOfList(l, index, item)
+            if *l == p.items && is_lit(index, "2") && is_lit(item, "z")
+    ));
+    // a computed index is left to the runtime
+    assert!(matches!(
+        &stmts[11],
+        Stmt::DataDeleteOfList(_, Expr::OperatorJoin(_, _))
+    ));
+    assert!(matches!(&stmts[12], Stmt::DataDeleteAllOfList(l) if *l == p.letters));
+    assert!(matches!(&stmts[15], Stmt::DataShowList(l) if *l == p.items));
+    assert!(matches!(&stmts[16], Stmt::DataHideList(l) if *l == p.letters));
+}
+
+#[test]
+fn special_indices() {
+    let proj = fixture("data");
+    let stmts = flag_script(&proj, "Sprite1");
+    assert!(matches!(
+        &stmts[7],
+        Stmt::DataDeleteOfList(_, Expr::LlListIndex(ListIndex::Last))
+    ));
+    assert!(matches!(
+        &stmts[9],
+        Stmt::DataInsertAtList(_, Expr::LlListIndex(ListIndex::Last), _)
+    ));
+    assert!(matches!(
+        &stmts[stmts.len() - 2],
+        Stmt::DataDeleteOfList(_, Expr::LlListIndex(ListIndex::All))
+    ));
+    assert!(said(&proj).iter().any(|e| matches!(
+        e,
+        Expr::DataItemOfList(_, index) if matches!(**index, Expr::LlListIndex(ListIndex::Random))
+    )));
+}
+
+#[test]
+fn list_reporters() {
+    let proj = fixture("data");
+    let p = paths(&proj);
+    let said = said(&proj);
+    assert!(said.iter().any(|e| matches!(
+        e,
+        Expr::DataItemOfList(l, index) if *l == p.items && is_lit(index, "1")
+    )));
+    assert!(said.iter().any(|e| matches!(
+        e,
+        Expr::DataItemNumOfList(l, item) if *l == p.items && is_lit(item, "z")
+    )));
+    assert!(said
+        .iter()
+        .any(|e| matches!(e, Expr::DataLengthOfList(l) if *l == p.items)));
+    assert!(said.iter().any(|e| matches!(
+        e,
+        Expr::Bool(b) if matches!(&**b, BoolExpr::DataListContainsItem(l, item) if *l == p.items && is_lit(item, "BEE"))
+    )));
+    assert!(said
+        .iter()
+        .any(|e| matches!(e, Expr::LlList(l) if *l == p.items)));
+    assert!(said
+        .iter()
+        .any(|e| matches!(e, Expr::LlList(l) if *l == p.letters)));
+}
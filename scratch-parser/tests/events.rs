@@ -0,0 +1,88 @@
+mod common;
+
+use common::{find_flag_script, fixture, is_lit};
+use scratch_parser::ast::{BlockItem, Expr, GreaterThanMenu, KeyId, Stmt};
+
+fn count(items: &[BlockItem], pred: impl Fn(&BlockItem) -> bool) -> usize {
+    items.iter().filter(|i| pred(i)).count()
+}
+
+#[test]
+fn hats() {
+    let proj = fixture("events");
+    let items = &proj.sprites[0].blocks;
+    // the hatless `move` script and the unknown `pen_whenSomething` hat are dropped
+    assert_eq!(items.len(), 8);
+    assert_eq!(
+        count(items, |i| matches!(
+            i,
+            BlockItem::EvWhenThisSpriteClicked(_)
+        )),
+        1
+    );
+    assert_eq!(
+        count(items, |i| matches!(i, BlockItem::ControlStartAsClone(_))),
+        1
+    );
+    assert_eq!(
+        count(
+            items,
+            |i| matches!(i, BlockItem::EvWhenKeyPressed(KeyId::Space, bs) if bs.blocks().len() == 1)
+        ),
+        1
+    );
+    assert_eq!(
+        count(
+            items,
+            |i| matches!(i, BlockItem::EvWhenBackdropSwitchesTo(b, _) if b == "stage")
+        ),
+        1
+    );
+    assert_eq!(
+        count(items, |i| matches!(
+            i,
+            BlockItem::EvWhenGreaterThan(GreaterThanMenu::Timer, value, _) if is_lit(value, "0.05")
+        )),
+        1
+    );
+    assert_eq!(
+        count(items, |i| matches!(
+            i,
+            BlockItem::EvWhenGreaterThan(GreaterThanMenu::Loudness, value, bs)
+                if is_lit(value, "10") && bs.blocks().is_empty()
+        )),
+        1
+    );
+    assert_eq!(
+        count(&proj.background.blocks, |i| matches!(
+            i,
+            BlockItem::EvWhenStageClicked(_)
+        )),
+        1
+    );
+}
+
+#[test]
+fn receive() {
+    let proj = fixture("events");
+    let message = &proj.background.broadcasts["bid"];
+    // the stage hat names the broadcast `Message1`, resolved by its id
+    let receiving =
+        |i: &BlockItem| matches!(i, BlockItem::EWhenRecieveBroadcast(b, _) if b == message);
+    assert_eq!(count(&proj.sprites[0].blocks, receiving), 1);
+    assert_eq!(count(&proj.background.blocks, receiving), 1);
+}
+
+#[test]
+fn broadcast() {
+    let proj = fixture("events");
+    let message = &proj.background.broadcasts["bid"];
+    let stmts = find_flag_script(&proj, "Sprite1");
+    assert!(matches!(&stmts[0], Stmt::EventBroadcast(Expr::LlBroadcast(b)) if b == message));
+    assert!(matches!(
+        &stmts[1],
+        Stmt::EventBroadcastAndWait(Expr::LooksSize)
+    ));
+    // a menu with an unknown id is resolved by its name, ignoring case
+    assert!(matches!(&stmts[2], Stmt::EventBroadcast(Expr::LlBroadcast(b)) if b == message));
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::parse;
+use scratch_parser::ast::{BlockItem, BoolExpr, Expr, KeyId, Stmt};
+use serde_json::json;
+
+#[test]
+fn key_names() {
+    let cases = [
+        ("space", KeyId::Space),
+        ("left arrow", KeyId::Left),
+        ("up arrow", KeyId::Up),
+        ("right arrow", KeyId::Right),
+        ("down arrow", KeyId::Down),
+        ("any", KeyId::Any),
+        ("enter", KeyId::Enter),
+        ("page down", KeyId::PageDown),
+        ("a", KeyId::Letter('A')),
+        ("Z", KeyId::Letter('Z')),
+        ("7", KeyId::Digit(7)),
+        ("-", KeyId::Symbol('-')),
+        (" ", KeyId::Space),
+        // scratch only looks at the first character of unknown names
+        ("apple", KeyId::Letter('A')),
+    ];
+    for (name, key) in cases {
+        assert_eq!(KeyId::from_scratch(name), Some(key), "{:?}", name);
+    }
+    assert_eq!(KeyId::from_scratch(""), None);
+    assert_eq!(KeyId::Left.name(), "left arrow");
+    assert_eq!(KeyId::Letter('Q').name(), "Q");
+    assert_eq!(KeyId::Digit(0).name(), "0");
+}
+
+#[test]
+fn when_key_pressed() {
+    let items = parse(json!({
+        "hat": {
+            "opcode": "event_whenkeypressed",
+            "next": null,
+            "parent": null,
+            "inputs": {},
+            "fields": { "KEY_OPTION": ["right arrow", null] },
+            "shadow": false,
+            "topLevel": true,
+            "x": 0,
+            "y": 0
+        }
+    }));
+    assert!(matches!(
+        items.as_slice(),
+        [BlockItem::EvWhenKeyPressed(KeyId::Right, bs)] if bs.blocks().is_empty()
+    ));
+}
+
+#[test]
+fn key_pressed_menu() {
+    let items = parse(json!({
+        "hat": {
+            "opcode": "event_whenflagclicked",
+            "next": "move",
+            "parent": null,
+            "inputs": {},
+            "fields": {},
+            "shadow": false,
+            "topLevel": true,
+            "x": 0,
+            "y": 0
+        },
+        "move": {
+            "opcode": "motion_movesteps",
+            "next": null,
+            "parent": "hat",
+            "inputs": { "STEPS": [3, "pressed", [4, "10"]] },
+            "fields": {},
+            "shadow": false,
+            "topLevel": false
+        },
+        "pressed": {
+            "opcode": "sensing_keypressed",
+            "next": null,
+            "parent": "move",
+            "inputs": { "KEY_OPTION": [1, "menu"] },
+            "fields": {},
+            "shadow": false,
+            "topLevel": false
+        },
+        "menu": {
+            "opcode": "sensing_keyoptions",
+            "next": null,
+            "parent": "pressed",
+            "inputs": {},
+            "fields": { "KEY_OPTION": ["enter", null] },
+            "shadow": true,
+            "topLevel": false
+        }
+    }));
+    let [BlockItem::EvWhenGreenFlagClicked(bs)] = items.as_slice() else {
+        panic!("unexpected items {:?}", items);
+    };
+    let [Stmt::MotionMove(Expr::Bool(cond))] = bs.blocks() else {
+        panic!("unexpected blocks {:?}", bs);
+    };
+    assert!(matches!(
+        &**cond,
+        BoolExpr::SensingKeyPressed(Expr::LlKey(KeyId::Enter))
+    ));
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use common::{fixture, flag_script, is_lit};
+use scratch_parser::ast::{CostumeMenu, Expr, GraphicEffect, NumberName, Stmt};
+
+fn has(pred: impl Fn(&Stmt) -> bool) -> bool {
+    let proj = fixture("looks");
+    let found = flag_script(&proj, "Sprite1").iter().any(pred);
+    found
+}
+
+#[test]
+fn say_and_think() {
+    assert!(has(
+        |s| matches!(s, Stmt::LooksSay(e) if is_lit(e, "Hello!"))
+    ));
+    assert!(has(|s| matches!(
+        s,
+        Stmt::LooksSayForSecs(e, secs) if is_lit(e, "Hmm") && is_lit(secs, "2")
+    )));
+    assert!(has(
+        |s| matches!(s, Stmt::LooksThink(e) if is_lit(e, "Ponder"))
+    ));
+    assert!(has(|s| matches!(
+        s,
+        Stmt::LooksThinkForSecs(e, secs) if is_lit(e, "Hmm...") && is_lit(secs, "3")
+    )));
+}
+
+#[test]
+fn switch_costume() {
+    let proj = fixture("looks");
+    let stmts = flag_script(&proj, "Sprite1");
+    let costume = stmts.iter().find_map(|s| match s {
+        Stmt::LooksSwitchCostume(Expr::LlCostume(CostumeMenu::Costume(idx))) => Some(*idx),
+        _ => None,
+    });
+    assert_eq!(proj.sprites[0].costumes[costume.unwrap()].name, "costume2");
+    assert!(stmts
+        .iter()
+        .any(|s| matches!(s, Stmt::LooksSwitchCostume(Expr::LooksSize))));
+    assert!(stmts.iter().any(|s| matches!(s, Stmt::LooksNextCostume)));
+}
+
+#[test]
+fn switch_backdrop() {
+    let proj = fixture("looks");
+    let stmts = flag_script(&proj, "Sprite1");
+    // resolved against the stage, not the sprite
+    let backdrop = stmts.iter().find_map(|s| match s {
+        Stmt::LooksSwitchBackdrop(Expr::LlCostume(CostumeMenu::Costume(idx))) => Some(*idx),
+        _ => None,
+    });
+    assert_eq!(
+        proj.background.costumes[backdrop.unwrap()].name,
+        "backdrop2"
+    );
+    assert!(stmts.iter().any(|s| matches!(
+        s,
+        Stmt::LooksSwitchBackdrop(Expr::LlCostume(CostumeMenu::Random))
+    )));
+    assert!(stmts.iter().any(|s| matches!(
+        s,
+        Stmt::LooksSwitchBackdropAndWait(Expr::LlCostume(CostumeMenu::Next))
+    )));
+    assert!(stmts.iter().any(|s| matches!(
+        s,
+        Stmt::LooksSwitchBackdropAndWait(Expr::LlCostume(CostumeMenu::Previous))
+    )));
+    assert!(stmts.iter().any(|s| matches!(s, Stmt::LooksNextBackdrop)));
+}
+
+#[test]
+fn size() {
+    assert!(has(
+        |s| matches!(s, Stmt::LooksChangeSize(e) if is_lit(e, "10"))
+    ));
+    assert!(has(
+        |s| matches!(s, Stmt::LooksSetSize(e) if is_lit(e, "100"))
+    ));
+}
+
+#[test]
+fn effects() {
+    assert!(has(|s| matches!(
+        s,
+        Stmt::LooksChangeEffect(GraphicEffect::Color, e) if is_lit(e, "25")
+    )));
+    assert!(has(|s| matches!(
+        s,
+        Stmt::LooksSetEffect(GraphicEffect::Ghost, e) if is_lit(e, "50")
+    )));
+    assert!(has(|s| matches!(s, Stmt::LooksClearEffects)));
+}
+
+#[test]
+fn visibility() {
+    assert!(has(|s| matches!(s, Stmt::LooksShow)));
+    assert!(has(|s| matches!(s, Stmt::LooksHide)));
+}
+
+#[test]
+fn layers() {
+    assert!(has(|s| matches!(s, Stmt::LooksGoToFront)));
+    assert!(has(|s| matches!(s, Stmt::LooksGoToBack)));
+    assert!(has(
+        |s| matches!(s, Stmt::LooksGoForwardLayers(e) if is_lit(e, "1"))
+    ));
+    assert!(has(
+        |s| matches!(s, Stmt::LooksGoBackwardLayers(e) if is_lit(e, "2"))
+    ));
+}
+
+#[test]
+fn reporters() {
+    assert!(has(|s| matches!(
+        s,
+        Stmt::LooksSay(Expr::LooksCostume(NumberName::Number))
+    )));
+    assert!(has(|s| matches!(
+        s,
+        Stmt::LooksSay(Expr::LooksCostume(NumberName::Name))
+    )));
+    assert!(has(|s| matches!(
+        s,
+        Stmt::LooksSay(Expr::LooksBackdrop(NumberName::Number))
+    )));
+    assert!(has(|s| matches!(
+        s,
+        Stmt::LooksSay(Expr::LooksBackdrop(NumberName::Name))
+    )));
+}
+
+#[test]
+fn initial_state() {
+    let proj = fixture("looks");
+    let sprite = &proj.sprites[0];
+    assert_eq!(sprite.size, 100.0);
+    assert!(sprite.visible);
+    assert_eq!(sprite.current_costume, 0);
+    assert_eq!(proj.background.current_costume, 0);
+}
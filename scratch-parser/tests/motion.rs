@@ -0,0 +1,111 @@
+mod common;
+
+use common::{fixture, flag_script, is_lit};
+use scratch_loader::sb3::RotationStyle;
+use scratch_parser::ast::{Expr, Stmt, TargetMenu};
+
+fn has(pred: impl Fn(&Stmt) -> bool) -> bool {
+    let proj = fixture("motion");
+    let found = flag_script(&proj, "Sprite1").iter().any(pred);
+    found
+}
+
+#[test]
+fn move_steps() {
+    assert!(has(|s| matches!(s, Stmt::MotionMove(e) if is_lit(e, "10"))));
+}
+
+#[test]
+fn turn() {
+    assert!(has(
+        |s| matches!(s, Stmt::MotionTurnRight(e) if is_lit(e, "15"))
+    ));
+    assert!(has(
+        |s| matches!(s, Stmt::MotionTurnLeft(e) if is_lit(e, "30"))
+    ));
+}
+
+#[test]
+fn go_to() {
+    assert!(has(|s| matches!(
+        s,
+        Stmt::MotionGoTo(Expr::LlTarget(TargetMenu::Random))
+    )));
+    assert!(has(|s| matches!(
+        s,
+        Stmt::MotionGoToXY(x, y) if is_lit(x, "1") && is_lit(y, "2")
+    )));
+}
+
+#[test]
+fn glide() {
+    assert!(has(|s| matches!(
+        s,
+        Stmt::MotionGlideTo(secs, Expr::LlTarget(TargetMenu::Sprite(name)))
+            if is_lit(secs, "1") && name == "Sprite2"
+    )));
+    assert!(has(|s| matches!(
+        s,
+        Stmt::MotionGlideToXY(secs, x, y) if is_lit(secs, "2") && is_lit(x, "3") && is_lit(y, "4")
+    )));
+}
+
+#[test]
+fn point() {
+    assert!(has(|s| matches!(
+        s,
+        Stmt::MotionPointInDirection(e) if is_lit(e, "-90")
+    )));
+    assert!(has(|s| matches!(
+        s,
+        Stmt::MotionPointTowards(Expr::LlTarget(TargetMenu::Mouse))
+    )));
+}
+
+#[test]
+fn change_and_set() {
+    assert!(has(
+        |s| matches!(s, Stmt::MotionChangeX(e) if is_lit(e, "5"))
+    ));
+    assert!(has(
+        |s| matches!(s, Stmt::MotionChangeY(e) if is_lit(e, "6"))
+    ));
+    assert!(has(|s| matches!(
+        s,
+        Stmt::MotionSetX(Expr::MotionYPosition)
+    )));
+    assert!(has(|s| matches!(
+        s,
+        Stmt::MotionSetY(Expr::MotionXPosition)
+    )));
+}
+
+#[test]
+fn bounce() {
+    assert!(has(|s| matches!(s, Stmt::MotionIfOnEdgeBounce)));
+}
+
+#[test]
+fn rotation_style() {
+    assert!(has(|s| matches!(
+        s,
+        Stmt::MotionSetRotationStyle(RotationStyle::LeftRight)
+    )));
+}
+
+#[test]
+fn direction_reporter() {
+    assert!(has(|s| matches!(
+        s,
+        Stmt::MotionPointInDirection(Expr::MotionDirection)
+    )));
+}
+
+#[test]
+fn initial_state() {
+    let proj = fixture("motion");
+    let sprite = &proj.sprites[0];
+    assert_eq!(sprite.position, (0.0, 0.0));
+    assert_eq!(sprite.direction, 90.0);
+    assert_eq!(sprite.rotation_style, RotationStyle::AllAround);
+}
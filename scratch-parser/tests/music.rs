@@ -0,0 +1,67 @@
+mod common;
+
+use common::{fixture, flag_script, is_lit, parse};
+use scratch_parser::ast::{BlockItem, Drum, Expr, Instrument, Stmt};
+use serde_json::json;
+
+#[test]
+fn music_blocks() {
+    let proj = fixture("music");
+    assert_eq!(proj.background.tempo, 120.0);
+    let stmts = flag_script(&proj, "Sprite1");
+    assert!(matches!(&stmts[0], Stmt::LooksSay(Expr::MusicGetTempo)));
+    assert!(matches!(
+        &stmts[1],
+        Stmt::MusicSetInstrument(Expr::LlInstrument(Instrument::Organ))
+    ));
+    assert!(matches!(&stmts[2], Stmt::MusicSetTempo(tempo) if is_lit(tempo, "240")));
+    assert!(matches!(&stmts[3], Stmt::MusicChangeTempo(tempo) if is_lit(tempo, "-40")));
+    assert!(matches!(
+        &stmts[4],
+        Stmt::MusicPlayDrumForBeats(Expr::LlDrum(Drum::SnareDrum), beats) if is_lit(beats, "0.25")
+    ));
+    assert!(matches!(&stmts[5], Stmt::MusicRestForBeats(beats) if is_lit(beats, "0.5")));
+    // the piano keyboard is a literal note
+    assert!(matches!(
+        &stmts[6],
+        Stmt::MusicPlayNoteForBeats(note, beats) if is_lit(note, "60") && is_lit(beats, "0.25")
+    ));
+    assert!(matches!(
+        &stmts[8],
+        Stmt::MusicSetInstrument(Expr::OperatorAdd(_, _))
+    ));
+}
+
+#[test]
+fn menus() {
+    assert_eq!(Instrument::from_scratch("1"), Some(Instrument::Piano));
+    assert_eq!(Instrument::from_scratch("21"), Some(Instrument::SynthPad));
+    assert_eq!(Instrument::from_scratch("22"), None);
+    assert_eq!(Instrument::from_scratch("0"), None);
+    assert_eq!(Drum::from_scratch("18"), Some(Drum::Cuica));
+    assert_eq!(Drum::Cowbell.number(), 11);
+}
+
+#[test]
+fn default_tempo() {
+    assert_eq!(fixture("pen").background.tempo, 60.0);
+}
+
+#[test]
+fn undeclared_extension() {
+    let items = parse(json!({
+        "hat": {
+            "opcode": "event_whenflagclicked", "next": "rest", "parent": null,
+            "inputs": {}, "fields": {}, "shadow": false, "topLevel": true, "x": 0, "y": 0
+        },
+        "rest": {
+            "opcode": "music_restForBeats", "next": null, "parent": "hat",
+            "inputs": { "BEATS": [1, [4, "1"]] }, "fields": {},
+            "shadow": false, "topLevel": false
+        }
+    }));
+    assert!(matches!(
+        items.as_slice(),
+        [BlockItem::EvWhenGreenFlagClicked(bs)] if bs.blocks().is_empty()
+    ));
+}
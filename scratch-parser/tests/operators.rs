@@ -0,0 +1,173 @@
+mod common;
+
+use common::{fixture, flag_script, is_lit, parse, try_parse};
+use scratch_parser::{
+    ast::{BlockItem, BoolExpr, Expr, MathOp, Stmt},
+    parser::ParseSb3Error,
+};
+use serde_json::json;
+
+fn has(pred: impl Fn(&Expr) -> bool) -> bool {
+    let proj = fixture("operators");
+    let found = flag_script(&proj, "Sprite1").iter().any(|s| match s {
+        Stmt::LooksSay(expr) => pred(expr),
+        _ => false,
+    });
+    found
+}
+
+#[test]
+fn arithmetic() {
+    assert!(has(
+        |e| matches!(e, Expr::OperatorAdd(a, b) if is_lit(a, "1") && is_lit(b, "2"))
+    ));
+    assert!(has(
+        |e| matches!(e, Expr::OperatorSubtract(a, b) if is_lit(a, "5") && is_lit(b, "7.5"))
+    ));
+    assert!(has(
+        |e| matches!(e, Expr::OperatorMultiply(a, _) if is_lit(a, "abc"))
+    ));
+    assert!(has(
+        |e| matches!(e, Expr::OperatorDivide(_, b) if is_lit(b, "0"))
+    ));
+    assert!(has(
+        |e| matches!(e, Expr::OperatorMod(a, b) if is_lit(a, "-7") && is_lit(b, "3"))
+    ));
+    assert!(has(
+        |e| matches!(e, Expr::OperatorRound(n) if is_lit(n, "2.5"))
+    ));
+    assert!(has(
+        |e| matches!(e, Expr::OperatorRandom(from, to) if is_lit(from, "1") && is_lit(to, "1"))
+    ));
+}
+
+#[test]
+fn strings() {
+    assert!(has(
+        |e| matches!(e, Expr::OperatorJoin(a, b) if is_lit(a, "foo") && is_lit(b, "12"))
+    ));
+    assert!(has(
+        |e| matches!(e, Expr::OperatorLetterOf(i, s) if is_lit(i, "2") && is_lit(s, "hello"))
+    ));
+    assert!(has(
+        |e| matches!(e, Expr::OperatorLength(s) if is_lit(s, "hello"))
+    ));
+}
+
+#[test]
+fn math_ops() {
+    use MathOp::*;
+    let expected = [
+        (Abs, "-5"),
+        (Floor, "2.7"),
+        (Ceiling, "2.1"),
+        (Sqrt, "16"),
+        (Sin, "180"),
+        (Cos, "60"),
+        (Tan, "90"),
+        (Asin, "1"),
+        (Acos, "1"),
+        (Atan, "1"),
+        (Ln, "1"),
+        (Log, "100"),
+        (PowE, "0"),
+        (Pow10, "3"),
+    ];
+    for (op, val) in expected {
+        assert!(
+            has(|e| matches!(e, Expr::OperatorMathOp(o, n) if *o == op && is_lit(n, val))),
+            "{:?}",
+            op
+        );
+    }
+}
+
+#[test]
+fn booleans() {
+    let cond = |pred: fn(&BoolExpr) -> bool| has(move |e| matches!(e, Expr::Bool(b) if pred(b)));
+    assert!(cond(
+        |b| matches!(b, BoolExpr::OperatorLt(a, b) if is_lit(a, "1") && is_lit(b, "10"))
+    ));
+    assert!(cond(
+        |b| matches!(b, BoolExpr::OperatorGt(a, _) if is_lit(a, "apple"))
+    ));
+    assert!(cond(
+        |b| matches!(b, BoolExpr::OperatorEquals(_, b) if is_lit(b, "abc"))
+    ));
+    assert!(cond(
+        |b| matches!(b, BoolExpr::OperatorContains(a, b) if is_lit(a, "Hello") && is_lit(b, "ELL"))
+    ));
+    // unfilled boolean inputs are not the same as `false`
+    assert!(cond(|b| matches!(
+        b,
+        BoolExpr::OperatorAnd(a, b)
+            if matches!(**a, BoolExpr::SensingKeyPressed(_)) && matches!(**b, BoolExpr::Empty)
+    )));
+    assert!(cond(|b| matches!(
+        b,
+        BoolExpr::OperatorOr(a, _)
+            if matches!(&**a, BoolExpr::OperatorNot(n) if matches!(**n, BoolExpr::Empty))
+    )));
+}
+
+#[test]
+fn missing_inputs() {
+    // `NUM2` of the second `add` was never filled in
+    assert!(has(
+        |e| matches!(e, Expr::OperatorAdd(a, b) if is_lit(a, "1") && is_lit(b, ""))
+    ));
+    let blocks = json!({
+        "hat": {
+            "opcode": "event_whenflagclicked", "next": "say", "parent": null,
+            "inputs": {}, "fields": {}, "shadow": false, "topLevel": true, "x": 0, "y": 0
+        },
+        "say": {
+            "opcode": "looks_say", "next": null, "parent": "hat",
+            "inputs": { "MESSAGE": [3, "op", [10, ""]] }, "fields": {},
+            "shadow": false, "topLevel": false
+        },
+        "op": {
+            "opcode": "operator_subtract", "next": null, "parent": "say",
+            "inputs": {}, "fields": {}, "shadow": false, "topLevel": false
+        }
+    });
+    let items = parse(blocks);
+    assert_eq!(items.len(), 1);
+}
+
+#[test]
+fn cleared_reporter_uses_shadow() {
+    let blocks = json!({
+        "hat": {
+            "opcode": "event_whenflagclicked", "next": "say", "parent": null,
+            "inputs": {}, "fields": {}, "shadow": false, "topLevel": true, "x": 0, "y": 0
+        },
+        "say": {
+            "opcode": "looks_say", "next": null, "parent": "hat",
+            "inputs": { "MESSAGE": [3, null, [10, "shown again"]] }, "fields": {},
+            "shadow": false, "topLevel": false
+        }
+    });
+    let items = parse(blocks);
+    let [BlockItem::EvWhenGreenFlagClicked(bs)] = items.as_slice() else {
+        panic!("unexpected items {:?}", items);
+    };
+    assert!(matches!(
+        bs.blocks(),
+        [Stmt::LooksSay(e)] if is_lit(e, "shown again")
+    ));
+}
+
+#[test]
+fn dangling_block() {
+    let blocks = json!({
+        "hat": {
+            "opcode": "event_whenflagclicked", "next": "gone", "parent": null,
+            "inputs": {}, "fields": {}, "shadow": false, "topLevel": true, "x": 0, "y": 0
+        }
+    });
+    match try_parse(blocks) {
+        Err(ParseSb3Error::CannotResolveBlock(id)) => assert_eq!(id, "gone"),
+        result => panic!("unexpected {:?}", result),
+    }
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::{fixture, flag_script, is_lit, parse};
+use scratch_parser::ast::{BlockItem, Expr, PenColorParam, Stmt};
+use serde_json::json;
+
+#[test]
+fn pen_blocks() {
+    let proj = fixture("pen");
+    assert_eq!(proj.extensions, ["pen"]);
+    let stmts = flag_script(&proj, "Sprite1");
+    assert!(matches!(
+        &stmts[..3],
+        [Stmt::PenClear, Stmt::PenDown, Stmt::PenSetColor(color)] if is_lit(color, "#ff0000")
+    ));
+    assert!(matches!(&stmts[9], Stmt::PenStamp));
+    assert!(matches!(&stmts[10], Stmt::PenUp));
+    assert!(matches!(&stmts[6], Stmt::PenSetSize(size) if is_lit(size, "5")));
+    assert!(matches!(&stmts[7], Stmt::PenChangeSize(size) if is_lit(size, "2000")));
+}
+
+#[test]
+fn color_params() {
+    let proj = fixture("pen");
+    let stmts = flag_script(&proj, "Sprite1");
+    assert!(matches!(
+        &stmts[3],
+        Stmt::PenSetColorParam(Expr::LlPenColorParam(PenColorParam::Saturation), value)
+            if is_lit(value, "50")
+    ));
+    assert!(matches!(
+        &stmts[4],
+        Stmt::PenChangeColorParam(Expr::LlPenColorParam(PenColorParam::Color), value)
+            if is_lit(value, "110")
+    ));
+    // a reporter dropped onto the menu is looked up at runtime
+    assert!(matches!(
+        &stmts[5],
+        Stmt::PenChangeColorParam(Expr::OperatorJoin(_, _), _)
+    ));
+}
+
+#[test]
+fn legacy_shade_and_hue() {
+    let proj = fixture("pen");
+    let stmts = flag_script(&proj, "Sprite1");
+    assert!(matches!(
+        &stmts[12..],
+        [
+            Stmt::PenSetHue(hue),
+            Stmt::PenChangeHue(hue_change),
+            Stmt::PenSetShade(shade),
+            Stmt::PenChangeShade(shade_change),
+        ] if is_lit(hue, "140") && is_lit(hue_change, "20")
+            && is_lit(shade, "250") && is_lit(shade_change, "-60")
+    ));
+}
+
+#[test]
+fn undeclared_extension() {
+    let items = parse(json!({
+        "hat": {
+            "opcode": "event_whenflagclicked", "next": "down", "parent": null,
+            "inputs": {}, "fields": {}, "shadow": false, "topLevel": true, "x": 0, "y": 0
+        },
+        "down": {
+            "opcode": "pen_penDown", "next": null, "parent": "hat",
+            "inputs": {}, "fields": {}, "shadow": false, "topLevel": false
+        }
+    }));
+    // the project has no `pen` in its extensions, so the block is skipped
+    assert!(matches!(
+        items.as_slice(),
+        [BlockItem::EvWhenGreenFlagClicked(bs)] if bs.blocks().is_empty()
+    ));
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use common::{fixture, flag_script, is_lit, parse};
+use scratch_parser::ast::{
+    BlockItem, BoolExpr, CurrentMenu, Expr, ParsedScratchProject, SensingOfProperty, Stmt,
+    TargetMenu,
+};
+use serde_json::json;
+
+fn said(proj: &ParsedScratchProject) -> Vec<&Expr> {
+    flag_script(proj, "Sprite1")
+        .iter()
+        .filter_map(|s| match s {
+            Stmt::LooksSay(expr) => Some(expr),
+            _ => None,
+        })
+        .collect()
+}
+
+fn has(pred: impl Fn(&Expr) -> bool) -> bool {
+    let proj = fixture("sensing");
+    let found = said(&proj).into_iter().any(pred);
+    found
+}
+
+fn has_bool(pred: impl Fn(&BoolExpr) -> bool) -> bool {
+    has(|e| matches!(e, Expr::Bool(b) if pred(b)))
+}
+
+#[test]
+fn stmts() {
+    let proj = fixture("sensing");
+    assert!(matches!(
+        &flag_script(&proj, "Sprite1")[..3],
+        [
+            Stmt::SensingAskAndWait(question),
+            Stmt::SensingResetTimer,
+            Stmt::SensingSetDragMode(true),
+        ] if is_lit(question, "name?")
+    ));
+}
+
+#[test]
+fn reporters() {
+    assert!(has(|e| matches!(e, Expr::SensingAnswer)));
+    assert!(has(|e| matches!(
+        e,
+        Expr::SensingDistanceTo(to) if matches!(&**to, Expr::LlTarget(TargetMenu::Sprite(s)) if s == "Sprite2")
+    )));
+    assert!(has(|e| matches!(
+        e,
+        Expr::SensingDistanceTo(to) if matches!(**to, Expr::LlTarget(TargetMenu::Mouse))
+    )));
+    assert!(has(|e| matches!(e, Expr::SensingMouseX)));
+    assert!(has(|e| matches!(e, Expr::SensingMouseY)));
+    assert!(has(|e| matches!(e, Expr::SensingLoudness)));
+    assert!(has(|e| matches!(e, Expr::SensingTimer)));
+    assert!(has(|e| matches!(
+        e,
+        Expr::SensingCurrent(CurrentMenu::Year)
+    )));
+    assert!(has(|e| matches!(e, Expr::SensingDaysSince2000)));
+    assert!(has(|e| matches!(e, Expr::SensingUsername)));
+}
+
+#[test]
+fn booleans() {
+    assert!(has_bool(|b| matches!(
+        b,
+        BoolExpr::SensingTouchingObject(Expr::LlTarget(TargetMenu::Edge))
+    )));
+    assert!(has_bool(|b| matches!(
+        b,
+        BoolExpr::SensingTouchingObject(Expr::LlTarget(TargetMenu::Sprite(s))) if s == "Sprite2"
+    )));
+    assert!(has_bool(
+        |b| matches!(b, BoolExpr::SensingTouchingColor(c) if is_lit(c, "#ff0000"))
+    ));
+    assert!(has_bool(|b| matches!(
+        b,
+        BoolExpr::SensingColorIsTouchingColor(a, b) if is_lit(a, "#ff0000") && is_lit(b, "#00ff00")
+    )));
+    assert!(has_bool(|b| matches!(b, BoolExpr::SensingMouseDown)));
+}
+
+#[test]
+fn attributes_of() {
+    assert!(has(|e| matches!(
+        e,
+        Expr::SensingOf(SensingOfProperty::XPosition, obj)
+            if matches!(&**obj, Expr::LlTarget(TargetMenu::Sprite(s)) if s == "Sprite2")
+    )));
+    assert!(has(|e| matches!(
+        e,
+        Expr::SensingOf(SensingOfProperty::BackdropNumber, obj)
+            if matches!(**obj, Expr::LlTarget(TargetMenu::Stage))
+    )));
+    assert!(has(|e| matches!(
+        e,
+        Expr::SensingOf(SensingOfProperty::CostumeName, _)
+    )));
+}
+
+#[test]
+fn variables_of() {
+    let proj = fixture("sensing");
+    let sprite2 = proj.sprites.iter().find(|s| s.name == "Sprite2").unwrap();
+    let hp = &sprite2.variables["hid"].0;
+    let score = &proj.background.variables["sid"].0;
+    let said = said(&proj);
+    // Sprite1 has an `hp` of its own, the one of Sprite2 is read
+    assert!(said.iter().any(|e| matches!(
+        e,
+        Expr::SensingOf(SensingOfProperty::Variable(v), _) if v == hp
+    )));
+    assert!(said.iter().any(|e| matches!(
+        e,
+        Expr::SensingOf(SensingOfProperty::Variable(v), _) if v == score
+    )));
+    // an unknown variable is left to the runtime, which answers 0 like scratch-vm
+    assert!(said.iter().any(|e| matches!(
+        e,
+        Expr::SensingOf(SensingOfProperty::VariableName(name), _) if name == "missing"
+    )));
+}
+
+#[test]
+fn variable_of_computed_object() {
+    let items = parse(json!({
+        "hat": {
+            "opcode": "event_whenflagclicked", "next": "say", "parent": null,
+            "inputs": {}, "fields": {}, "shadow": false, "topLevel": true, "x": 0, "y": 0
+        },
+        "say": {
+            "opcode": "looks_say", "next": null, "parent": "hat",
+            "inputs": { "MESSAGE": [3, "of", [10, ""]] }, "fields": {},
+            "shadow": false, "topLevel": false
+        },
+        "of": {
+            "opcode": "sensing_of", "next": null, "parent": "say",
+            "inputs": { "OBJECT": [3, "join", "menu"] },
+            "fields": { "PROPERTY": ["hp", null] },
+            "shadow": false, "topLevel": false
+        },
+        "menu": {
+            "opcode": "sensing_of_object_menu", "next": null, "parent": "of",
+            "inputs": {}, "fields": { "OBJECT": ["_stage_", null] },
+            "shadow": true, "topLevel": false
+        },
+        "join": {
+            "opcode": "operator_join", "next": null, "parent": "of",
+            "inputs": { "STRING1": [1, [10, "Sprite"]], "STRING2": [1, [10, "2"]] },
+            "fields": {}, "shadow": false, "topLevel": false
+        }
+    }));
+    let [BlockItem::EvWhenGreenFlagClicked(bs)] = items.as_slice() else {
+        panic!("unexpected items {:?}", items);
+    };
+    assert!(matches!(
+        bs.blocks(),
+        [Stmt::LooksSay(Expr::SensingOf(SensingOfProperty::VariableName(name), obj))]
+            if name == "hp" && matches!(**obj, Expr::OperatorJoin(_, _))
+    ));
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::{fixture, flag_script, is_lit};
+use scratch_parser::ast::{Expr, SoundEffect, Stmt};
+
+fn has(pred: impl Fn(&Stmt) -> bool) -> bool {
+    let proj = fixture("sound");
+    let found = flag_script(&proj, "Sprite1").iter().any(pred);
+    found
+}
+
+#[test]
+fn play() {
+    let proj = fixture("sound");
+    let stmts = flag_script(&proj, "Sprite1");
+    let sounds = &proj.sprites[0].sounds;
+    let played = stmts.iter().find_map(|s| match s {
+        Stmt::SoundPlay(Expr::LlSound(idx)) => Some(&sounds[*idx]),
+        _ => None,
+    });
+    assert_eq!(played.unwrap().name, "Pop");
+    let played = stmts.iter().find_map(|s| match s {
+        Stmt::SoundPlayUntilDone(Expr::LlSound(idx)) => Some(&sounds[*idx]),
+        _ => None,
+    });
+    assert_eq!(played.unwrap().name, "Meow");
+    assert!(stmts
+        .iter()
+        .any(|s| matches!(s, Stmt::SoundPlay(Expr::SoundVolume))));
+}
+
+#[test]
+fn stop_all() {
+    assert!(has(|s| matches!(s, Stmt::SoundStopAll)));
+}
+
+#[test]
+fn volume() {
+    assert!(has(
+        |s| matches!(s, Stmt::SoundChangeVolume(e) if is_lit(e, "-10"))
+    ));
+    assert!(has(
+        |s| matches!(s, Stmt::SoundSetVolume(e) if is_lit(e, "100"))
+    ));
+    assert_eq!(fixture("sound").sprites[0].volume, 100.0);
+}
+
+#[test]
+fn effects() {
+    assert!(has(|s| matches!(
+        s,
+        Stmt::SoundChangeEffect(SoundEffect::Pitch, e) if is_lit(e, "10")
+    )));
+    assert!(has(|s| matches!(
+        s,
+        Stmt::SoundSetEffect(SoundEffect::Pan, e) if is_lit(e, "-100")
+    )));
+    assert!(has(|s| matches!(s, Stmt::SoundClearEffects)));
+}
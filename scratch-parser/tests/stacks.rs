@@ -0,0 +1,161 @@
+mod common;
+
+use common::parse;
+use scratch_loader::sb3::ScratchValue;
+use scratch_parser::ast::{BlockItem, BoolExpr, Expr, KeyId, Stmt};
+use serde_json::json;
+
+#[test]
+fn empty_substack() {
+    let items = parse(json!({
+        "hat": {
+            "opcode": "event_whenflagclicked",
+            "next": "loop",
+            "parent": null,
+            "inputs": {},
+            "fields": {},
+            "shadow": false,
+            "topLevel": true,
+            "x": 0,
+            "y": 0
+        },
+        "loop": {
+            "opcode": "control_forever",
+            "next": null,
+            "parent": "hat",
+            "inputs": {},
+            "fields": {},
+            "shadow": false,
+            "topLevel": false
+        }
+    }));
+    let [BlockItem::EvWhenGreenFlagClicked(bs)] = items.as_slice() else {
+        panic!("unexpected items {:?}", items);
+    };
+    assert!(matches!(
+        bs.blocks(),
+        [Stmt::ControlForever(body)] if body.blocks().is_empty()
+    ));
+}
+
+#[test]
+fn typed_inputs() {
+    let items = parse(json!({
+        "def": {
+            "opcode": "procedures_definition",
+            "next": "call",
+            "parent": null,
+            "inputs": { "custom_block": [1, "proto"] },
+            "fields": {},
+            "shadow": false,
+            "topLevel": true,
+            "x": 0,
+            "y": 0
+        },
+        "proto": {
+            "opcode": "procedures_prototype",
+            "next": null,
+            "parent": "def",
+            "inputs": {},
+            "fields": {},
+            "shadow": true,
+            "topLevel": false,
+            "mutation": {
+                "tagName": "mutation",
+                "children": [],
+                "proccode": "f %s %b",
+                "argumentids": "[\"a\",\"b\"]",
+                "argumentnames": "[\"n\",\"c\"]",
+                "argumentdefaults": "[\"\",\"false\"]",
+                "warp": "false"
+            }
+        },
+        "call": {
+            "opcode": "procedures_call",
+            "next": "move",
+            "parent": "def",
+            "inputs": { "a": [3, "cond", [10, ""]] },
+            "fields": {},
+            "shadow": false,
+            "topLevel": false,
+            "mutation": {
+                "tagName": "mutation",
+                "children": [],
+                "proccode": "f %s %b",
+                "argumentids": "[\"a\",\"b\"]",
+                "warp": "false"
+            }
+        },
+        "cond": {
+            "opcode": "argument_reporter_boolean",
+            "next": null,
+            "parent": "call",
+            "inputs": {},
+            "fields": { "VALUE": ["c", null] },
+            "shadow": false,
+            "topLevel": false
+        },
+        "move": {
+            "opcode": "motion_movesteps",
+            "next": null,
+            "parent": "call",
+            "inputs": { "STEPS": [1, [4, "10"]] },
+            "fields": {},
+            "shadow": false,
+            "topLevel": false
+        }
+    }));
+    let [BlockItem::Def(def, bs)] = items.as_slice() else {
+        panic!("unexpected items {:?}", items);
+    };
+    let [Stmt::ProcCall(path, args), Stmt::MotionMove(Expr::LlScratchValue(ScratchValue::Str(steps)))] =
+        bs.blocks()
+    else {
+        panic!("unexpected blocks {:?}", bs);
+    };
+    assert_eq!(*path, def.path);
+    assert_eq!(steps, "10");
+    // a boolean reporter in a round input and a missing boolean input
+    assert!(matches!(
+        args.as_slice(),
+        [Expr::Bool(a), Expr::Bool(b)]
+            if matches!(&**a, BoolExpr::ProcArg(arg) if *arg == def.args[1].path)
+                && matches!(**b, BoolExpr::Empty)
+    ));
+}
+
+#[test]
+fn scripts_in_position_order() {
+    let hat = |key: &str, x: i32, y: i32| {
+        json!({
+            "opcode": "event_whenkeypressed",
+            "next": null,
+            "parent": null,
+            "inputs": {},
+            "fields": { "KEY_OPTION": [key, null] },
+            "shadow": false,
+            "topLevel": true,
+            "x": x,
+            "y": y
+        })
+    };
+    let items = parse(json!({
+        "h1": hat("a", 0, 300),
+        "h2": hat("b", 50, 0),
+        "h3": hat("c", 0, 0),
+        "h4": hat("d", -20, 100),
+        "h5": hat("e", 0, 100),
+        "h0": hat("f", 0, 100)
+    }));
+    let keys: Vec<_> = items
+        .iter()
+        .map(|item| match item {
+            BlockItem::EvWhenKeyPressed(key, _) => *key,
+            item => panic!("unexpected item {:?}", item),
+        })
+        .collect();
+    assert_eq!(
+        keys,
+        ["c", "b", "d", "f", "e", "a"].map(|key| KeyId::from_scratch(key).unwrap())
+    );
+}
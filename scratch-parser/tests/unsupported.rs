@@ -0,0 +1,197 @@
+mod common;
+
+use common::{flag_script, is_lit, try_fixture_with, try_parse_with};
+use scratch_parser::{
+    ast::{BlockItem, Expr, FallbackBlock, FallbackInput, Stmt},
+    parser::{ParseSb3Error, UnsupportedPolicy},
+};
+use serde_json::json;
+
+fn said(stmt: &Stmt) -> &Expr {
+    match stmt {
+        Stmt::LooksSay(expr) => expr,
+        stmt => panic!("unexpected {:?}", stmt),
+    }
+}
+
+#[test]
+fn error() {
+    match try_fixture_with("unsupported", UnsupportedPolicy::Error) {
+        Err(ParseSb3Error::Unsupported {
+            opcode,
+            block,
+            target,
+        }) => {
+            assert_eq!(opcode, "control_incr_counter");
+            assert_eq!(block, "co1");
+            assert_eq!(target, "Sprite1");
+        }
+        result => panic!("unexpected {:?}", result.map(|_| ())),
+    }
+}
+
+#[test]
+fn error_on_hats() {
+    let blocks = json!({
+        "hat": {
+            "opcode": "foo_whenSomething", "next": null, "parent": null,
+            "inputs": {}, "fields": {}, "shadow": false, "topLevel": true, "x": 0, "y": 0
+        }
+    });
+    assert!(matches!(
+        try_parse_with(blocks.clone(), UnsupportedPolicy::Error),
+        Err(ParseSb3Error::Unsupported { opcode, .. }) if opcode == "foo_whenSomething"
+    ));
+    // only the other policies skip the script
+    let items = try_parse_with(blocks, UnsupportedPolicy::Fallback).unwrap();
+    assert!(items.is_empty());
+}
+
+#[test]
+fn error_on_undeclared_extension() {
+    let blocks = json!({
+        "hat": {
+            "opcode": "event_whenflagclicked", "next": "pen", "parent": null,
+            "inputs": {}, "fields": {}, "shadow": false, "topLevel": true, "x": 0, "y": 0
+        },
+        "pen": {
+            "opcode": "pen_clear", "next": null, "parent": "hat",
+            "inputs": {}, "fields": {}, "shadow": false, "topLevel": false
+        }
+    });
+    assert!(matches!(
+        try_parse_with(blocks.clone(), UnsupportedPolicy::Error),
+        Err(ParseSb3Error::Unsupported { opcode, block, .. })
+            if opcode == "pen_clear" && block == "pen"
+    ));
+    assert!(try_parse_with(blocks, UnsupportedPolicy::Stub).is_ok());
+}
+
+#[test]
+fn stub() {
+    let proj = try_fixture_with("unsupported", UnsupportedPolicy::Stub).unwrap();
+    let stmts = flag_script(&proj, "Sprite1");
+    assert_eq!(stmts.len(), 6);
+    // unknown statements stay in place, their substacks are dropped
+    assert!(matches!(&stmts[0], Stmt::Stub(opcode) if opcode == "control_incr_counter"));
+    assert!(matches!(&stmts[1], Stmt::Stub(opcode) if opcode == "control_incr_counter"));
+    assert!(matches!(&stmts[3], Stmt::Stub(opcode) if opcode == "foo_repeatFast"));
+    for idx in [2, 4, 5] {
+        assert!(is_lit(said(&stmts[idx]), "0"));
+    }
+}
+
+#[test]
+fn fallback() {
+    let proj = try_fixture_with("unsupported", UnsupportedPolicy::Fallback).unwrap();
+    let stmts = flag_script(&proj, "Sprite1");
+    assert_eq!(stmts.len(), 6);
+    assert!(matches!(
+        &stmts[0],
+        Stmt::Fallback(FallbackBlock { opcode, fields, inputs })
+            if opcode == "control_incr_counter" && fields.is_empty() && inputs.is_empty()
+    ));
+    assert!(matches!(
+        said(&stmts[2]),
+        Expr::Fallback(FallbackBlock { opcode, .. }) if opcode == "control_get_counter"
+    ));
+}
+
+#[test]
+fn fallback_inputs() {
+    let proj = try_fixture_with("unsupported", UnsupportedPolicy::Fallback).unwrap();
+    let stmts = flag_script(&proj, "Sprite1");
+    let Stmt::Fallback(block) = &stmts[3] else {
+        panic!("unexpected {:?}", stmts[3]);
+    };
+    assert_eq!(block.fields, [("MODE".to_string(), "fast".to_string())]);
+    // sorted by name, the substack is parsed like any other
+    assert!(matches!(
+        block.inputs.as_slice(),
+        [(substack, FallbackInput::Stack(body)), (times, FallbackInput::Expr(n))]
+            if substack == "SUBSTACK" && times == "TIMES" && is_lit(n, "3")
+                && matches!(body.blocks(), [Stmt::MotionChangeX(_)])
+    ));
+    // known reporters are compiled, the menu of the unknown block is passed as its value
+    let Expr::Fallback(block) = said(&stmts[4]) else {
+        panic!("unexpected {:?}", stmts[4]);
+    };
+    assert!(matches!(
+        block.inputs.as_slice(),
+        [(_, FallbackInput::Expr(Expr::MotionXPosition)), (_, FallbackInput::Expr(what))]
+            if is_lit(what, "speed")
+    ));
+}
+
+// known blocks with a menu value the parser does not know
+fn odd_menus() -> serde_json::Value {
+    json!({
+        "hat": {
+            "opcode": "event_whenflagclicked", "next": "effect", "parent": null,
+            "inputs": {}, "fields": {}, "shadow": false, "topLevel": true, "x": 0, "y": 0
+        },
+        "effect": {
+            "opcode": "looks_seteffectto", "next": "say", "parent": "hat",
+            "inputs": { "VALUE": [1, [4, "10"]] }, "fields": { "EFFECT": ["blur", null] },
+            "shadow": false, "topLevel": false
+        },
+        "say": {
+            "opcode": "looks_say", "next": "stop", "parent": "effect",
+            "inputs": { "MESSAGE": [3, "op", [10, ""]] }, "fields": {},
+            "shadow": false, "topLevel": false
+        },
+        "op": {
+            "opcode": "operator_mathop", "next": null, "parent": "say",
+            "inputs": { "NUM": [1, [4, "8"]] }, "fields": { "OPERATOR": ["cbrt", null] },
+            "shadow": false, "topLevel": false
+        },
+        "stop": {
+            "opcode": "control_stop", "next": null, "parent": "say",
+            "inputs": {}, "fields": { "STOP_OPTION": ["everything", null] },
+            "shadow": false, "topLevel": false
+        }
+    })
+}
+
+fn flag_stmts(items: &[BlockItem]) -> &[Stmt] {
+    match items {
+        [BlockItem::EvWhenGreenFlagClicked(bs)] => bs.blocks(),
+        items => panic!("unexpected items {:?}", items),
+    }
+}
+
+#[test]
+fn odd_menus_follow_the_policy() {
+    match try_parse_with(odd_menus(), UnsupportedPolicy::Error) {
+        Err(ParseSb3Error::Unsupported {
+            opcode,
+            block,
+            target,
+        }) => {
+            assert_eq!(opcode, "looks_seteffectto");
+            assert_eq!(block, "effect");
+            assert_eq!(target, "Stage");
+        }
+        result => panic!("unexpected {:?}", result.map(|_| ())),
+    }
+
+    let items = try_parse_with(odd_menus(), UnsupportedPolicy::Stub).unwrap();
+    assert!(matches!(
+        flag_stmts(&items),
+        [Stmt::Stub(effect), Stmt::LooksSay(said), Stmt::Stub(stop)]
+            if effect == "looks_seteffectto" && is_lit(said, "0") && stop == "control_stop"
+    ));
+
+    let items = try_parse_with(odd_menus(), UnsupportedPolicy::Fallback).unwrap();
+    let stmts = flag_stmts(&items);
+    let [Stmt::Fallback(effect), Stmt::LooksSay(Expr::Fallback(op)), Stmt::Fallback(stop)] = stmts
+    else {
+        panic!("unexpected {:?}", stmts);
+    };
+    assert_eq!(effect.fields, [("EFFECT".to_string(), "blur".to_string())]);
+    assert_eq!(op.opcode, "operator_mathop");
+    assert_eq!(
+        stop.fields,
+        [("STOP_OPTION".to_string(), "everything".to_string())]
+    );
+}
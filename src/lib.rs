@@ -1,15 +1,48 @@
 mod reporter;
 
-use scratch_loader::{load_sb3::load, sb3::Sb3File};
+use std::rc::Rc;
 
+use scratch_codegen::js::JsCodegen;
+use scratch_loader::{
+    load_sb2,
+    load_sb3::{load_dir_with, load_with},
+    observer::Observer,
+    options::LoadOptions,
+    sb3::Sb3File,
+};
+
+pub use reporter::ConsoleObserver;
 use reporter::Reporter;
-use scratch_parser::{ast::ParsedScratchProject, parser::Sb3FormatParser};
+use scratch_parser::{
+    ast::ParsedScratchProject,
+    parser::{Sb3FormatParser, UnsupportedPolicy},
+};
 
-pub fn load_sb3(src: &str) -> Sb3File {
-    load(src).report()
+pub fn load_sb3(src: &str, observer: Rc<dyn Observer>) -> Sb3File {
+    let options = LoadOptions {
+        observer,
+        ..Default::default()
+    };
+    if std::path::Path::new(src).is_dir() {
+        load_dir_with(src, &options).report()
+    } else if src.ends_with(".sb2") {
+        load_sb2::load_with(src, &options).report()
+    } else {
+        load_with(src, &options).report()
+    }
 }
 
-pub fn parse_sb3(src: Sb3File) -> ParsedScratchProject {
-    let parser = Sb3FormatParser::new(src);
+pub fn parse_sb3(
+    src: Sb3File,
+    observer: Rc<dyn Observer>,
+    unsupported: UnsupportedPolicy,
+) -> ParsedScratchProject {
+    let parser = Sb3FormatParser::new(src)
+        .observer(observer)
+        .unsupported(unsupported);
     parser.parse().report()
 }
+
+pub fn gen_js(ast: &ParsedScratchProject) -> String {
+    JsCodegen::new(ast).generate()
+}
@@ -1,10 +1,50 @@
-use ira::{load_sb3, parse_sb3};
+use std::rc::Rc;
+
+use ira::{gen_js, load_sb3, parse_sb3, ConsoleObserver};
+use scratch_parser::parser::UnsupportedPolicy;
 
 use colored::Colorize;
 
+const USAGE: &str = "usage: ira [--unsupported=error|stub|fallback] <source> [output]";
+
 fn main() {
-    // todo! parse args at first
-    let proj = load_sb3(&std::env::args().nth(1).expect("usage: ira <source>"));
-    let ast = parse_sb3(proj);
-    println!("{}: {:?}", "result".bright_blue(), ast);
+    let (flags, args): (Vec<_>, Vec<_>) = std::env::args()
+        .skip(1)
+        .partition(|arg| arg.starts_with("--"));
+    let mut unsupported = UnsupportedPolicy::default();
+    for flag in flags {
+        unsupported = match flag.as_str() {
+            "--unsupported=error" => UnsupportedPolicy::Error,
+            "--unsupported=stub" => UnsupportedPolicy::Stub,
+            "--unsupported=fallback" => UnsupportedPolicy::Fallback,
+            _ => {
+                eprintln!("{}: unknown option `{}`", "error".bright_red(), flag);
+                eprintln!("{}", USAGE);
+                std::process::exit(1);
+            }
+        };
+    }
+    let mut args = args.into_iter();
+    let Some(source) = args.next() else {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    };
+    let observer = Rc::new(ConsoleObserver);
+    let proj = load_sb3(&source, observer.clone());
+    let ast = parse_sb3(proj, observer, unsupported);
+    let js = gen_js(&ast);
+    match args.next() {
+        Some(output) => {
+            if let Err(err) = std::fs::write(&output, js) {
+                eprintln!(
+                    "{}: unable to write `{}`: {}",
+                    "error".bright_red(),
+                    output,
+                    err
+                );
+                std::process::exit(1);
+            }
+        }
+        None => print!("{}", js),
+    }
 }
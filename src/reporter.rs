@@ -1,12 +1,13 @@
-use std::fmt::Display;
+use std::{fmt::Display, time::Duration};
 
 use colored::Colorize;
+use scratch_loader::observer::{Observer, Phase, Progress, Warning};
 
 pub fn report<T>(err: T) -> !
 where
     T: Display,
 {
-    println!("{}: {}", "error".bright_red(), err);
+    eprintln!("{}: {}", "error".bright_red(), err);
     std::process::exit(1);
 }
 
@@ -22,3 +23,30 @@ impl<T, E: Display> Reporter<T> for Result<T, E> {
         }
     }
 }
+
+// progress and warnings go to stderr, stdout may be the generated js
+pub struct ConsoleObserver;
+
+impl Observer for ConsoleObserver {
+    fn progress(&self, event: Progress) {
+        match event {
+            Progress::Extracting(name) => eprintln!("  {} `{}`", "Extracting".bright_green(), name),
+            Progress::Reading(name) => eprintln!("  {} `{}`", "Reading".bright_green(), name),
+            Progress::Skipping(name) => eprintln!("  {} `{}`", "Skipping".bright_yellow(), name),
+            Progress::Parsing(name) => eprintln!("  {} `{}`", "Parsing".bright_green(), name),
+        }
+    }
+
+    fn warning(&self, warning: &Warning) {
+        eprintln!("{}: {}", "warning".bright_yellow(), warning);
+    }
+
+    fn timing(&self, phase: Phase, elapsed: Duration) {
+        eprintln!(
+            "  {} {} in {:.2?}",
+            "Finished".bright_green(),
+            phase,
+            elapsed
+        );
+    }
+}